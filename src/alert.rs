@@ -0,0 +1,467 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::{Curve, LiquidityChange, LiquidityKind, LiquidityPool, TradeOp, TradeRecord};
+
+/// Direction in which a value must cross a threshold to trigger an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CrossDirection {
+    /// Triggers when the value rises from below the threshold to at or above it.
+    Above,
+    /// Triggers when the value falls from at or above the threshold to below it.
+    Below,
+}
+
+impl CrossDirection {
+    fn crossed(self, threshold: f64, before: f64, after: f64) -> bool {
+        match self {
+            CrossDirection::Above => before < threshold && after >= threshold,
+            CrossDirection::Below => before >= threshold && after < threshold,
+        }
+    }
+}
+
+/// Sent when a trade moves the spot price across a watched threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceAlert {
+    pub threshold: f64,
+    pub direction: CrossDirection,
+    pub price_before: f64,
+    pub price_after: f64,
+    /// The trade that caused the crossing.
+    pub trade: TradeRecord,
+}
+
+/// A reserve-level condition, evaluated against the reserves the pool held when the watch
+/// was registered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReserveCondition {
+    /// The native reserve is more than `percent`% below its reference level.
+    NativeReserveDrop { percent: f64 },
+    /// The token reserve is more than `percent`% below its reference level.
+    TokenReserveDrop { percent: f64 },
+    /// The native/token reserve ratio differs from its reference value by more than `percent`%.
+    RatioShift { percent: f64 },
+}
+
+impl ReserveCondition {
+    fn holds(self, reference: (u64, u64), reserves: (u64, u64)) -> bool {
+        let drop = |reference: u64, current: u64| {
+            (reference as f64 - current as f64) / reference as f64 * 100.0
+        };
+        match self {
+            ReserveCondition::NativeReserveDrop { percent } => {
+                drop(reference.0, reserves.0) > percent
+            }
+            ReserveCondition::TokenReserveDrop { percent } => {
+                drop(reference.1, reserves.1) > percent
+            }
+            ReserveCondition::RatioShift { percent } => {
+                let ratio = |(native, token): (u64, u64)| native as f64 / token as f64;
+                (ratio(reserves) / ratio(reference) - 1.0).abs() * 100.0 > percent
+            }
+        }
+    }
+}
+
+/// A mutation of the pool reserves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReserveChange {
+    Trade(TradeRecord),
+    Liquidity(LiquidityChange),
+}
+
+/// Sent when a trade or liquidity change makes a watched [`ReserveCondition`] start to hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReserveAlert {
+    pub condition: ReserveCondition,
+    /// `(native_reserve, token_reserve)` when the watch was registered.
+    pub reference: (u64, u64),
+    pub reserves_before: (u64, u64),
+    pub reserves_after: (u64, u64),
+    /// The change that made the condition hold.
+    pub cause: ReserveChange,
+}
+
+/// Criteria for flagging unusually large trades. A trade is flagged if it meets either one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhaleCriteria {
+    /// Flags trades whose native amount exceeds this percentile (0 to 100) of the sizes of
+    /// the previous `lookback` trades. Only applies once `lookback` trades have been seen.
+    pub percentile: Option<f64>,
+    pub lookback: usize,
+    /// Flags trades moving more than this percentage of the token reserve.
+    pub reserve_percent: Option<f64>,
+}
+
+/// Sent when a trade meets the watched [`WhaleCriteria`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhaleAlert {
+    /// The percentile size the trade exceeded, if that criterion was met.
+    pub size_threshold: Option<u64>,
+    /// Tokens traded as a percentage of the token reserve before the trade.
+    pub reserve_percent: f64,
+    pub trade: TradeRecord,
+}
+
+/// Applies [`WhaleCriteria`] to a stream of trades, keeping the recent sizes it needs.
+#[derive(Debug, Clone)]
+struct WhaleDetector {
+    criteria: WhaleCriteria,
+    recent: VecDeque<u64>,
+}
+
+impl WhaleDetector {
+    fn new(criteria: WhaleCriteria) -> Self {
+        Self {
+            criteria,
+            recent: VecDeque::with_capacity(criteria.lookback),
+        }
+    }
+
+    fn observe(&mut self, trade: TradeRecord, token_reserve_before: u64) -> Option<WhaleAlert> {
+        let lookback = self.criteria.lookback;
+        let size_threshold = self
+            .criteria
+            .percentile
+            .filter(|_| lookback > 0 && self.recent.len() == lookback)
+            .map(|percentile| {
+                let mut sizes: Vec<u64> = self.recent.iter().copied().collect();
+                sizes.sort_unstable();
+                // Nearest-rank percentile.
+                let rank = (percentile / 100.0 * lookback as f64).ceil() as usize;
+                sizes[rank.clamp(1, lookback) - 1]
+            })
+            .filter(|threshold| trade.native_amount > *threshold);
+        let reserve_percent = trade.op.token_amount() as f64 / token_reserve_before as f64 * 100.0;
+        let by_reserve = self
+            .criteria
+            .reserve_percent
+            .is_some_and(|limit| reserve_percent > limit);
+
+        if lookback > 0 {
+            if self.recent.len() == lookback {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(trade.native_amount);
+        }
+        (size_threshold.is_some() || by_reserve).then_some(WhaleAlert {
+            size_threshold,
+            reserve_percent,
+            trade,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PriceWatch {
+    threshold: f64,
+    direction: CrossDirection,
+    sender: Sender<PriceAlert>,
+}
+
+#[derive(Debug)]
+struct ReserveWatch {
+    condition: ReserveCondition,
+    reference: (u64, u64),
+    sender: Sender<ReserveAlert>,
+}
+
+#[derive(Debug)]
+struct WhaleWatch {
+    detector: WhaleDetector,
+    sender: Sender<WhaleAlert>,
+}
+
+/// Alert subscriptions registered on a pool.
+///
+/// Subscriptions belong to the pool they were registered on: clones of the pool, such as the
+/// scratch copies used for simulations, start without any.
+#[derive(Debug, Default)]
+pub(crate) struct Alerts {
+    price_watches: Vec<PriceWatch>,
+    reserve_watches: Vec<ReserveWatch>,
+    whale_watches: Vec<WhaleWatch>,
+}
+
+impl Clone for Alerts {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Market-relevant state captured before a mutation, to detect crossings afterwards.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AlertState {
+    price: f64,
+    reserves: (u64, u64),
+}
+
+impl Alerts {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.price_watches.is_empty()
+            && self.reserve_watches.is_empty()
+            && self.whale_watches.is_empty()
+    }
+
+    pub(crate) fn capture<C: Curve>(pool: &LiquidityPool<C>) -> AlertState {
+        AlertState {
+            price: pool.spot_price(),
+            reserves: reserves(pool),
+        }
+    }
+
+    /// Notifies every subscription crossed between `before` and the current state of `pool`,
+    /// dropping subscriptions whose receiver is gone.
+    pub(crate) fn notify<C: Curve>(
+        &mut self,
+        before: AlertState,
+        pool: &LiquidityPool<C>,
+        trade: TradeRecord,
+    ) {
+        let price_after = pool.spot_price();
+        self.price_watches.retain(|watch| {
+            if !watch
+                .direction
+                .crossed(watch.threshold, before.price, price_after)
+            {
+                return true;
+            }
+            let alert = PriceAlert {
+                threshold: watch.threshold,
+                direction: watch.direction,
+                price_before: before.price,
+                price_after,
+                trade,
+            };
+            watch.sender.send(alert).is_ok()
+        });
+
+        self.notify_reserves(before, pool, ReserveChange::Trade(trade));
+
+        self.whale_watches.retain_mut(|watch| {
+            match watch.detector.observe(trade, before.reserves.1) {
+                Some(alert) => watch.sender.send(alert).is_ok(),
+                None => true,
+            }
+        });
+    }
+
+    /// Notifies the reserve subscriptions whose condition started to hold with `cause`.
+    pub(crate) fn notify_reserves<C: Curve>(
+        &mut self,
+        before: AlertState,
+        pool: &LiquidityPool<C>,
+        cause: ReserveChange,
+    ) {
+        let reserves_after = reserves(pool);
+        self.reserve_watches.retain(|watch| {
+            let held = watch.condition.holds(watch.reference, before.reserves);
+            if held || !watch.condition.holds(watch.reference, reserves_after) {
+                return true;
+            }
+            let alert = ReserveAlert {
+                condition: watch.condition,
+                reference: watch.reference,
+                reserves_before: before.reserves,
+                reserves_after,
+                cause,
+            };
+            watch.sender.send(alert).is_ok()
+        });
+    }
+}
+
+fn reserves<C: Curve>(pool: &LiquidityPool<C>) -> (u64, u64) {
+    (pool.get_native_reserve(), pool.get_token_reserve())
+}
+
+impl LiquidityPool {
+    /// Registers a price alert, returning the channel on which it is delivered every time a
+    /// trade moves [`spot_price`](Self::spot_price) across `threshold` in `direction`.
+    ///
+    /// The subscription ends when the receiver is dropped.
+    pub fn watch_price(
+        &mut self,
+        threshold: f64,
+        direction: CrossDirection,
+    ) -> Receiver<PriceAlert> {
+        let (sender, receiver) = mpsc::channel();
+        self.alerts.price_watches.push(PriceWatch {
+            threshold,
+            direction,
+            sender,
+        });
+        receiver
+    }
+
+    /// Registers a reserve alert, returning the channel on which it is delivered every time a
+    /// trade or liquidity change makes `condition` start to hold. The condition is measured against the current
+    /// reserves.
+    ///
+    /// The subscription ends when the receiver is dropped.
+    pub fn watch_reserves(&mut self, condition: ReserveCondition) -> Receiver<ReserveAlert> {
+        let (sender, receiver) = mpsc::channel();
+        self.alerts.reserve_watches.push(ReserveWatch {
+            condition,
+            reference: reserves(self),
+            sender,
+        });
+        receiver
+    }
+
+    /// Registers a whale alert, returning the channel on which every later trade meeting
+    /// `criteria` is delivered.
+    ///
+    /// The subscription ends when the receiver is dropped.
+    pub fn watch_whales(&mut self, criteria: WhaleCriteria) -> Receiver<WhaleAlert> {
+        let (sender, receiver) = mpsc::channel();
+        self.alerts.whale_watches.push(WhaleWatch {
+            detector: WhaleDetector::new(criteria),
+            sender,
+        });
+        receiver
+    }
+
+    /// Applies `criteria` to the recorded history, returning the alerts a whale watch
+    /// registered when history was enabled would have delivered.
+    pub fn whale_trades(&self, criteria: WhaleCriteria) -> Vec<WhaleAlert> {
+        let Some(history) = &self.history else {
+            return Vec::new();
+        };
+        let mut detector = WhaleDetector::new(criteria);
+        let mut token_reserve = history.origin.token_reserve();
+        let mut changes = history.liquidity_changes.iter().peekable();
+        history
+            .records
+            .iter()
+            .filter_map(|record| {
+                while let Some(change) = changes.next_if(|change| change.seq < record.seq) {
+                    token_reserve = match change.kind {
+                        LiquidityKind::Add => token_reserve + change.token_amount,
+                        LiquidityKind::Remove => token_reserve - change.token_amount,
+                    };
+                }
+                let alert = detector.observe(*record, token_reserve);
+                token_reserve = match record.op {
+                    TradeOp::Buy { token_amount, .. } => token_reserve - token_amount,
+                    TradeOp::Sell { token_amount, .. } => token_reserve + token_amount,
+                };
+                alert
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TradeOp;
+
+    #[test]
+    fn test_price_crossings() {
+        let mut pool = LiquidityPool::default();
+        let threshold = pool.spot_price() * 1.3;
+        let above = pool.watch_price(threshold, CrossDirection::Above);
+        let below = pool.watch_price(threshold, CrossDirection::Below);
+        let token_amount = 50_000_000 * 10u64.pow(6);
+
+        pool.buy(token_amount, None).unwrap();
+        assert!(above.try_recv().is_err());
+        pool.buy(3 * token_amount, None).unwrap();
+        let alert = above.try_recv().unwrap();
+        assert!(alert.price_before < threshold && alert.price_after >= threshold);
+        assert_eq!(alert.price_after, pool.spot_price());
+        // The anchored market price moves less and has not crossed yet.
+        assert!(pool.market_price() < threshold);
+        assert_eq!(alert.trade.seq, 2);
+        assert!(matches!(alert.trade.op, TradeOp::Buy { .. }));
+
+        pool.sell(4 * token_amount, None).unwrap();
+        assert_eq!(below.try_recv().unwrap().trade.seq, 3);
+        assert!(above.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_reserve_conditions() {
+        let mut pool = LiquidityPool::default();
+        let native_drop =
+            pool.watch_reserves(ReserveCondition::NativeReserveDrop { percent: 10.0 });
+        let ratio_shift = pool.watch_reserves(ReserveCondition::RatioShift { percent: 50.0 });
+        let token_amount = 200_000_000 * 10u64.pow(6);
+
+        pool.sell(token_amount / 2, None).unwrap();
+        assert!(native_drop.try_recv().is_err());
+        pool.sell(token_amount / 2, None).unwrap();
+        let alert = native_drop.try_recv().unwrap();
+        assert!(matches!(alert.cause, ReserveChange::Trade(trade) if trade.seq == 2));
+        assert_eq!(
+            alert.reference.0,
+            LiquidityPool::default().get_native_reserve()
+        );
+        assert!(ratio_shift.try_recv().is_err());
+        pool.sell(2 * token_amount, None).unwrap();
+        assert!(native_drop.try_recv().is_err());
+        let alert = ratio_shift.try_recv().unwrap();
+        assert!(matches!(alert.cause, ReserveChange::Trade(trade) if trade.seq == 3));
+    }
+
+    #[test]
+    fn test_liquidity_removal_alert() {
+        let mut pool = LiquidityPool::default();
+        let native_drop =
+            pool.watch_reserves(ReserveCondition::NativeReserveDrop { percent: 40.0 });
+        pool.remove_liquidity(pool.lp_supply() / 4).unwrap();
+        assert!(native_drop.try_recv().is_err());
+        let removed = pool.remove_liquidity(pool.lp_supply() / 4).unwrap();
+        let alert = native_drop.try_recv().unwrap();
+        assert_eq!(alert.cause, ReserveChange::Liquidity(removed));
+        assert_eq!(
+            alert.reserves_after.0,
+            alert.reserves_before.0 - removed.native_amount
+        );
+    }
+
+    #[test]
+    fn test_whale_alerts() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let criteria = WhaleCriteria {
+            percentile: Some(90.0),
+            lookback: 3,
+            reserve_percent: Some(5.0),
+        };
+        let whales = pool.watch_whales(criteria);
+        let token_amount = 10u64.pow(12);
+        for _ in 0..3 {
+            pool.buy(token_amount, None).unwrap();
+        }
+        assert!(whales.try_recv().is_err());
+        pool.buy(2 * token_amount, None).unwrap();
+        let by_size = whales.try_recv().unwrap();
+        assert_eq!(by_size.trade.seq, 4);
+        assert!(by_size.size_threshold.is_some());
+        assert!(by_size.reserve_percent < 5.0);
+
+        pool.sell(100 * token_amount, None).unwrap();
+        let by_reserve = whales.try_recv().unwrap();
+        assert!(by_reserve.reserve_percent > 5.0);
+        assert_eq!(pool.whale_trades(criteria), [by_size, by_reserve]);
+    }
+
+    #[test]
+    fn test_clones_and_dropped_receivers() {
+        let mut pool = LiquidityPool::default();
+        let receiver = pool.watch_price(0.0, CrossDirection::Below);
+        let _ = pool.watch_price(pool.spot_price() * 1.01, CrossDirection::Above);
+        let mut clone = pool.clone();
+        clone.buy(100_000_000 * 10u64.pow(6), None).unwrap();
+        assert!(clone.alerts.is_empty());
+        pool.buy(100_000_000 * 10u64.pow(6), None).unwrap();
+        assert_eq!(pool.alerts.price_watches.len(), 1);
+        drop(receiver);
+    }
+}
@@ -0,0 +1,179 @@
+use core::fmt;
+use core::iter::Sum;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crate::{Curve, LiquidityPool, Result};
+
+macro_rules! amount {
+    ($(#[$doc:meta])* $name:ident, $unit:literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(transparent)
+        )]
+        #[cfg_attr(
+            feature = "borsh",
+            derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+        )]
+        pub struct $name(pub u64);
+
+        impl $name {
+            pub const ZERO: Self = Self(0);
+
+            pub fn get(self) -> u64 {
+                self.0
+            }
+
+            pub fn checked_add(self, rhs: Self) -> Option<Self> {
+                self.0.checked_add(rhs.0).map(Self)
+            }
+
+            pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+                self.0.checked_sub(rhs.0).map(Self)
+            }
+
+            pub fn saturating_sub(self, rhs: Self) -> Self {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+        }
+
+        impl From<u64> for $name {
+            fn from(amount: u64) -> Self {
+                Self(amount)
+            }
+        }
+
+        impl From<$name> for u64 {
+            fn from(amount: $name) -> Self {
+                amount.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!("{} ", $unit), self.0)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::ZERO, Add::add)
+            }
+        }
+    };
+}
+
+amount!(
+    /// An amount of native currency, in its smallest unit.
+    Native,
+    "native"
+);
+
+amount!(
+    /// An amount of tokens, in their smallest unit.
+    Token,
+    "tokens"
+);
+
+/// Typed counterparts of the trading API, taking and returning [`Native`] and [`Token`] so that
+/// swapping the two sides of a trade fails to compile.
+impl<C: Curve> LiquidityPool<C> {
+    pub fn native_reserve_typed(&self) -> Native {
+        Native(self.native_reserve)
+    }
+
+    pub fn token_reserve_typed(&self) -> Token {
+        Token(self.token_reserve)
+    }
+
+    /// Typed [`buy`](Self::buy).
+    pub fn buy_typed(&mut self, tokens: Token, max_native: Option<Native>) -> Result<Native> {
+        self.buy(tokens.0, max_native.map(Native::get)).map(Native)
+    }
+
+    /// Typed [`sell`](Self::sell).
+    pub fn sell_typed(&mut self, tokens: Token, min_native: Option<Native>) -> Result<Native> {
+        self.sell(tokens.0, min_native.map(Native::get)).map(Native)
+    }
+
+    /// Typed [`simulate_buy`](Self::simulate_buy), without a slippage limit.
+    pub fn simulate_buy_typed(&self, tokens: Token) -> Result<Native> {
+        self.simulate_buy(tokens.0, None).map(Native)
+    }
+
+    /// Typed [`simulate_sell`](Self::simulate_sell), without a slippage limit.
+    pub fn simulate_sell_typed(&self, tokens: Token) -> Result<Native> {
+        self.simulate_sell(tokens.0, None).map(Native)
+    }
+}
+
+impl LiquidityPool {
+    /// Typed [`calculate_tokens_received`](Self::calculate_tokens_received).
+    pub fn tokens_received_typed(&self, native: Native) -> Result<Token> {
+        self.calculate_tokens_received(native.0).map(Token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PoolError;
+
+    #[test]
+    fn test_typed_amounts() {
+        let mut pool = LiquidityPool::default();
+        let tokens = Token(10u64.pow(6));
+        let cost = pool.simulate_buy_typed(tokens).unwrap();
+        assert_eq!(pool.buy_typed(tokens, Some(cost)), Ok(cost));
+        let received = pool.sell_typed(tokens, None).unwrap();
+        assert!(received <= cost);
+        assert_eq!(cost - received + received, cost);
+        assert!(matches!(
+            pool.sell_typed(tokens, Some(cost + Native(1))),
+            Err(PoolError::SlippageExceeded { limit, .. }) if limit == cost.get() + 1
+        ));
+        assert_eq!(
+            u64::from(pool.native_reserve_typed()),
+            pool.get_native_reserve()
+        );
+        assert_eq!([Token(1), Token(2)].into_iter().sum::<Token>(), Token(3));
+        assert_eq!(Native(5).checked_sub(Native(6)), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_display() {
+        assert_eq!(Native(5).to_string(), "5 native");
+        assert_eq!(Token(7).to_string(), "7 tokens");
+    }
+}
@@ -0,0 +1,191 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{LiquidityPool, PoolError, Result, MAX_FEE_BPS};
+
+const DAYS_PER_YEAR: f64 = 365.0;
+const SECONDS_PER_YEAR: f64 = DAYS_PER_YEAR * 86_400.0;
+
+/// Fee APR right after a recorded trade, as returned by
+/// [`LiquidityPool::rolling_fee_apr`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeAprPoint {
+    /// Value of [`LiquidityPool::trade_count`] once the trade was applied.
+    pub seq: u64,
+    pub timestamp: u64,
+    /// LPs' fees charged over the window ending at the trade, trade included.
+    pub window_fees: u128,
+    /// Value of the liquidity right after the trade, as
+    /// [`position_value_in_native`](LiquidityPool::position_value_in_native).
+    pub liquidity: u64,
+    /// `window_fees` annualized over `liquidity`, as a simple rate.
+    pub apr: f64,
+}
+
+impl LiquidityPool {
+    /// Projects the yearly return of providing liquidity, as a fraction of the position value,
+    /// if `daily_volume_native` trades a day paid a fee of `fee_bps`. Only the LPs' part of the
+    /// fee counts, see [`with_protocol_fee_share`](Self::with_protocol_fee_share), and the
+    /// liquidity is valued as [`position_value_in_native`](Self::position_value_in_native).
+    /// Returns a simple rate, without compounding. Fails with [`PoolError::InvalidAmount`]
+    /// unless the fee is below 100%.
+    pub fn estimate_fee_apr(&self, daily_volume_native: u128, fee_bps: u16) -> Result<f64> {
+        if fee_bps >= MAX_FEE_BPS {
+            return Err(PoolError::InvalidAmount);
+        }
+        let daily_fees = daily_volume_native as f64 * fee_bps as f64 / MAX_FEE_BPS as f64;
+        self.apr_of_daily_fees(daily_fees)
+    }
+
+    /// Projects the yearly return of providing liquidity from the fees recorded in
+    /// [`stats`](Self::stats), assuming they were charged over `elapsed_days` and keep
+    /// accruing at the same pace. Fails with [`PoolError::InvalidAmount`] unless
+    /// `elapsed_days` is positive and finite.
+    pub fn fee_apr_from_stats(&self, elapsed_days: f64) -> Result<f64> {
+        if !(elapsed_days > 0.0 && elapsed_days.is_finite()) {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.apr_of_daily_fees(self.stats.fees as f64 / elapsed_days)
+    }
+
+    /// Returns the fee APR after every recorded trade made with a timestamp, from the LPs' part
+    /// of the fees charged over the `window` seconds ending at it, for charting the yield of a
+    /// run over time. Timestamps are taken from the oracle, see
+    /// [`enable_oracle`](Self::enable_oracle); trades recorded without one are left out.
+    /// Fails with [`PoolError::InvalidAmount`] for an empty window,
+    /// [`PoolError::HistoryUnavailable`] if history is disabled, and like
+    /// [`position_value_in_native`](Self::position_value_in_native) on a pool along the way.
+    pub fn rolling_fee_apr(&self, window: u64) -> Result<Vec<FeeAprPoint>> {
+        if window == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let history = self.history.as_ref().ok_or(PoolError::HistoryUnavailable)?;
+        // Timestamps and LPs' fees of the trades within the window, oldest first.
+        let mut fees: VecDeque<(u64, u64)> = VecDeque::new();
+        let mut window_fees = 0u128;
+        let mut points = Vec::new();
+        let mut failure = None;
+        history.replay(history.origin, self.trade_count, |record, pool| {
+            let Some(timestamp) = record.timestamp else {
+                return;
+            };
+            let lp_fee = record.fee - record.protocol_fee;
+            fees.push_back((timestamp, lp_fee));
+            window_fees += lp_fee as u128;
+            while let Some(&(start, fee)) = fees.front() {
+                if start.saturating_add(window) > timestamp {
+                    break;
+                }
+                fees.pop_front();
+                window_fees -= fee as u128;
+            }
+            match pool.position_value_in_native(pool.lp_supply) {
+                Ok(liquidity) => points.push(FeeAprPoint {
+                    seq: record.seq,
+                    timestamp,
+                    window_fees,
+                    liquidity,
+                    apr: window_fees as f64 * SECONDS_PER_YEAR / window as f64 / liquidity as f64,
+                }),
+                Err(error) => {
+                    failure.get_or_insert(error);
+                }
+            }
+        })?;
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(points),
+        }
+    }
+
+    /// Yearly LP return if `daily_fees` of native currency were charged every day.
+    fn apr_of_daily_fees(&self, daily_fees: f64) -> Result<f64> {
+        let liquidity = self.position_value_in_native(self.lp_supply)?;
+        let lp_share = 1.0 - self.protocol_fee_share_bps as f64 / MAX_FEE_BPS as f64;
+        Ok(daily_fees * lp_share * DAYS_PER_YEAR / liquidity as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_fee_apr() {
+        let pool = LiquidityPool::default();
+        // The whole liquidity turning over once a day at 0.3%.
+        let liquidity = 2 * pool.get_native_reserve() as u128;
+        let apr = pool.estimate_fee_apr(liquidity, 30).unwrap();
+        assert!((apr - 0.003 * 365.0).abs() < 1e-9);
+        assert_eq!(
+            pool.estimate_fee_apr(liquidity, MAX_FEE_BPS),
+            Err(PoolError::InvalidAmount)
+        );
+
+        let shared = pool.clone().with_protocol_fee_share(2_500).unwrap();
+        let shared_apr = shared.estimate_fee_apr(liquidity, 30).unwrap();
+        assert!((shared_apr - 0.75 * apr).abs() < 1e-9);
+        let protocol_only = pool.with_protocol_fee_share(MAX_FEE_BPS).unwrap();
+        assert_eq!(protocol_only.estimate_fee_apr(liquidity, 30), Ok(0.0));
+    }
+
+    #[test]
+    fn test_fee_apr_from_stats() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        assert_eq!(pool.fee_apr_from_stats(1.0), Ok(0.0));
+        pool.buy(10u64.pow(12), None).unwrap();
+        pool.sell(10u64.pow(12), None).unwrap();
+        let daily = pool.fee_apr_from_stats(1.0).unwrap();
+        let expected = pool.stats().fees as f64 * 365.0
+            / pool.position_value_in_native(pool.lp_supply()).unwrap() as f64;
+        assert!((daily - expected).abs() < 1e-12);
+        assert!((pool.fee_apr_from_stats(7.0).unwrap() - daily / 7.0).abs() < 1e-12);
+        assert_eq!(pool.fee_apr_from_stats(0.0), Err(PoolError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_rolling_fee_apr() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        pool.enable_history();
+        let token_amount = 10u64.pow(12);
+        // Made before the oracle, so without a timestamp.
+        pool.buy(token_amount, None).unwrap();
+        pool.enable_oracle(0, 8);
+        pool.buy_at(0, token_amount, None).unwrap();
+        pool.sell_at(1_800, token_amount, None).unwrap();
+        pool.buy_at(3_600, token_amount, None).unwrap();
+        let history = pool.history().to_vec();
+        assert_eq!(history[0].timestamp, None);
+        assert_eq!(history[3].timestamp, Some(3_600));
+
+        let points = pool.rolling_fee_apr(3_600).unwrap();
+        assert_eq!(
+            points.iter().map(|point| point.seq).collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+        assert_eq!(
+            points[1].window_fees,
+            (history[1].fee + history[2].fee) as u128
+        );
+        // The trade at 0 left the window ending at 3,600.
+        assert_eq!(
+            points[2].window_fees,
+            (history[2].fee + history[3].fee) as u128
+        );
+        let last = points[2];
+        assert_eq!(
+            last.liquidity,
+            pool.position_value_in_native(pool.lp_supply()).unwrap()
+        );
+        let expected = last.window_fees as f64 * 24.0 * 365.0 / last.liquidity as f64;
+        assert!((last.apr - expected).abs() < 1e-12);
+
+        assert_eq!(pool.rolling_fee_apr(0), Err(PoolError::InvalidAmount));
+        pool.disable_history();
+        assert_eq!(
+            pool.rolling_fee_apr(3_600),
+            Err(PoolError::HistoryUnavailable)
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Arbitrage between two constant product pools trading the same pair.
+//!
+//! Buying `t` tokens from a pool with reserves `(x_a, y_a)` and selling them into one with
+//! reserves `(x_b, y_b)` returns
+//! `(1 - f_b) * x_b * t / (y_b + t) - (1 + f_a) * x_a * t / (y_a - t)`, where the fee `f_a` is
+//! added to the native paid and `f_b` withheld from the native received. The profit peaks
+//! where `(y_b + t) / (y_a - t) = sqrt((1 - f_b) * x_b * y_b / ((1 + f_a) * x_a * y_a))`, which
+//! is solved in closed form and then adjusted against exact quotes.
+
+use crate::{LiquidityPool, MAX_FEE_BPS};
+
+/// Units around the closed-form optimum checked against exact quotes, absorbing rounding.
+const SEARCH_RADIUS: u64 = 2;
+
+/// One of the two pools passed to [`optimal_trade`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Venue {
+    A,
+    B,
+}
+
+/// Trade capturing the price difference between two pools: buy `token_amount` tokens on
+/// `buy_on`, then sell them on the other pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArbitragePlan {
+    pub buy_on: Venue,
+    pub token_amount: u64,
+    /// Native paid for the tokens, fee included.
+    pub native_in: u64,
+    /// Native received for the tokens, net of the fee.
+    pub native_out: u64,
+    /// `native_out - native_in`.
+    pub profit: u64,
+}
+
+/// Returns the most profitable trade buying tokens on one pool and selling them on the other,
+/// or `None` if no trade is profitable after fees.
+pub fn optimal_trade(a: &LiquidityPool, b: &LiquidityPool) -> Option<ArbitragePlan> {
+    if a.spot_price() < b.spot_price() {
+        plan(a, b, Venue::A)
+    } else {
+        plan(b, a, Venue::B)
+    }
+}
+
+fn plan(buy: &LiquidityPool, sell: &LiquidityPool, buy_on: Venue) -> Option<ArbitragePlan> {
+    let fee = |pool: &LiquidityPool| pool.fee_bps() as f64 / MAX_FEE_BPS as f64;
+    let (buy_native, buy_token) = (
+        buy.get_native_reserve() as f64,
+        buy.get_token_reserve() as f64,
+    );
+    let (sell_native, sell_token) = (
+        sell.get_native_reserve() as f64,
+        sell.get_token_reserve() as f64,
+    );
+    let ratio = ((1.0 - fee(sell)) * sell_native * sell_token
+        / ((1.0 + fee(buy)) * buy_native * buy_token))
+        .sqrt();
+    let optimum = (ratio * buy_token - sell_token) / (1.0 + ratio);
+    if optimum.is_nan() || optimum < 1.0 {
+        return None;
+    }
+    let optimum = (optimum as u64).min(buy.real_token_reserve());
+    (optimum.saturating_sub(SEARCH_RADIUS).max(1)..=optimum.saturating_add(SEARCH_RADIUS))
+        .filter_map(|token_amount| {
+            let native_in = buy.quote_buy(token_amount).ok()?.amount_in;
+            let native_out = sell.quote_sell(token_amount).ok()?.amount_out;
+            Some(ArbitragePlan {
+                buy_on,
+                token_amount,
+                native_in,
+                native_out,
+                profit: native_out
+                    .checked_sub(native_in)
+                    .filter(|&profit| profit > 0)?,
+            })
+        })
+        .max_by_key(|plan| plan.profit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimal_trade() {
+        let a = LiquidityPool::new(10u64.pow(12), 10u64.pow(12))
+            .unwrap()
+            .with_fee(30)
+            .unwrap();
+        let b = LiquidityPool::new(12 * 10u64.pow(11), 10u64.pow(12))
+            .unwrap()
+            .with_fee(25)
+            .unwrap();
+        let plan = optimal_trade(&b, &a).unwrap();
+        assert_eq!(plan.buy_on, Venue::B);
+        assert_eq!(optimal_trade(&a, &b).unwrap().buy_on, Venue::A);
+
+        let profit = |token_amount| {
+            let native_in = a.quote_buy(token_amount).unwrap().amount_in;
+            let native_out = b.quote_sell(token_amount).unwrap().amount_out;
+            native_out as i128 - native_in as i128
+        };
+        assert_eq!(profit(plan.token_amount), plan.profit as i128);
+        for offset in [1, 1_000, 10u64.pow(9)] {
+            assert!(profit(plan.token_amount - offset) <= plan.profit as i128);
+            assert!(profit(plan.token_amount + offset) <= plan.profit as i128);
+        }
+    }
+
+    #[test]
+    fn test_no_arbitrage_within_fees() {
+        let a = LiquidityPool::new(10u64.pow(12), 10u64.pow(12))
+            .unwrap()
+            .with_fee(30)
+            .unwrap();
+        let b = LiquidityPool::new(10u64.pow(12) + 10u64.pow(9), 10u64.pow(12))
+            .unwrap()
+            .with_fee(30)
+            .unwrap();
+        assert_eq!(optimal_trade(&a, &b), None);
+        assert_eq!(optimal_trade(&a, &a.clone()), None);
+    }
+}
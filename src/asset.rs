@@ -0,0 +1,178 @@
+use core::fmt;
+
+use crate::{
+    Amount, Direction, LiquidityPool, PoolError, Result, SlippageLimit, SwapParams, SwapResult,
+};
+
+/// Identifies an asset held by an [`AssetPool`], such as a mint address or a ticker.
+pub trait AssetId: Clone + Eq + fmt::Debug {}
+
+impl<T: Clone + Eq + fmt::Debug> AssetId for T {}
+
+/// Outcome of an [`AssetPool`] swap.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssetSwap<A> {
+    pub asset_in: A,
+    pub amount_in: u64,
+    pub asset_out: A,
+    pub amount_out: u64,
+    /// Swap fee, in units of the first asset of the pool.
+    pub fee: u64,
+}
+
+/// A two-asset pool trading arbitrary assets against each other.
+///
+/// The first asset takes the native side of the underlying [`LiquidityPool`] and the second
+/// the token side, so fees are charged in the first asset and every native/token method stays
+/// available through [`pool`](Self::pool).
+#[derive(Debug, Clone)]
+pub struct AssetPool<A: AssetId> {
+    pool: LiquidityPool,
+    assets: (A, A),
+}
+
+impl<A: AssetId> AssetPool<A> {
+    /// Creates a pool holding `reserve_a` of `asset_a` and `reserve_b` of `asset_b`. Fails with
+    /// [`PoolError::InvalidAmount`] if both assets are the same or a reserve is zero.
+    pub fn new(asset_a: A, reserve_a: u64, asset_b: A, reserve_b: u64) -> Result<Self> {
+        if asset_a == asset_b {
+            return Err(PoolError::InvalidAmount);
+        }
+        Ok(Self {
+            pool: LiquidityPool::new(reserve_a, reserve_b)?,
+            assets: (asset_a, asset_b),
+        })
+    }
+
+    /// Charges a swap fee of `fee_bps` basis points, see [`LiquidityPool::with_fee`].
+    pub fn with_fee(mut self, fee_bps: u16) -> Result<Self> {
+        self.pool = self.pool.with_fee(fee_bps)?;
+        Ok(self)
+    }
+
+    pub fn assets(&self) -> (&A, &A) {
+        (&self.assets.0, &self.assets.1)
+    }
+
+    pub fn pool(&self) -> &LiquidityPool {
+        &self.pool
+    }
+
+    pub fn pool_mut(&mut self) -> &mut LiquidityPool {
+        &mut self.pool
+    }
+
+    /// Returns the direction of a trade paying `asset_in` into the pool, and the asset it
+    /// pays out.
+    fn side(&self, asset_in: &A) -> Result<(Direction, &A)> {
+        if *asset_in == self.assets.0 {
+            Ok((Direction::BuyToken, &self.assets.1))
+        } else if *asset_in == self.assets.1 {
+            Ok((Direction::SellToken, &self.assets.0))
+        } else {
+            Err(PoolError::UnknownAsset)
+        }
+    }
+
+    /// Returns the reserve of `asset`.
+    pub fn reserve(&self, asset: &A) -> Result<u64> {
+        match self.side(asset)?.0 {
+            Direction::BuyToken => Ok(self.pool.get_native_reserve()),
+            Direction::SellToken => Ok(self.pool.get_token_reserve()),
+        }
+    }
+
+    /// Returns the marginal price of one unit of `asset` in units of the other asset.
+    pub fn spot_price(&self, asset: &A) -> Result<f64> {
+        match self.side(asset)?.0 {
+            Direction::BuyToken => Ok(self.pool.price_inverse()),
+            Direction::SellToken => Ok(self.pool.spot_price()),
+        }
+    }
+
+    /// Quotes paying exactly `amount_in` of `asset_in` into the pool.
+    pub fn quote_swap(&self, asset_in: &A, amount_in: u64) -> Result<AssetSwap<A>> {
+        let (direction, asset_out) = self.side(asset_in)?;
+        let result = self
+            .pool
+            .quote_swap(&Self::params(direction, amount_in, None))?;
+        Ok(Self::outcome(asset_in, asset_out, &result))
+    }
+
+    /// Pays exactly `amount_in` of `asset_in` into the pool for the other asset, receiving at
+    /// least `min_out` if set. Paying in the first asset may leave a remainder too small to
+    /// buy a whole unit of the second, which is not spent.
+    pub fn swap(
+        &mut self,
+        asset_in: &A,
+        amount_in: u64,
+        min_out: Option<u64>,
+    ) -> Result<AssetSwap<A>> {
+        let (direction, asset_out) = self.side(asset_in)?;
+        let asset_out = asset_out.clone();
+        let result = self
+            .pool
+            .swap(&Self::params(direction, amount_in, min_out))?;
+        Ok(Self::outcome(asset_in, &asset_out, &result))
+    }
+
+    fn params(direction: Direction, amount_in: u64, min_out: Option<u64>) -> SwapParams {
+        SwapParams {
+            direction,
+            amount: Amount::ExactIn(amount_in),
+            limit: min_out.map_or(SlippageLimit::None, SlippageLimit::MinOut),
+        }
+    }
+
+    fn outcome(asset_in: &A, asset_out: &A, result: &SwapResult) -> AssetSwap<A> {
+        AssetSwap {
+            asset_in: asset_in.clone(),
+            amount_in: result.amount_in,
+            asset_out: asset_out.clone(),
+            amount_out: result.amount_out,
+            fee: result.fee,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_pool_swaps() {
+        let mut pool = AssetPool::new("USDC", 2_000 * 10u64.pow(9), "ETH", 10u64.pow(9))
+            .unwrap()
+            .with_fee(30)
+            .unwrap();
+        assert_eq!(pool.spot_price(&"ETH"), Ok(2_000.0));
+        assert_eq!(pool.spot_price(&"USDC"), Ok(1.0 / 2_000.0));
+
+        let quote = pool.quote_swap(&"ETH", 10u64.pow(6)).unwrap();
+        let sold = pool.swap(&"ETH", 10u64.pow(6), None).unwrap();
+        assert_eq!(sold, quote);
+        assert_eq!((sold.asset_in, sold.asset_out), ("ETH", "USDC"));
+        assert!(sold.amount_out < 2_000 * 10u64.pow(6));
+
+        let bought = pool.swap(&"USDC", sold.amount_out, None).unwrap();
+        assert_eq!(bought.asset_out, "ETH");
+        assert!(bought.amount_in <= sold.amount_out && bought.amount_out < 10u64.pow(6));
+        assert_eq!(pool.reserve(&"USDC"), Ok(pool.pool().get_native_reserve()));
+        assert_eq!(
+            pool.swap(&"SOL", 1, None).map(|_| ()),
+            Err(PoolError::UnknownAsset)
+        );
+        assert!(matches!(
+            pool.swap(&"ETH", 10u64.pow(6), Some(u64::MAX)),
+            Err(PoolError::SlippageExceeded {
+                limit: u64::MAX,
+                ..
+            })
+        ));
+        assert_eq!(
+            AssetPool::new("ETH", 1, "ETH", 1).map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, RwLock};
+
+use crate::{AsyncPoolSource, Direction, LiquidityPool, Result, TradeEvent, TradeOp};
+
+/// Events a lagging subscriber can fall behind by before missing some.
+const EVENT_CAPACITY: usize = 1_024;
+
+/// A pool shared between tasks, exposing quoting and execution as async operations.
+///
+/// Cloning an `AsyncPool` is cheap and every clone refers to the same pool and event stream.
+#[derive(Debug, Clone)]
+pub struct AsyncPool {
+    inner: Arc<RwLock<LiquidityPool>>,
+    events: broadcast::Sender<TradeEvent>,
+}
+
+impl AsyncPool {
+    pub fn new(pool: LiquidityPool) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(pool)),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Quotes `op` against the current state without executing it.
+    pub async fn quote(&self, op: TradeOp) -> Result<u64> {
+        self.inner.read().await.quote(&op)
+    }
+
+    /// Executes `op`, waiting for exclusive access to the pool, and publishes it to the
+    /// streams returned by [`subscribe`](Self::subscribe).
+    pub async fn execute(&self, op: TradeOp) -> Result<u64> {
+        let mut pool = self.inner.write().await;
+        let native_amount = pool.execute(&op)?;
+        // Sent under the lock so subscribers see trades in the order they were applied; having
+        // no subscriber is not an error.
+        let _ = self.events.send(TradeEvent {
+            seq: pool.trade_count(),
+            direction: match op {
+                TradeOp::Buy { .. } => Direction::BuyToken,
+                TradeOp::Sell { .. } => Direction::SellToken,
+            },
+            token_amount: op.token_amount(),
+            native_amount,
+            reserves_after: (pool.get_native_reserve(), pool.get_token_reserve()),
+        });
+        Ok(native_amount)
+    }
+
+    /// Returns a stream of the trades executed through any clone of this `AsyncPool` from now
+    /// on. A subscriber more than 1,024 events behind misses the oldest and is told how many
+    /// by [`broadcast::error::RecvError::Lagged`].
+    pub fn subscribe(&self) -> broadcast::Receiver<TradeEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns a copy of the current pool state.
+    pub async fn snapshot(&self) -> LiquidityPool {
+        self.inner.read().await.clone()
+    }
+
+    /// Replaces the shared state with `pool`, returning the previous state.
+    pub async fn replace(&self, pool: LiquidityPool) -> LiquidityPool {
+        std::mem::replace(&mut *self.inner.write().await, pool)
+    }
+
+    /// Reloads the shared state from `source`, without holding the pool while fetching. The
+    /// pool is left untouched if the fetch fails.
+    pub async fn refresh(&self, source: &impl AsyncPoolSource) -> Result<()> {
+        let pool = source.fetch_pool().await?;
+        self.replace(pool).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_quote_then_execute() {
+        let pool = AsyncPool::new(LiquidityPool::default());
+        let op = TradeOp::Buy {
+            token_amount: 1_000_000 * 10u64.pow(6),
+            max_native: None,
+        };
+        let quoted = pool.quote(op).await.unwrap();
+        let handle = {
+            let pool = pool.clone();
+            tokio::spawn(async move { pool.execute(op).await })
+        };
+        assert_eq!(handle.await.unwrap().unwrap(), quoted);
+        assert_eq!(
+            pool.snapshot().await.get_native_reserve(),
+            LiquidityPool::default().get_native_reserve() + quoted
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let pool = AsyncPool::new(LiquidityPool::default());
+        let mut events = pool.subscribe();
+        let token_amount = 10u64.pow(12);
+        let paid = pool
+            .execute(TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            })
+            .await
+            .unwrap();
+        let rejected = TradeOp::Sell {
+            token_amount,
+            min_native: Some(u64::MAX),
+        };
+        assert!(pool.execute(rejected).await.is_err());
+        pool.clone()
+            .execute(TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            })
+            .await
+            .unwrap();
+
+        let bought = events.recv().await.unwrap();
+        assert_eq!((bought.seq, bought.direction), (1, Direction::BuyToken));
+        assert_eq!(bought.native_amount, paid);
+        let sold = events.recv().await.unwrap();
+        assert_eq!((sold.seq, sold.direction), (2, Direction::SellToken));
+        let state = pool.snapshot().await;
+        assert_eq!(
+            sold.reserves_after,
+            (state.get_native_reserve(), state.get_token_reserve())
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_from_source() {
+        let pool = AsyncPool::new(LiquidityPool::default());
+        let source = || async { LiquidityPool::new(10, 20) };
+        pool.refresh(&source).await.unwrap();
+        assert_eq!(pool.snapshot().await.get_token_reserve(), 20);
+
+        let failing = || async { LiquidityPool::new(0, 20) };
+        assert!(pool.refresh(&failing).await.is_err());
+        assert_eq!(pool.snapshot().await.get_token_reserve(), 20);
+    }
+}
@@ -0,0 +1,306 @@
+//! Backtesting of trading strategies against historical swap logs.
+//!
+//! The swaps of a log are replayed in order through a copy of the pool, and a [`Strategy`] may
+//! trade through a [`Trader`] before each of them. A second copy replays the log alone as the
+//! baseline, so the report shows both what the strategy earned and how much its own impact
+//! cost it and the historical traders.
+//!
+//! Logs are read from CSV with a header line, or deserialized as [`HistoricalSwap`]s with the
+//! `serde` feature:
+//!
+//! ```text
+//! timestamp,direction,token_amount
+//! 1700000000,buy,1000000000000
+//! 1700000012,sell,500000000000
+//! ```
+
+use std::path::Path;
+
+use crate::{Direction, LiquidityPool, PoolError, TradeOp, Trader};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BacktestError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A swap from a historical log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoricalSwap {
+    pub timestamp: u64,
+    pub direction: Direction,
+    pub token_amount: u64,
+}
+
+impl HistoricalSwap {
+    /// The swap as a trade without slippage limit.
+    pub fn op(&self) -> TradeOp {
+        match self.direction {
+            Direction::BuyToken => TradeOp::Buy {
+                token_amount: self.token_amount,
+                max_native: None,
+            },
+            Direction::SellToken => TradeOp::Sell {
+                token_amount: self.token_amount,
+                min_native: None,
+            },
+        }
+    }
+}
+
+/// Decides the trades a backtested strategy makes.
+pub trait Strategy {
+    /// Returns the trades to make before `swap` is replayed, given the pool and the trader's
+    /// balances at that point. They are executed in order, the rejected ones skipped.
+    fn before_swap(
+        &mut self,
+        swap: &HistoricalSwap,
+        pool: &LiquidityPool,
+        trader: &Trader,
+    ) -> Vec<TradeOp>;
+}
+
+/// A strategy trade that was executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    /// Timestamp of the historical swap it preceded.
+    pub timestamp: u64,
+    pub op: TradeOp,
+    /// Native currency spent (buy) or received (sell).
+    pub native_amount: u64,
+    /// Native currency the same trade would have spent or received on the baseline pool, or
+    /// `None` if the baseline pool could not fill it.
+    pub baseline_native: Option<u64>,
+}
+
+impl Fill {
+    /// Native currency the trade lost to the strategy's earlier impact: paid beyond the
+    /// baseline on buys, received short of it on sells. Zero without a baseline.
+    pub fn slippage(&self) -> i128 {
+        self.baseline_native.map_or(0, |baseline| {
+            shortfall(&self.op, self.native_amount, baseline)
+        })
+    }
+}
+
+/// Native currency `op` paid beyond `baseline` if a buy, or received short of it if a sell.
+fn shortfall(op: &TradeOp, native_amount: u64, baseline: u64) -> i128 {
+    let (native_amount, baseline) = (native_amount as i128, baseline as i128);
+    match op {
+        TradeOp::Buy { .. } => native_amount - baseline,
+        TradeOp::Sell { .. } => baseline - native_amount,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    /// The trader once the whole log was replayed.
+    pub trader: Trader,
+    pub fills: Vec<Fill>,
+    /// Strategy trades that failed, with the timestamp of the swap they preceded.
+    pub rejected: Vec<(u64, TradeOp, PoolError)>,
+    /// Historical swaps the pool rejected, with the strategy's trades interleaved.
+    pub historical_rejected: usize,
+    /// Native currency the historical traders lost to the strategy's impact, paid beyond the
+    /// baseline on buys and received short of it on sells, over the swaps both pools filled.
+    pub historical_slippage: i128,
+    pub final_price: f64,
+    /// Spot price of the baseline pool once the whole log was replayed.
+    pub baseline_final_price: f64,
+}
+
+impl BacktestReport {
+    pub fn realized_pnl(&self) -> i128 {
+        self.trader.realized_pnl()
+    }
+
+    /// Value of the tokens the trader still holds at the final spot price, beyond their cost.
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.trader.token_balance() as f64 * self.final_price - self.trader.cost_basis() as f64
+    }
+
+    /// Native currency the strategy lost to its own impact across all fills.
+    pub fn slippage(&self) -> i128 {
+        self.fills.iter().map(Fill::slippage).sum()
+    }
+}
+
+/// Replays `swaps` through a copy of `pool`, letting `strategy` trade through `trader` before
+/// each of them.
+pub fn run<S: Strategy>(
+    pool: &LiquidityPool,
+    swaps: &[HistoricalSwap],
+    mut trader: Trader,
+    strategy: &mut S,
+) -> BacktestReport {
+    let mut pool = pool.clone();
+    let mut baseline = pool.clone();
+    let mut fills = Vec::new();
+    let mut rejected = Vec::new();
+    let mut historical_rejected = 0;
+    let mut historical_slippage = 0;
+    for swap in swaps {
+        for op in strategy.before_swap(swap, &pool, &trader) {
+            let baseline_native = baseline.quote(&op).ok();
+            let result = match op {
+                TradeOp::Buy {
+                    token_amount,
+                    max_native,
+                } => trader.buy(&mut pool, token_amount, max_native),
+                TradeOp::Sell {
+                    token_amount,
+                    min_native,
+                } => trader.sell(&mut pool, token_amount, min_native),
+            };
+            match result {
+                Ok(native_amount) => fills.push(Fill {
+                    timestamp: swap.timestamp,
+                    op,
+                    native_amount,
+                    baseline_native,
+                }),
+                Err(err) => rejected.push((swap.timestamp, op, err)),
+            }
+        }
+        let op = swap.op();
+        match (pool.execute(&op), baseline.execute(&op)) {
+            (Ok(native_amount), Ok(baseline_native)) => {
+                historical_slippage += shortfall(&op, native_amount, baseline_native);
+            }
+            (Err(_), _) => historical_rejected += 1,
+            (Ok(_), Err(_)) => {}
+        }
+    }
+    BacktestReport {
+        trader,
+        fills,
+        rejected,
+        historical_rejected,
+        historical_slippage,
+        final_price: pool.spot_price(),
+        baseline_final_price: baseline.spot_price(),
+    }
+}
+
+/// Parses a CSV log with a `timestamp,direction,token_amount` header, directions being `buy`
+/// or `sell`. Blank lines are skipped.
+pub fn parse_csv(s: &str) -> Result<Vec<HistoricalSwap>, BacktestError> {
+    let mut lines = s
+        .lines()
+        .enumerate()
+        .map(|(index, raw)| (index + 1, raw.trim()))
+        .filter(|(_, raw)| !raw.is_empty());
+    let error = |line, message: String| BacktestError::Parse { line, message };
+    match lines.next() {
+        Some((_, "timestamp,direction,token_amount")) => {}
+        Some((line, header)) => return Err(error(line, format!("unexpected header `{header}`"))),
+        None => return Ok(Vec::new()),
+    }
+    let parse_u64 = |line, field, value: &str| {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| error(line, format!("invalid {field} `{value}`")))
+    };
+    lines
+        .map(|(line, raw)| match *raw.split(',').collect::<Vec<_>>() {
+            [timestamp, direction, token_amount] => Ok(HistoricalSwap {
+                timestamp: parse_u64(line, "timestamp", timestamp)?,
+                direction: match direction.trim() {
+                    "buy" => Direction::BuyToken,
+                    "sell" => Direction::SellToken,
+                    other => return Err(error(line, format!("invalid direction `{other}`"))),
+                },
+                token_amount: parse_u64(line, "token amount", token_amount)?,
+            }),
+            _ => Err(error(line, format!("expected 3 fields in `{raw}`"))),
+        })
+        .collect()
+}
+
+/// Loads a CSV log from a file, see [`parse_csv`].
+pub fn from_csv_file(path: impl AsRef<Path>) -> Result<Vec<HistoricalSwap>, BacktestError> {
+    parse_csv(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "
+        timestamp,direction,token_amount
+        1700000000,buy,1000000000000
+        1700000012,buy,2000000000000
+        1700000030,sell,500000000000
+    ";
+
+    /// Buys ahead of every historical buy and sells everything before the first sell.
+    struct FrontRunner;
+
+    impl Strategy for FrontRunner {
+        fn before_swap(
+            &mut self,
+            swap: &HistoricalSwap,
+            _pool: &LiquidityPool,
+            trader: &Trader,
+        ) -> Vec<TradeOp> {
+            match swap.direction {
+                Direction::BuyToken => vec![TradeOp::Buy {
+                    token_amount: 10u64.pow(12),
+                    max_native: None,
+                }],
+                Direction::SellToken if trader.token_balance() > 0 => vec![TradeOp::Sell {
+                    token_amount: trader.token_balance(),
+                    min_native: None,
+                }],
+                Direction::SellToken => Vec::new(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let swaps = parse_csv(LOG).unwrap();
+        assert_eq!(swaps.len(), 3);
+        assert_eq!(
+            swaps[2],
+            HistoricalSwap {
+                timestamp: 1700000030,
+                direction: Direction::SellToken,
+                token_amount: 500000000000
+            }
+        );
+        let err = parse_csv("timestamp,direction,token_amount\n1,swap,5").unwrap_err();
+        assert!(matches!(err, BacktestError::Parse { line: 2, .. }));
+        assert!(parse_csv("time,side,size").is_err());
+        assert!(parse_csv("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_backtest_front_runner() {
+        let pool = LiquidityPool::default();
+        let swaps = parse_csv(LOG).unwrap();
+        let report = run(&pool, &swaps, Trader::new(10u64.pow(9)), &mut FrontRunner);
+        assert_eq!(report.fills.len(), 3);
+        assert!(report.rejected.is_empty());
+        assert_eq!(report.historical_rejected, 0);
+        // Front-running the buys pays off, at the historical buyers' expense.
+        assert!(report.realized_pnl() > 0);
+        assert!(report.historical_slippage > 0);
+        assert_eq!(report.trader.token_balance(), 0);
+        assert_eq!(report.unrealized_pnl(), 0.0);
+        // The second buy pays for the first one's impact.
+        assert_eq!(report.fills[0].slippage(), 0);
+        assert!(report.fills[1].slippage() > 0);
+        assert_eq!(report.baseline_final_price, {
+            let mut baseline = pool.clone();
+            for swap in &swaps {
+                baseline.execute(&swap.op()).unwrap();
+            }
+            baseline.spot_price()
+        });
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{Curve, LiquidityPool, PoolError, Result, MAX_FEE_BPS};
+
+/// Condition completing a bonding curve, after which it stops trading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum GraduationTarget {
+    /// The real native reserve reaches this amount.
+    NativeReserve(u64),
+    /// This share of the tokens for sale, in basis points, has been bought.
+    TokensSoldBps(u16),
+}
+
+impl GraduationTarget {
+    pub(crate) fn is_valid(&self) -> bool {
+        match *self {
+            GraduationTarget::NativeReserve(native_reserve) => native_reserve > 0,
+            GraduationTarget::TokensSoldBps(bps) => bps > 0 && bps <= MAX_FEE_BPS,
+        }
+    }
+}
+
+/// A graduation target together with the tokens for sale when it was set, as returned by
+/// [`LiquidityPool::graduation`].
+///
+/// With the `borsh` feature, it is encoded as the target (a variant byte followed by a `u64`
+/// native reserve or `u16` basis points) then `token_supply: u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Graduation {
+    pub(crate) target: GraduationTarget,
+    pub(crate) token_supply: u64,
+}
+
+impl Graduation {
+    pub fn target(&self) -> GraduationTarget {
+        self.target
+    }
+
+    /// Real token reserve when the target was set, against which tokens sold are counted.
+    pub fn token_supply(&self) -> u64 {
+        self.token_supply
+    }
+
+    /// Returns how far `pool` is toward the target, as `(reached, required)`.
+    fn progress<C: Curve>(&self, pool: &LiquidityPool<C>) -> (u128, u128) {
+        match self.target {
+            GraduationTarget::NativeReserve(native_reserve) => {
+                (pool.real_native_reserve() as u128, native_reserve as u128)
+            }
+            GraduationTarget::TokensSoldBps(bps) => {
+                let sold = self.token_supply.saturating_sub(pool.real_token_reserve());
+                (
+                    sold as u128 * MAX_FEE_BPS as u128,
+                    self.token_supply as u128 * bps as u128,
+                )
+            }
+        }
+    }
+}
+
+impl LiquidityPool {
+    /// Creates a launchpad-style bonding curve whose reserves hold virtual amounts on top of
+    /// the real ones.
+    ///
+    /// Virtual reserves shape the curve: pricing uses the effective reserves, real plus
+    /// virtual. They cannot be withdrawn: buys stop once every real token is sold and sells
+    /// once the real native reserve is paid out. The real native reserve may start at zero.
+    /// Liquidity cannot be added to or removed from such pools.
+    pub fn with_virtual_reserves(
+        real_native_reserve: u64,
+        real_token_reserve: u64,
+        virtual_native: u64,
+        virtual_token: u64,
+    ) -> Result<Self> {
+        if real_token_reserve == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let native_reserve = real_native_reserve
+            .checked_add(virtual_native)
+            .ok_or(PoolError::Overflow)?;
+        let token_reserve = real_token_reserve
+            .checked_add(virtual_token)
+            .ok_or(PoolError::Overflow)?;
+        let mut pool = Self::new(native_reserve, token_reserve)?;
+        pool.virtual_native = virtual_native;
+        pool.virtual_token = virtual_token;
+        Ok(pool)
+    }
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Completes the curve once `target` is reached: from then on every trade fails with
+    /// [`PoolError::CurveCompleted`], as the liquidity is expected to migrate elsewhere. The
+    /// trade reaching the target still goes through. Tokens sold are counted from the current
+    /// real token reserve. Fails with [`PoolError::InvalidAmount`] for a zero target or a
+    /// share above 100%.
+    pub fn with_graduation(mut self, target: GraduationTarget) -> Result<Self> {
+        if !target.is_valid() {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.graduation = Some(Graduation {
+            target,
+            token_supply: self.real_token_reserve(),
+        });
+        Ok(self)
+    }
+
+    pub fn graduation(&self) -> Option<Graduation> {
+        self.graduation
+    }
+
+    /// Returns the progress toward the graduation target, from 0 to 1, or 0 without one.
+    pub fn curve_progress(&self) -> f64 {
+        match self.graduation {
+            Some(graduation) => {
+                let (reached, required) = graduation.progress(self);
+                if required == 0 {
+                    1.0
+                } else {
+                    (reached as f64 / required as f64).min(1.0)
+                }
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Returns whether the graduation target has been reached.
+    pub fn is_graduated(&self) -> bool {
+        self.graduation.is_some_and(|graduation| {
+            let (reached, required) = graduation.progress(self);
+            reached >= required
+        })
+    }
+
+    /// Returns whether the pool holds any virtual reserve.
+    pub fn has_virtual_reserves(&self) -> bool {
+        self.virtual_native > 0 || self.virtual_token > 0
+    }
+
+    pub fn virtual_native_reserve(&self) -> u64 {
+        self.virtual_native
+    }
+
+    pub fn virtual_token_reserve(&self) -> u64 {
+        self.virtual_token
+    }
+
+    /// Returns the native currency actually held by the pool, the most sellers can receive.
+    pub fn real_native_reserve(&self) -> u64 {
+        self.native_reserve - self.virtual_native
+    }
+
+    /// Returns the tokens actually held by the pool, the most buyers can receive.
+    pub fn real_token_reserve(&self) -> u64 {
+        self.token_reserve - self.virtual_token
+    }
+
+    /// Returns the native reserve used for pricing, virtual part included. Same as
+    /// [`get_native_reserve`](Self::get_native_reserve).
+    pub fn effective_native_reserve(&self) -> u64 {
+        self.native_reserve
+    }
+
+    /// Returns the token reserve used for pricing, virtual part included. Same as
+    /// [`get_token_reserve`](Self::get_token_reserve).
+    pub fn effective_token_reserve(&self) -> u64 {
+        self.token_reserve
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TradeOp;
+
+    const REAL_TOKENS: u64 = 793_100_000 * 10u64.pow(6);
+
+    fn launch() -> LiquidityPool {
+        LiquidityPool::with_virtual_reserves(
+            0,
+            REAL_TOKENS,
+            30 * 10u64.pow(9),
+            279_900_000 * 10u64.pow(6),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_virtual_reserves_price_but_cannot_be_withdrawn() {
+        let mut pool = launch();
+        assert_eq!(pool.real_native_reserve(), 0);
+        assert_eq!(pool.effective_token_reserve(), 1_073_000_000 * 10u64.pow(6));
+        assert!((pool.spot_price() - 30.0 / 1_073_000.0).abs() < 1e-12);
+        assert!(matches!(
+            pool.sell(1, None),
+            Err(PoolError::InsufficientPoolFunds { available: 0, .. })
+        ));
+        assert_eq!(
+            pool.add_liquidity(10u64.pow(9), u64::MAX).map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+
+        let spent = pool.buy(REAL_TOKENS, None).unwrap();
+        assert_eq!(pool.real_token_reserve(), 0);
+        assert_eq!(pool.real_native_reserve(), spent);
+        assert_eq!(
+            pool.buy(1, None),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: 1,
+                available: 0
+            })
+        );
+
+        let sold = pool
+            .execute_saturating(&TradeOp::Sell {
+                token_amount: u64::MAX,
+                min_native: None,
+            })
+            .unwrap();
+        assert!(sold.op.token_amount() <= REAL_TOKENS);
+        assert!(pool.get_native_reserve() >= pool.virtual_native_reserve());
+    }
+
+    #[test]
+    fn test_graduation() {
+        let mut pool = launch()
+            .with_graduation(GraduationTarget::TokensSoldBps(5_000))
+            .unwrap();
+        assert_eq!(pool.curve_progress(), 0.0);
+        pool.buy(REAL_TOKENS / 4, None).unwrap();
+        assert_eq!(pool.curve_progress(), 0.5);
+        assert!(!pool.is_graduated());
+        pool.buy(REAL_TOKENS / 2, None).unwrap();
+        assert!(pool.is_graduated());
+        assert_eq!(pool.curve_progress(), 1.0);
+        assert_eq!(pool.buy(1, None), Err(PoolError::CurveCompleted));
+        assert_eq!(pool.sell(1, None), Err(PoolError::CurveCompleted));
+
+        let mut pool = launch()
+            .with_graduation(GraduationTarget::NativeReserve(10u64.pow(9)))
+            .unwrap();
+        pool.spend_native(10u64.pow(9) / 2).unwrap();
+        assert!((pool.curve_progress() - 0.5).abs() < 1e-6);
+        assert_eq!(
+            launch()
+                .with_graduation(GraduationTarget::TokensSoldBps(10_001))
+                .map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn test_virtual_reserves_survive_snapshots() {
+        let mut pool = launch()
+            .with_graduation(GraduationTarget::NativeReserve(10u64.pow(9)))
+            .unwrap();
+        pool.buy(10u64.pow(12), None).unwrap();
+        let restored = LiquidityPool::try_from(pool.snapshot()).unwrap();
+        assert_eq!(restored.real_token_reserve(), pool.real_token_reserve());
+        assert_eq!(restored.real_native_reserve(), pool.real_native_reserve());
+        assert_eq!(restored.graduation(), pool.graduation());
+
+        let mut corrupt = pool.snapshot();
+        corrupt.virtual_native = corrupt.native_reserve + 1;
+        assert_eq!(
+            LiquidityPool::try_from(corrupt).map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,544 @@
+//! Calibrating pool models against observed on-chain activity.
+//!
+//! Swaps are modelled as a constant product curve charging a fee on the input amount, which
+//! stays in the pool: `out = reserve_out * g * in / (reserve_in + g * in)` with `g = 1 - fee`.
+
+use crate::{Direction, LiquidityPool, PoolError, Result};
+
+/// Largest fee rate considered when fitting.
+const MAX_FEE: f64 = 0.3;
+/// Standard deviations covered by [`Bounds`], for a 95% confidence interval.
+const CONFIDENCE_Z: f64 = 1.96;
+/// Difference between the buy and sell fee rates above which a transfer tax is reported.
+const TAX_TOLERANCE: f64 = 0.001;
+
+/// A swap observed on a pool, in the order it was executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObservedSwap {
+    pub direction: Direction,
+    /// Native (buy) or tokens (sell) paid in.
+    pub amount_in: u64,
+    /// Tokens (buy) or native (sell) paid out.
+    pub amount_out: u64,
+}
+
+/// A swap observed together with the pool reserves right before it executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapSample {
+    pub native_reserve: u64,
+    pub token_reserve: u64,
+    pub swap: ObservedSwap,
+}
+
+/// Curve parameters best explaining a sequence of swaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurveFit {
+    /// Native reserve before the first swap.
+    pub native_reserve: f64,
+    /// Token reserve before the first swap.
+    pub token_reserve: f64,
+    /// Fee rate charged on the input amount, from 0 to 1.
+    pub fee: f64,
+    /// Root mean square of the relative error between predicted and observed outputs.
+    pub rms_error: f64,
+}
+
+impl CurveFit {
+    /// Builds a pool holding the fitted reserves, rounded to whole units.
+    pub fn to_pool(&self) -> Result<LiquidityPool> {
+        LiquidityPool::new(
+            self.native_reserve.round() as u64,
+            self.token_reserve.round() as u64,
+        )
+    }
+}
+
+/// Token transfer taxes detected on top of the pool fee, as rates of the traded amount.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransferTax {
+    pub buy: f64,
+    pub sell: f64,
+}
+
+/// Effective fee rates implied by swaps against known reserves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeEstimate {
+    /// Pool fee rate: the median over all swaps, or the lower of the two directions when a
+    /// transfer tax is detected.
+    pub fee: f64,
+    /// Median effective fee rate of buys, if any were observed.
+    pub buy_fee: Option<f64>,
+    /// Median effective fee rate of sells, if any were observed.
+    pub sell_fee: Option<f64>,
+    /// Difference between the highest and lowest per-swap fee rates, a measure of noise.
+    pub spread: f64,
+    /// Set when buys and sells pay noticeably different rates, which a symmetric pool fee
+    /// cannot explain. Requires swaps in both directions.
+    pub transfer_tax: Option<TransferTax>,
+}
+
+/// Solves for the effective fee rate of each sampled swap from the reserves it traded
+/// against, and summarizes them. Fails with [`PoolError::InvalidAmount`] if there are no
+/// samples, a swap is empty or an output is not below the reserve it is paid from.
+pub fn infer_fee(samples: &[SwapSample]) -> Result<FeeEstimate> {
+    let mut buys = Vec::new();
+    let mut sells = Vec::new();
+    for sample in samples {
+        let (native, token) = (sample.native_reserve as f64, sample.token_reserve as f64);
+        let (amount_in, amount_out) = (sample.swap.amount_in as f64, sample.swap.amount_out as f64);
+        let (reserve_in, reserve_out, fees) = match sample.swap.direction {
+            Direction::BuyToken => (native, token, &mut buys),
+            Direction::SellToken => (token, native, &mut sells),
+        };
+        if amount_in == 0.0 || amount_out == 0.0 || amount_out >= reserve_out {
+            return Err(PoolError::InvalidAmount);
+        }
+        // out * (reserve_in + g * in) = reserve_out * g * in, solved for g.
+        let g = amount_out * reserve_in / (amount_in * (reserve_out - amount_out));
+        fees.push(1.0 - g);
+    }
+    let mut all: Vec<f64> = buys.iter().chain(&sells).copied().collect();
+    let fee = median(&mut all).ok_or(PoolError::InvalidAmount)?;
+    let spread = all[all.len() - 1] - all[0];
+    let (buy_fee, sell_fee) = (median(&mut buys), median(&mut sells));
+    let (fee, transfer_tax) = match (buy_fee, sell_fee) {
+        (Some(buy), Some(sell)) if (buy - sell).abs() > TAX_TOLERANCE => {
+            let fee = buy.min(sell);
+            let tax = TransferTax {
+                buy: buy - fee,
+                sell: sell - fee,
+            };
+            (fee, Some(tax))
+        }
+        _ => (fee, None),
+    };
+    Ok(FeeEstimate {
+        fee,
+        buy_fee,
+        sell_fee,
+        spread,
+        transfer_tax,
+    })
+}
+
+/// Sorts `values` and returns their median.
+fn median(values: &mut [f64]) -> Option<f64> {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[mid - 1] + values[mid]) / 2.0),
+        _ => Some(values[mid]),
+    }
+}
+
+/// A trade from a price series: its size and the average price it executed at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PricedTrade {
+    pub direction: Direction,
+    pub token_amount: u64,
+    /// Native paid or received per token.
+    pub price: f64,
+}
+
+/// An estimated value with a 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Bounds {
+    pub fn contains(&self, value: f64) -> bool {
+        (self.low..=self.high).contains(&value)
+    }
+}
+
+/// Reserves reconstructed from a price series, as held after its last trade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReserveEstimate {
+    pub native_reserve: Bounds,
+    pub token_reserve: Bounds,
+}
+
+/// Reconstructs the reserves of a fee-less constant product pool from the sizes and
+/// execution prices of a series of consecutive trades.
+///
+/// A buy of `d` tokens executes at `native / (token - d)` and a sell at
+/// `native / (token + d)`, which is linear in the reserves before the first trade, so they
+/// are fitted by least squares and the confidence bounds follow from the residuals. Fails
+/// with [`PoolError::InvalidAmount`] if fewer than three trades are given, a trade is empty
+/// or has a non-positive or non-finite price, or the series cannot determine the reserves.
+pub fn estimate_reserves(trades: &[PricedTrade]) -> Result<ReserveEstimate> {
+    if trades.len() < 3
+        || trades
+            .iter()
+            .any(|trade| trade.token_amount == 0 || !trade.price.is_finite() || trade.price <= 0.0)
+    {
+        return Err(PoolError::InvalidAmount);
+    }
+    // Change each trade makes to the (native, token) reserves.
+    let delta = |trade: &PricedTrade| {
+        let amount = trade.token_amount as f64;
+        match trade.direction {
+            Direction::BuyToken => (trade.price * amount, -amount),
+            Direction::SellToken => (-trade.price * amount, amount),
+        }
+    };
+    let mut rows = Vec::with_capacity(trades.len());
+    let (mut native_change, mut token_change) = (0.0, 0.0);
+    for trade in trades {
+        let (native_delta, token_delta) = delta(trade);
+        // price * (token_0 + token_change + token_delta) = native_0 + native_change
+        rows.push((
+            [trade.price, -1.0],
+            native_change - trade.price * (token_change + token_delta),
+        ));
+        native_change += native_delta;
+        token_change += token_delta;
+    }
+    let fit = least_squares(rows.into_iter()).ok_or(PoolError::InvalidAmount)?;
+    let bounds = |index: usize, change: f64| {
+        let value = fit.x[index] + change;
+        let margin = CONFIDENCE_Z * fit.covariance[index][index].sqrt();
+        Bounds {
+            value,
+            low: value - margin,
+            high: value + margin,
+        }
+    };
+    let estimate = ReserveEstimate {
+        token_reserve: bounds(0, token_change),
+        native_reserve: bounds(1, native_change),
+    };
+    if estimate.native_reserve.value <= 0.0 || estimate.token_reserve.value <= 0.0 {
+        return Err(PoolError::InvalidAmount);
+    }
+    Ok(estimate)
+}
+
+/// Estimates the reserves before the first swap and the fee rate that best explain `swaps`.
+///
+/// For a given fee the reserves follow from a linear least squares fit, so only the fee is
+/// searched. Fails with [`PoolError::InvalidAmount`] if fewer than two non-empty swaps are
+/// given or no positive reserves explain them.
+pub fn fit_curve(swaps: &[ObservedSwap]) -> Result<CurveFit> {
+    if swaps.len() < 2
+        || swaps
+            .iter()
+            .any(|swap| swap.amount_in == 0 || swap.amount_out == 0)
+    {
+        return Err(PoolError::InvalidAmount);
+    }
+    let evaluate = |fee: f64| {
+        let [native_reserve, token_reserve] = fit_reserves(swaps, 1.0 - fee)?;
+        (native_reserve > 0.0 && token_reserve > 0.0).then(|| CurveFit {
+            native_reserve,
+            token_reserve,
+            fee,
+            rms_error: rms_error(swaps, native_reserve, token_reserve, fee),
+        })
+    };
+    // Coarse grid, then golden-section refinement around the best point.
+    let steps = 60;
+    let grid_step = MAX_FEE / steps as f64;
+    let best = (0..=steps)
+        .filter_map(|step| evaluate(step as f64 * grid_step))
+        .min_by(|a, b| a.rms_error.total_cmp(&b.rms_error))
+        .ok_or(PoolError::InvalidAmount)?;
+    let error_at = |fee: f64| evaluate(fee).map_or(f64::INFINITY, |fit| fit.rms_error);
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (
+        (best.fee - grid_step).max(0.0),
+        (best.fee + grid_step).min(MAX_FEE),
+    );
+    for _ in 0..60 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if error_at(left) < error_at(right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    Ok(evaluate((low + high) / 2.0)
+        .filter(|fit| fit.rms_error < best.rms_error)
+        .unwrap_or(best))
+}
+
+/// Reserve changes applied before each swap, relative to the initial reserves.
+fn offsets(swaps: &[ObservedSwap]) -> impl Iterator<Item = (&ObservedSwap, f64, f64)> {
+    swaps.iter().scan((0.0, 0.0), |(native, token), swap| {
+        let before = (swap, *native, *token);
+        let (amount_in, amount_out) = (swap.amount_in as f64, swap.amount_out as f64);
+        match swap.direction {
+            Direction::BuyToken => {
+                *native += amount_in;
+                *token -= amount_out;
+            }
+            Direction::SellToken => {
+                *token += amount_in;
+                *native -= amount_out;
+            }
+        }
+        Some(before)
+    })
+}
+
+/// Solves for the initial `[native_reserve, token_reserve]` given the input multiplier `g`.
+fn fit_reserves(swaps: &[ObservedSwap], g: f64) -> Option<[f64; 2]> {
+    // out * (reserve_in + g * in) = reserve_out * g * in is linear in the initial reserves.
+    let rows = offsets(swaps).map(|(swap, native, token)| {
+        let (amount_in, amount_out) = (swap.amount_in as f64, swap.amount_out as f64);
+        match swap.direction {
+            Direction::BuyToken => (
+                [amount_out, -g * amount_in],
+                g * amount_in * token - amount_out * native - amount_out * g * amount_in,
+            ),
+            Direction::SellToken => (
+                [-g * amount_in, amount_out],
+                g * amount_in * native - amount_out * token - amount_out * g * amount_in,
+            ),
+        }
+    });
+    least_squares(rows).map(|fit| fit.x)
+}
+
+/// Least squares solution of a system in two unknowns, with its estimated covariance.
+struct LeastSquares {
+    x: [f64; 2],
+    /// Zero when there are no more rows than unknowns to estimate the noise from.
+    covariance: [[f64; 2]; 2],
+}
+
+/// Solves the overdetermined system `a · x = b` in two unknowns. Rows are normalized so each
+/// observation carries the same weight whatever its size, and columns so that unknowns of
+/// very different magnitudes stay well conditioned.
+fn least_squares(rows: impl Iterator<Item = ([f64; 2], f64)>) -> Option<LeastSquares> {
+    let rows: Vec<([f64; 2], f64)> = rows
+        .filter_map(|(a, b)| {
+            let norm = a[0].hypot(a[1]);
+            (norm > 0.0).then(|| ([a[0] / norm, a[1] / norm], b / norm))
+        })
+        .collect();
+    let scale = [0, 1].map(|j| rows.iter().map(|(a, _)| a[j] * a[j]).sum::<f64>().sqrt());
+    if scale.contains(&0.0) {
+        return None;
+    }
+    let (mut aa, mut ab) = ([[0.0; 2]; 2], [0.0; 2]);
+    for (a, b) in &rows {
+        let a = [a[0] / scale[0], a[1] / scale[1]];
+        for i in 0..2 {
+            for j in 0..2 {
+                aa[i][j] += a[i] * a[j];
+            }
+            ab[i] += a[i] * b;
+        }
+    }
+    let det = aa[0][0] * aa[1][1] - aa[0][1] * aa[1][0];
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+    let inverse = [
+        [aa[1][1] / det, -aa[0][1] / det],
+        [-aa[1][0] / det, aa[0][0] / det],
+    ];
+    let x = [0, 1].map(|i| (inverse[i][0] * ab[0] + inverse[i][1] * ab[1]) / scale[i]);
+    let rss: f64 = rows
+        .iter()
+        .map(|(a, b)| (a[0] * x[0] + a[1] * x[1] - b).powi(2))
+        .sum();
+    let variance = if rows.len() > 2 {
+        rss / (rows.len() - 2) as f64
+    } else {
+        0.0
+    };
+    let covariance =
+        [0, 1].map(|i| [0, 1].map(|j| variance * inverse[i][j] / (scale[i] * scale[j])));
+    Some(LeastSquares { x, covariance })
+}
+
+fn rms_error(swaps: &[ObservedSwap], native_reserve: f64, token_reserve: f64, fee: f64) -> f64 {
+    let g = 1.0 - fee;
+    let sum: f64 = offsets(swaps)
+        .map(|(swap, native, token)| {
+            let (native, token) = (native_reserve + native, token_reserve + token);
+            let (reserve_in, reserve_out) = match swap.direction {
+                Direction::BuyToken => (native, token),
+                Direction::SellToken => (token, native),
+            };
+            let amount_in = g * swap.amount_in as f64;
+            let predicted = reserve_out * amount_in / (reserve_in + amount_in);
+            (predicted / swap.amount_out as f64 - 1.0).powi(2)
+        })
+        .sum();
+    (sum / swaps.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Swaps against an ideal curve charging `fee` on inputs, with `sell_tax` of the tokens
+    /// sold withheld before they reach the pool.
+    fn synthetic_swaps(native: f64, token: f64, fee: f64, sell_tax: f64) -> Vec<SwapSample> {
+        let (mut native, mut token) = (native, token);
+        [
+            (true, 2e8),
+            (false, 3e14),
+            (true, 5e7),
+            (true, 4e8),
+            (false, 1e14),
+        ]
+        .into_iter()
+        .map(|(is_buy, amount_in)| {
+            let (native_reserve, token_reserve) = (native as u64, token as u64);
+            let (reserve_in, reserve_out) = if is_buy {
+                (&mut native, &mut token)
+            } else {
+                (&mut token, &mut native)
+            };
+            let received = if is_buy {
+                amount_in
+            } else {
+                amount_in * (1.0 - sell_tax)
+            };
+            let effective = received * (1.0 - fee);
+            let amount_out = (*reserve_out * effective / (*reserve_in + effective)).floor();
+            *reserve_in += received;
+            *reserve_out -= amount_out;
+            let swap = ObservedSwap {
+                direction: if is_buy {
+                    Direction::BuyToken
+                } else {
+                    Direction::SellToken
+                },
+                amount_in: amount_in as u64,
+                amount_out: amount_out as u64,
+            };
+            SwapSample {
+                native_reserve,
+                token_reserve,
+                swap,
+            }
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_fit_pool_trades() {
+        let mut pool = LiquidityPool::default();
+        let swaps: Vec<_> = [
+            (true, 10u64.pow(14)),
+            (false, 3 * 10u64.pow(13)),
+            (true, 10u64.pow(13)),
+        ]
+        .into_iter()
+        .map(|(is_buy, token_amount)| {
+            if is_buy {
+                let native = pool.buy(token_amount, None).unwrap();
+                ObservedSwap {
+                    direction: Direction::BuyToken,
+                    amount_in: native,
+                    amount_out: token_amount,
+                }
+            } else {
+                let native = pool.sell(token_amount, None).unwrap();
+                ObservedSwap {
+                    direction: Direction::SellToken,
+                    amount_in: token_amount,
+                    amount_out: native,
+                }
+            }
+        })
+        .collect();
+        let fit = fit_curve(&swaps).unwrap();
+        assert!(fit.fee < 1e-4);
+        assert!((fit.native_reserve / 1e9 - 1.0).abs() < 1e-3);
+        assert!((fit.token_reserve / 1e15 - 1.0).abs() < 1e-3);
+        let pool = fit.to_pool().unwrap();
+        assert!(pool.get_native_reserve() > 0);
+    }
+
+    #[test]
+    fn test_fit_recovers_fee() {
+        let swaps: Vec<_> = synthetic_swaps(1e9, 1e15, 0.0025, 0.0)
+            .iter()
+            .map(|sample| sample.swap)
+            .collect();
+        let fit = fit_curve(&swaps).unwrap();
+        assert!((fit.fee - 0.0025).abs() < 1e-4, "{fit:?}");
+        assert!((fit.token_reserve / 1e15 - 1.0).abs() < 1e-3);
+        assert!(fit.rms_error < 1e-6);
+        assert_eq!(fit_curve(&swaps[..1]), Err(PoolError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_infer_fee() {
+        let samples = synthetic_swaps(1e9, 1e15, 0.003, 0.0);
+        let estimate = infer_fee(&samples).unwrap();
+        assert!((estimate.fee - 0.003).abs() < 1e-5, "{estimate:?}");
+        assert!(estimate.spread < 1e-5);
+        assert!(estimate.transfer_tax.is_none());
+        assert_eq!(infer_fee(&[]), Err(PoolError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_infer_fee_detects_transfer_tax() {
+        let samples = synthetic_swaps(1e9, 1e15, 0.003, 0.05);
+        let estimate = infer_fee(&samples).unwrap();
+        assert!((estimate.fee - 0.003).abs() < 1e-3, "{estimate:?}");
+        let tax = estimate.transfer_tax.unwrap();
+        assert!(tax.buy.abs() < 1e-9);
+        assert!((tax.sell - 0.05).abs() < 5e-3, "{tax:?}");
+    }
+
+    #[test]
+    fn test_estimate_reserves() {
+        let mut pool = LiquidityPool::default();
+        let mut rng = crate::rng::SplitMix64::new(11);
+        let trades: Vec<_> = (0..40)
+            .map(|index| {
+                let token_amount = rng.range_u64(10u64.pow(12), 10u64.pow(13));
+                let (direction, native) = if index % 3 == 2 {
+                    (Direction::SellToken, pool.sell(token_amount, None).unwrap())
+                } else {
+                    (Direction::BuyToken, pool.buy(token_amount, None).unwrap())
+                };
+                // Prices as reported by an indexer, with up to 0.1% of noise.
+                let noise = 1.0 + (rng.next_f64() - 0.5) * 2e-3;
+                PricedTrade {
+                    direction,
+                    token_amount,
+                    price: native as f64 / token_amount as f64 * noise,
+                }
+            })
+            .collect();
+        let estimate = estimate_reserves(&trades).unwrap();
+        let token_reserve = pool.get_token_reserve() as f64;
+        let native_reserve = pool.get_native_reserve() as f64;
+        assert!(
+            estimate.token_reserve.contains(token_reserve),
+            "{estimate:?}"
+        );
+        assert!(
+            estimate.native_reserve.contains(native_reserve),
+            "{estimate:?}"
+        );
+        assert!((estimate.token_reserve.value / token_reserve - 1.0).abs() < 0.05);
+        assert!(estimate.token_reserve.high - estimate.token_reserve.low < 0.2 * token_reserve);
+        assert_eq!(
+            estimate_reserves(&trades[..2]),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
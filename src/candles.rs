@@ -0,0 +1,158 @@
+//! OHLCV candles built from trade events, for charting a pool without an external indexer.
+//!
+//! Events carry no time, so each is pushed with the timestamp it was observed at. A trade's
+//! price is the spot price it left the pool at, `native_reserve / token_reserve`, and
+//! intervals without trades produce no candle.
+
+use alloc::vec::Vec;
+
+use crate::{Direction, PoolError, Result, TradeEvent};
+
+/// Trades within one interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candle {
+    /// Start of the interval, a multiple of the interval length.
+    pub start: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Tokens bought and sold.
+    pub token_volume: u128,
+    /// Native currency paid and received, fee included.
+    pub native_volume: u128,
+    pub buys: u64,
+    pub sells: u64,
+}
+
+impl Candle {
+    fn open(start: u64, price: f64) -> Self {
+        Self {
+            start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            token_volume: 0,
+            native_volume: 0,
+            buys: 0,
+            sells: 0,
+        }
+    }
+
+    fn add(&mut self, price: f64, event: &TradeEvent) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.token_volume += event.token_amount as u128;
+        self.native_volume += event.native_amount as u128;
+        match event.direction {
+            Direction::BuyToken => self.buys += 1,
+            Direction::SellToken => self.sells += 1,
+        }
+    }
+}
+
+/// Candles of a fixed interval, updated one trade at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandleSeries {
+    interval: u64,
+    candles: Vec<Candle>,
+    last_timestamp: Option<u64>,
+}
+
+impl CandleSeries {
+    /// Creates an empty series of `interval`-second candles. Fails with
+    /// [`PoolError::InvalidAmount`] for a zero interval.
+    pub fn new(interval: u64) -> Result<Self> {
+        if interval == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        Ok(Self {
+            interval,
+            candles: Vec::new(),
+            last_timestamp: None,
+        })
+    }
+
+    /// Builds a series from events paired with their timestamps, oldest first.
+    pub fn from_events<'a>(
+        interval: u64,
+        events: impl IntoIterator<Item = (u64, &'a TradeEvent)>,
+    ) -> Result<Self> {
+        let mut series = Self::new(interval)?;
+        for (timestamp, event) in events {
+            series.push(timestamp, event)?;
+        }
+        Ok(series)
+    }
+
+    /// Adds a trade observed at `timestamp`, updating the current candle or opening the next
+    /// one. Fails with [`PoolError::InvalidAmount`] if `timestamp` precedes the previous
+    /// trade's.
+    pub fn push(&mut self, timestamp: u64, event: &TradeEvent) -> Result<()> {
+        if self.last_timestamp.is_some_and(|last| timestamp < last) {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.last_timestamp = Some(timestamp);
+        let (native_reserve, token_reserve) = event.reserves_after;
+        let price = native_reserve as f64 / token_reserve as f64;
+        let start = timestamp - timestamp % self.interval;
+        match self.candles.last_mut() {
+            Some(candle) if candle.start == start => candle.add(price, event),
+            _ => {
+                let mut candle = Candle::open(start, price);
+                candle.add(price, event);
+                self.candles.push(candle);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Candles oldest first; the last one is still open to new trades.
+    pub fn candles(&self) -> &[Candle] {
+        &self.candles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityPool;
+
+    #[test]
+    fn test_candles() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let token_amount = 10u64.pow(12);
+        pool.buy(token_amount, None).unwrap();
+        pool.buy(token_amount, None).unwrap();
+        pool.sell(3 * token_amount, None).unwrap();
+        pool.buy(token_amount, None).unwrap();
+        let events = pool.events().unwrap();
+        let prices: Vec<f64> = events
+            .iter()
+            .map(|event| event.reserves_after.0 as f64 / event.reserves_after.1 as f64)
+            .collect();
+
+        let timestamps = [0, 59, 60, 185];
+        let series = CandleSeries::from_events(60, timestamps.into_iter().zip(&events)).unwrap();
+        let candles = series.candles();
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].start, 0);
+        assert_eq!((candles[0].open, candles[0].close), (prices[0], prices[1]));
+        assert_eq!(candles[0].high, prices[1]);
+        assert_eq!(candles[0].token_volume, 2 * token_amount as u128);
+        assert_eq!((candles[1].buys, candles[1].sells), (0, 1));
+        assert_eq!(candles[2].start, 180);
+
+        let mut series = series;
+        assert_eq!(series.push(100, &events[0]), Err(PoolError::InvalidAmount));
+        assert_eq!(CandleSeries::new(0), Err(PoolError::InvalidAmount));
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::{Curve, LiquidityPool, PoolSnapshot};
+
+/// When a pool with checkpoints enabled snapshots itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckpointPolicy {
+    /// Take a checkpoint every `n` trades.
+    pub every_trades: Option<u64>,
+    /// Take a checkpoint on the first trade applied at least this long after the previous one.
+    pub every_interval: Option<Duration>,
+    /// Number of checkpoints kept; the oldest is dropped once the ring is full.
+    pub capacity: usize,
+}
+
+/// A snapshot taken automatically by the checkpoint policy, right after a trade and before
+/// any liquidity change or reanchor that follows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub taken_at: Instant,
+    pub snapshot: PoolSnapshot,
+}
+
+impl Checkpoint {
+    /// Number of trades applied when the checkpoint was taken.
+    pub fn seq(&self) -> u64 {
+        self.snapshot.trade_count()
+    }
+}
+
+/// Bounded ring of checkpoints maintained by a pool.
+#[derive(Debug, Clone)]
+pub(crate) struct Checkpoints {
+    policy: CheckpointPolicy,
+    ring: VecDeque<Checkpoint>,
+    /// Trade count and time checkpointing started from, until the first checkpoint.
+    start: (u64, Instant),
+}
+
+impl Checkpoints {
+    pub(crate) fn new<C: Curve>(policy: CheckpointPolicy, pool: &LiquidityPool<C>) -> Self {
+        Self {
+            policy,
+            ring: VecDeque::with_capacity(policy.capacity),
+            start: (pool.trade_count(), Instant::now()),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = &Checkpoint> {
+        self.ring.iter()
+    }
+
+    fn is_due(&self, trade_count: u64, now: Instant) -> bool {
+        let (last_seq, last_taken_at) = self
+            .ring
+            .back()
+            .map_or(self.start, |last| (last.seq(), last.taken_at));
+        let by_trades = self
+            .policy
+            .every_trades
+            .is_some_and(|every| every > 0 && trade_count - last_seq >= every);
+        let by_time = self
+            .policy
+            .every_interval
+            .is_some_and(|every| now.duration_since(last_taken_at) >= every);
+        by_trades || by_time
+    }
+
+    /// Discards every checkpoint and starts over from the current state of `pool`.
+    pub(crate) fn reset<C: Curve>(&mut self, pool: &LiquidityPool<C>) {
+        *self = Self::new(self.policy, pool);
+    }
+
+    /// Records a checkpoint of `pool`, which just applied a trade, if the policy says one is
+    /// due. Only trades are observed, so that replaying history from a checkpoint never
+    /// applies a liquidity change the snapshot already holds.
+    pub(crate) fn observe<C: Curve>(&mut self, pool: &LiquidityPool<C>) {
+        let now = Instant::now();
+        if self.policy.capacity == 0 || !self.is_due(pool.trade_count(), now) {
+            return;
+        }
+        if self.ring.len() == self.policy.capacity {
+            self.ring.pop_front();
+        }
+        self.ring.push_back(Checkpoint {
+            taken_at: now,
+            snapshot: PoolSnapshot::of(pool),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_every_n_trades() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_checkpoints(CheckpointPolicy {
+            every_trades: Some(2),
+            every_interval: None,
+            capacity: 3,
+        });
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        for _ in 0..10 {
+            pool.buy(token_amount, None).unwrap();
+        }
+        let seqs: Vec<u64> = pool.checkpoints().map(Checkpoint::seq).collect();
+        assert_eq!(seqs, vec![6, 8, 10]);
+
+        let mut resumed = pool.checkpoints().next().unwrap().snapshot.to_pool();
+        for _ in 0..4 {
+            resumed.buy(token_amount, None).unwrap();
+        }
+        assert_eq!(resumed.get_native_reserve(), pool.get_native_reserve());
+        assert_eq!(resumed.trade_count(), pool.trade_count());
+    }
+
+    #[test]
+    fn test_checkpoint_by_interval() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_checkpoints(CheckpointPolicy {
+            every_trades: None,
+            every_interval: Some(Duration::ZERO),
+            capacity: 8,
+        });
+        pool.sell(10u64.pow(6), None).unwrap();
+        pool.sell(10u64.pow(6), None).unwrap();
+        assert_eq!(pool.checkpoints().count(), 2);
+        pool.disable_checkpoints();
+        assert_eq!(pool.checkpoints().count(), 0);
+    }
+}
@@ -0,0 +1,5 @@
+//! Formulas of deployed AMM programs, reproduced to the unit so quotes computed off-chain
+//! match what the program executes.
+
+pub mod raydium_cpmm;
+pub mod uniswap_v2;
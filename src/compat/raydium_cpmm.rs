@@ -0,0 +1,348 @@
+//! Raydium CP-Swap (CPMM) swap math, as in the program's `CurveCalculator`.
+//!
+//! The trade fee is taken from the input at `trade_fee_rate` over [`FEE_RATE_DENOMINATOR`],
+//! rounded up, and the rest trades along `x * y = k`: `amount_out` rounds down for exact-input
+//! swaps and the input rounds up for exact-output ones. Protocol and fund fees are shares of
+//! the trade fee, rounded down, that stay in the vaults but leave the reserves, so only the
+//! remainder of the fee accrues to LPs.
+
+use crate::{Direction, PoolError, Result};
+
+/// Denominator of every rate of an [`AmmConfig`].
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Fee rates of a pool, as stored in its `AmmConfig` account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmmConfig {
+    /// Share of the input charged as trade fee.
+    pub trade_fee_rate: u64,
+    /// Share of the trade fee owed to the protocol.
+    pub protocol_fee_rate: u64,
+    /// Share of the trade fee owed to the fund.
+    pub fund_fee_rate: u64,
+}
+
+impl AmmConfig {
+    /// A 0.25% trade fee, of which 12% goes to the protocol and 4% to the fund.
+    pub const STANDARD: Self = Self {
+        trade_fee_rate: 2_500,
+        protocol_fee_rate: 120_000,
+        fund_fee_rate: 40_000,
+    };
+
+    fn is_valid(&self) -> bool {
+        self.trade_fee_rate < FEE_RATE_DENOMINATOR
+            && self.protocol_fee_rate + self.fund_fee_rate <= FEE_RATE_DENOMINATOR
+    }
+}
+
+/// Amounts of a swap, as the program's `SwapResult`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapResult {
+    /// Paid in, trade fee included.
+    pub source_amount_swapped: u64,
+    /// Paid out.
+    pub destination_amount_swapped: u64,
+    pub trade_fee: u64,
+    /// Part of the trade fee owed to the protocol.
+    pub protocol_fee: u64,
+    /// Part of the trade fee owed to the fund.
+    pub fund_fee: u64,
+}
+
+/// Returns the swap of `amount_in` into reserves `source_reserve` and `destination_reserve`,
+/// as `CurveCalculator::swap_base_input`.
+pub fn swap_base_input(
+    amount_in: u64,
+    source_reserve: u64,
+    destination_reserve: u64,
+    config: &AmmConfig,
+) -> Result<SwapResult> {
+    check(amount_in, source_reserve, destination_reserve, config)?;
+    let trade_fee = ceil_div(amount_in as u128, config.trade_fee_rate)?;
+    let amount_in_less_fees = amount_in as u128 - trade_fee;
+    let destination_amount_swapped = amount_in_less_fees * destination_reserve as u128
+        / (source_reserve as u128 + amount_in_less_fees);
+    result(
+        amount_in as u128,
+        destination_amount_swapped,
+        trade_fee,
+        config,
+    )
+}
+
+/// Returns the swap paying out exactly `amount_out` from reserves `source_reserve` and
+/// `destination_reserve`, as `CurveCalculator::swap_base_output`. Fails with
+/// [`PoolError::InsufficientPoolFunds`] unless `amount_out` is below `destination_reserve`.
+pub fn swap_base_output(
+    amount_out: u64,
+    source_reserve: u64,
+    destination_reserve: u64,
+    config: &AmmConfig,
+) -> Result<SwapResult> {
+    check(amount_out, source_reserve, destination_reserve, config)?;
+    if amount_out >= destination_reserve {
+        return Err(PoolError::InsufficientPoolFunds {
+            requested: amount_out,
+            available: destination_reserve,
+        });
+    }
+    let source_amount_swapped = checked_ceil_div(
+        source_reserve as u128 * amount_out as u128,
+        (destination_reserve - amount_out) as u128,
+    );
+    // The input before the fee, so that taking the fee from it leaves the swapped amount.
+    let fee_denominator = (FEE_RATE_DENOMINATOR - config.trade_fee_rate) as u128;
+    let amount_in = source_amount_swapped
+        .checked_mul(DENOMINATOR)
+        .ok_or(PoolError::Overflow)?
+        .div_ceil(fee_denominator);
+    let trade_fee = ceil_div(amount_in, config.trade_fee_rate)?;
+    result(amount_in, amount_out as u128, trade_fee, config)
+}
+
+fn check(
+    amount: u64,
+    source_reserve: u64,
+    destination_reserve: u64,
+    config: &AmmConfig,
+) -> Result<()> {
+    if amount == 0 || !config.is_valid() {
+        return Err(PoolError::InvalidAmount);
+    }
+    if source_reserve == 0 || destination_reserve == 0 {
+        return Err(PoolError::ZeroLiquidity);
+    }
+    Ok(())
+}
+
+fn result(
+    amount_in: u128,
+    amount_out: u128,
+    trade_fee: u128,
+    config: &AmmConfig,
+) -> Result<SwapResult> {
+    let to_u64 = |amount: u128| u64::try_from(amount).map_err(|_| PoolError::Overflow);
+    Ok(SwapResult {
+        source_amount_swapped: to_u64(amount_in)?,
+        destination_amount_swapped: to_u64(amount_out)?,
+        trade_fee: to_u64(trade_fee)?,
+        protocol_fee: to_u64(trade_fee * config.protocol_fee_rate as u128 / DENOMINATOR)?,
+        fund_fee: to_u64(trade_fee * config.fund_fee_rate as u128 / DENOMINATOR)?,
+    })
+}
+
+const DENOMINATOR: u128 = FEE_RATE_DENOMINATOR as u128;
+
+/// `amount * rate / FEE_RATE_DENOMINATOR` rounded up, as the program's fee helper.
+fn ceil_div(amount: u128, rate: u64) -> Result<u128> {
+    amount
+        .checked_mul(rate as u128)
+        .map(|product| product.div_ceil(DENOMINATOR))
+        .ok_or(PoolError::Overflow)
+}
+
+/// `numerator / denominator` rounded up, except that a quotient below one rounds to the nearest
+/// integer, as `spl_math`'s `checked_ceil_div`.
+fn checked_ceil_div(numerator: u128, denominator: u128) -> u128 {
+    if numerator < denominator {
+        u128::from(numerator * 2 >= denominator)
+    } else {
+        numerator.div_ceil(denominator)
+    }
+}
+
+/// Native/token CP-Swap pool, with the protocol and fund fees owed tracked apart from the
+/// reserves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pool {
+    pub config: AmmConfig,
+    /// Native vault balance, less the fees owed.
+    pub native_reserve: u64,
+    /// Token vault balance, less the fees owed.
+    pub token_reserve: u64,
+    /// Protocol fees owed, `(native, token)`.
+    pub protocol_fees: (u64, u64),
+    /// Fund fees owed, `(native, token)`.
+    pub fund_fees: (u64, u64),
+}
+
+impl Pool {
+    pub fn new(config: AmmConfig, native_reserve: u64, token_reserve: u64) -> Result<Self> {
+        if !config.is_valid() {
+            return Err(PoolError::InvalidAmount);
+        }
+        if native_reserve == 0 || token_reserve == 0 {
+            return Err(PoolError::ZeroLiquidity);
+        }
+        Ok(Self {
+            config,
+            native_reserve,
+            token_reserve,
+            protocol_fees: (0, 0),
+            fund_fees: (0, 0),
+        })
+    }
+
+    /// Reserves of the side paid in and of the side paid out.
+    fn reserves(&self, direction: Direction) -> (u64, u64) {
+        match direction {
+            Direction::BuyToken => (self.native_reserve, self.token_reserve),
+            Direction::SellToken => (self.token_reserve, self.native_reserve),
+        }
+    }
+
+    /// Quotes paying `amount_in` in `direction`: native currency for tokens when buying.
+    pub fn quote_base_input(&self, direction: Direction, amount_in: u64) -> Result<SwapResult> {
+        let (source_reserve, destination_reserve) = self.reserves(direction);
+        swap_base_input(amount_in, source_reserve, destination_reserve, &self.config)
+    }
+
+    /// Quotes receiving exactly `amount_out` in `direction`.
+    pub fn quote_base_output(&self, direction: Direction, amount_out: u64) -> Result<SwapResult> {
+        let (source_reserve, destination_reserve) = self.reserves(direction);
+        swap_base_output(
+            amount_out,
+            source_reserve,
+            destination_reserve,
+            &self.config,
+        )
+    }
+
+    /// Swaps `amount_in` in `direction`, failing with [`PoolError::SlippageExceeded`] if it
+    /// returns less than `min_out`.
+    pub fn swap_base_input(
+        &mut self,
+        direction: Direction,
+        amount_in: u64,
+        min_out: Option<u64>,
+    ) -> Result<SwapResult> {
+        let swap = self.quote_base_input(direction, amount_in)?;
+        if let Some(min_out) = min_out.filter(|&min_out| swap.destination_amount_swapped < min_out)
+        {
+            return Err(PoolError::SlippageExceeded {
+                limit: min_out,
+                actual: swap.destination_amount_swapped,
+            });
+        }
+        self.settle(direction, &swap)?;
+        Ok(swap)
+    }
+
+    /// Swaps in `direction` for exactly `amount_out`, failing with
+    /// [`PoolError::SlippageExceeded`] if that costs more than `max_in`.
+    pub fn swap_base_output(
+        &mut self,
+        direction: Direction,
+        amount_out: u64,
+        max_in: Option<u64>,
+    ) -> Result<SwapResult> {
+        let swap = self.quote_base_output(direction, amount_out)?;
+        if let Some(max_in) = max_in.filter(|&max_in| swap.source_amount_swapped > max_in) {
+            return Err(PoolError::SlippageExceeded {
+                limit: max_in,
+                actual: swap.source_amount_swapped,
+            });
+        }
+        self.settle(direction, &swap)?;
+        Ok(swap)
+    }
+
+    /// Moves the swapped amounts through the reserves, setting aside the fees owed on the
+    /// input side.
+    fn settle(&mut self, direction: Direction, swap: &SwapResult) -> Result<()> {
+        let (source_reserve, destination_reserve) = self.reserves(direction);
+        let source_reserve = source_reserve
+            .checked_add(swap.source_amount_swapped - swap.protocol_fee - swap.fund_fee)
+            .ok_or(PoolError::Overflow)?;
+        let destination_reserve = destination_reserve - swap.destination_amount_swapped;
+        let (protocol_fees, fund_fees) = match direction {
+            Direction::BuyToken => {
+                self.native_reserve = source_reserve;
+                self.token_reserve = destination_reserve;
+                (&mut self.protocol_fees.0, &mut self.fund_fees.0)
+            }
+            Direction::SellToken => {
+                self.token_reserve = source_reserve;
+                self.native_reserve = destination_reserve;
+                (&mut self.protocol_fees.1, &mut self.fund_fees.1)
+            }
+        };
+        *protocol_fees += swap.protocol_fee;
+        *fund_fees += swap.fund_fee;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_base_input() {
+        let swap = swap_base_input(
+            10_000_000,
+            10u64.pow(9),
+            10u64.pow(12),
+            &AmmConfig::STANDARD,
+        )
+        .unwrap();
+        // The fee is 0.25% of the input rounded up, 12% and 4% of which are set aside.
+        assert_eq!(
+            swap,
+            SwapResult {
+                source_amount_swapped: 10_000_000,
+                destination_amount_swapped: 9_876_482_091,
+                trade_fee: 25_000,
+                protocol_fee: 3_000,
+                fund_fee: 1_000,
+            }
+        );
+        assert_eq!(
+            swap_base_input(1, 0, 1, &AmmConfig::STANDARD),
+            Err(PoolError::ZeroLiquidity)
+        );
+    }
+
+    #[test]
+    fn test_swap_base_output() {
+        let mut pool = Pool::new(AmmConfig::STANDARD, 10u64.pow(9), 10u64.pow(12)).unwrap();
+        let swap = pool
+            .swap_base_output(Direction::BuyToken, 5 * 10u64.pow(9), None)
+            .unwrap();
+        assert_eq!(swap.source_amount_swapped, 5_037_721);
+        assert_eq!(swap.trade_fee, 12_595);
+        // Paying the same amount in gets at least the requested output.
+        let reference = Pool::new(AmmConfig::STANDARD, 10u64.pow(9), 10u64.pow(12)).unwrap();
+        assert!(
+            reference
+                .quote_base_input(Direction::BuyToken, swap.source_amount_swapped)
+                .unwrap()
+                .destination_amount_swapped
+                >= 5 * 10u64.pow(9)
+        );
+        assert_eq!(
+            pool.native_reserve,
+            10u64.pow(9) + swap.source_amount_swapped - swap.protocol_fee - swap.fund_fee
+        );
+        assert_eq!(pool.protocol_fees, (swap.protocol_fee, 0));
+
+        let sold = pool
+            .swap_base_input(Direction::SellToken, 10u64.pow(9), None)
+            .unwrap();
+        assert_eq!(pool.fund_fees.1, sold.fund_fee);
+        assert_eq!(
+            pool.swap_base_output(Direction::SellToken, 10u64.pow(6), Some(1)),
+            Err(PoolError::SlippageExceeded {
+                limit: 1,
+                actual: pool
+                    .quote_base_output(Direction::SellToken, 10u64.pow(6))
+                    .unwrap()
+                    .source_amount_swapped
+            })
+        );
+    }
+}
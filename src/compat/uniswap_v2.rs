@@ -0,0 +1,279 @@
+//! Uniswap V2 swap math, as in `UniswapV2Library` and enforced by `UniswapV2Pair::swap`.
+//!
+//! A swap pays a 0.3% fee on its input, kept in the reserves: `getAmountOut` is
+//! `amountIn * 997 * reserveOut / (reserveIn * 1000 + amountIn * 997)` rounded down, and
+//! `getAmountIn` is `reserveIn * amountOut * 1000 / ((reserveOut - amountOut) * 997)` rounded
+//! down plus one. Amounts and reserves are `u128`, which holds the pair's `uint112` reserves;
+//! intermediate products are computed over 256 bits as in Solidity, and anything that would
+//! not fit fails with [`PoolError::Overflow`] where the contract would revert, including a
+//! [`Pair`] swap that would push a reserve above [`MAX_RESERVE`].
+
+use alloc::vec::Vec;
+
+use crate::curve::mul_div;
+use crate::{Direction, PoolError, Result};
+
+/// Fee numerator, over [`FEE_DENOMINATOR`]: the input left after the 0.3% fee.
+pub const FEE_NUMERATOR: u128 = 997;
+pub const FEE_DENOMINATOR: u128 = 1000;
+/// Largest reserve a pair stores, `uint112(-1)`: `UniswapV2Pair::_update` reverts with
+/// `OVERFLOW` above it.
+pub const MAX_RESERVE: u128 = (1 << 112) - 1;
+
+/// Returns the output of swapping `amount_in` into a pair holding `reserve_in` and
+/// `reserve_out`, as `UniswapV2Library.getAmountOut`.
+pub fn get_amount_out(amount_in: u128, reserve_in: u128, reserve_out: u128) -> Result<u128> {
+    if amount_in == 0 {
+        return Err(PoolError::InvalidAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(PoolError::ZeroLiquidity);
+    }
+    let amount_in_with_fee = amount_in
+        .checked_mul(FEE_NUMERATOR)
+        .ok_or(PoolError::Overflow)?;
+    let denominator = reserve_in
+        .checked_mul(FEE_DENOMINATOR)
+        .and_then(|reserve| reserve.checked_add(amount_in_with_fee))
+        .ok_or(PoolError::Overflow)?;
+    mul_div(amount_in_with_fee, reserve_out, denominator).ok_or(PoolError::Overflow)
+}
+
+/// Returns the input needed to take `amount_out` out of a pair holding `reserve_in` and
+/// `reserve_out`, as `UniswapV2Library.getAmountIn`. Fails with
+/// [`PoolError::InsufficientPoolFunds`] unless `amount_out` is below `reserve_out`.
+pub fn get_amount_in(amount_out: u128, reserve_in: u128, reserve_out: u128) -> Result<u128> {
+    if amount_out == 0 {
+        return Err(PoolError::InvalidAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(PoolError::ZeroLiquidity);
+    }
+    if amount_out >= reserve_out {
+        return Err(PoolError::InsufficientPoolFunds {
+            requested: saturate(amount_out),
+            available: saturate(reserve_out),
+        });
+    }
+    let numerator = amount_out
+        .checked_mul(FEE_DENOMINATOR)
+        .ok_or(PoolError::Overflow)?;
+    let denominator = (reserve_out - amount_out)
+        .checked_mul(FEE_NUMERATOR)
+        .ok_or(PoolError::Overflow)?;
+    mul_div(reserve_in, numerator, denominator)
+        .and_then(|amount_in| amount_in.checked_add(1))
+        .ok_or(PoolError::Overflow)
+}
+
+/// Returns the amount of the other asset worth `amount` at the pair's current ratio, without
+/// fee, as `UniswapV2Library.quote`, used when adding liquidity.
+pub fn quote(amount: u128, reserve: u128, reserve_other: u128) -> Result<u128> {
+    if amount == 0 {
+        return Err(PoolError::InvalidAmount);
+    }
+    if reserve == 0 || reserve_other == 0 {
+        return Err(PoolError::ZeroLiquidity);
+    }
+    mul_div(amount, reserve_other, reserve).ok_or(PoolError::Overflow)
+}
+
+/// Returns the amounts along a multi-hop route, `amount_in` first, as
+/// `UniswapV2Library.getAmountsOut`. Each hop is the `(reserve_in, reserve_out)` of its pair.
+pub fn get_amounts_out(amount_in: u128, hops: &[(u128, u128)]) -> Result<Vec<u128>> {
+    if hops.is_empty() {
+        return Err(PoolError::InvalidAmount);
+    }
+    let mut amounts = Vec::with_capacity(hops.len() + 1);
+    amounts.push(amount_in);
+    for &(reserve_in, reserve_out) in hops {
+        let amount = *amounts.last().expect("amounts start with the input");
+        amounts.push(get_amount_out(amount, reserve_in, reserve_out)?);
+    }
+    Ok(amounts)
+}
+
+/// Returns the amounts along a multi-hop route ending with `amount_out`, as
+/// `UniswapV2Library.getAmountsIn`, solved from the last hop backwards.
+pub fn get_amounts_in(amount_out: u128, hops: &[(u128, u128)]) -> Result<Vec<u128>> {
+    if hops.is_empty() {
+        return Err(PoolError::InvalidAmount);
+    }
+    let mut amounts = Vec::with_capacity(hops.len() + 1);
+    amounts.push(amount_out);
+    for &(reserve_in, reserve_out) in hops.iter().rev() {
+        let amount = *amounts.last().expect("amounts start with the output");
+        amounts.push(get_amount_in(amount, reserve_in, reserve_out)?);
+    }
+    amounts.reverse();
+    Ok(amounts)
+}
+
+/// Native/token pair trading with Uniswap V2 math, with the fee left in the reserves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pair {
+    pub native_reserve: u128,
+    pub token_reserve: u128,
+}
+
+impl Pair {
+    /// Reserves of the side paid in and of the side paid out.
+    fn reserves(&self, direction: Direction) -> (u128, u128) {
+        match direction {
+            Direction::BuyToken => (self.native_reserve, self.token_reserve),
+            Direction::SellToken => (self.token_reserve, self.native_reserve),
+        }
+    }
+
+    /// Output of paying `amount_in` in `direction`: tokens for native currency when buying.
+    pub fn amount_out(&self, direction: Direction, amount_in: u128) -> Result<u128> {
+        let (reserve_in, reserve_out) = self.reserves(direction);
+        get_amount_out(amount_in, reserve_in, reserve_out)
+    }
+
+    /// Input needed to receive `amount_out` in `direction`.
+    pub fn amount_in(&self, direction: Direction, amount_out: u128) -> Result<u128> {
+        let (reserve_in, reserve_out) = self.reserves(direction);
+        get_amount_in(amount_out, reserve_in, reserve_out)
+    }
+
+    /// Swaps `amount_in` in `direction`, failing with [`PoolError::SlippageExceeded`] if it
+    /// returns less than `min_out`. Returns the output.
+    pub fn swap_exact_in(
+        &mut self,
+        direction: Direction,
+        amount_in: u128,
+        min_out: Option<u128>,
+    ) -> Result<u128> {
+        let amount_out = self.amount_out(direction, amount_in)?;
+        if let Some(min_out) = min_out.filter(|&min_out| amount_out < min_out) {
+            return Err(PoolError::SlippageExceeded {
+                limit: saturate(min_out),
+                actual: saturate(amount_out),
+            });
+        }
+        self.settle(direction, amount_in, amount_out)?;
+        Ok(amount_out)
+    }
+
+    /// Swaps in `direction` for exactly `amount_out`, failing with
+    /// [`PoolError::SlippageExceeded`] if that costs more than `max_in`. Returns the input.
+    pub fn swap_exact_out(
+        &mut self,
+        direction: Direction,
+        amount_out: u128,
+        max_in: Option<u128>,
+    ) -> Result<u128> {
+        let amount_in = self.amount_in(direction, amount_out)?;
+        if let Some(max_in) = max_in.filter(|&max_in| amount_in > max_in) {
+            return Err(PoolError::SlippageExceeded {
+                limit: saturate(max_in),
+                actual: saturate(amount_in),
+            });
+        }
+        self.settle(direction, amount_in, amount_out)?;
+        Ok(amount_in)
+    }
+
+    /// Moves the reserves by a swap, failing with [`PoolError::Overflow`] like the pair's
+    /// `OVERFLOW` revert if the reserve paid into would exceed [`MAX_RESERVE`].
+    fn settle(&mut self, direction: Direction, amount_in: u128, amount_out: u128) -> Result<()> {
+        let (reserve_in, reserve_out) = self.reserves(direction);
+        let reserve_in = reserve_in
+            .checked_add(amount_in)
+            .filter(|&reserve| reserve <= MAX_RESERVE)
+            .ok_or(PoolError::Overflow)?;
+        let reserve_out = reserve_out - amount_out;
+        (self.native_reserve, self.token_reserve) = match direction {
+            Direction::BuyToken => (reserve_in, reserve_out),
+            Direction::SellToken => (reserve_out, reserve_in),
+        };
+        Ok(())
+    }
+}
+
+/// Converts to `u64` for error context, saturating at `u64::MAX`.
+fn saturate(amount: u128) -> u64 {
+    u64::try_from(amount).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const E18: u128 = 10u128.pow(18);
+
+    #[test]
+    fn test_golden_vectors() {
+        // `(amount_in, reserve_in, reserve_out, amount_out)` from the swap cases of the
+        // Uniswap V2 core test suite, the largest outputs `UniswapV2Pair::swap` accepts.
+        let cases = [
+            (1, 5, 10, 1_662_497_915_624_478_906),
+            (1, 10, 5, 453_305_446_940_074_565),
+            (2, 5, 10, 2_851_015_155_847_869_602),
+            (2, 10, 5, 831_248_957_812_239_453),
+            (1, 10, 10, 906_610_893_880_149_131),
+            (1, 100, 100, 987_158_034_397_061_298),
+            (1, 1000, 1000, 996_006_981_039_903_216),
+        ];
+        for (amount_in, reserve_in, reserve_out, amount_out) in cases {
+            let (amount_in, reserve_in, reserve_out) =
+                (amount_in * E18, reserve_in * E18, reserve_out * E18);
+            assert_eq!(
+                get_amount_out(amount_in, reserve_in, reserve_out),
+                Ok(amount_out)
+            );
+            let needed = get_amount_in(amount_out, reserve_in, reserve_out).unwrap();
+            assert!(needed <= amount_in);
+            assert!(get_amount_out(needed, reserve_in, reserve_out).unwrap() >= amount_out);
+        }
+        // The periphery library tests.
+        assert_eq!(get_amount_out(2, 100, 100), Ok(1));
+        assert_eq!(get_amount_in(1, 100, 100), Ok(2));
+        assert_eq!(quote(1, 100, 200), Ok(2));
+        assert_eq!(get_amounts_out(2, &[(100, 100)]), Ok(vec![2, 1]));
+        assert_eq!(get_amounts_in(1, &[(100, 100)]), Ok(vec![2, 1]));
+        assert_eq!(get_amount_out(2, 0, 100), Err(PoolError::ZeroLiquidity));
+        assert_eq!(get_amount_in(0, 100, 100), Err(PoolError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_pair_swaps() {
+        let mut pair = Pair {
+            native_reserve: 5 * E18,
+            token_reserve: 10 * E18,
+        };
+        let received = pair
+            .swap_exact_in(Direction::BuyToken, E18, Some(1_662_497_915_624_478_906))
+            .unwrap();
+        assert_eq!(received, 1_662_497_915_624_478_906);
+        assert_eq!(pair.native_reserve, 6 * E18);
+        // The fee stays in the reserves, so `k` grows.
+        assert!(mul_div(pair.native_reserve, pair.token_reserve, E18).unwrap() > 50 * E18);
+
+        let paid = pair
+            .swap_exact_out(Direction::SellToken, E18, None)
+            .unwrap();
+        assert_eq!(pair.token_reserve, 10 * E18 - received + paid);
+        assert!(matches!(
+            pair.swap_exact_out(Direction::BuyToken, 100 * E18, None),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: u64::MAX,
+                ..
+            })
+        ));
+
+        let mut full = Pair {
+            native_reserve: MAX_RESERVE - E18,
+            token_reserve: 10 * E18,
+        };
+        assert_eq!(
+            full.swap_exact_in(Direction::BuyToken, E18 + 1, None),
+            Err(PoolError::Overflow)
+        );
+        assert_eq!(full.native_reserve, MAX_RESERVE - E18);
+        assert!(full.swap_exact_in(Direction::BuyToken, E18, None).is_ok());
+        assert_eq!(full.native_reserve, MAX_RESERVE);
+    }
+}
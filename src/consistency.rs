@@ -0,0 +1,164 @@
+use alloc::vec::Vec;
+
+use crate::{LiquidityPool, TradeOp};
+
+/// A broken identity between two pool APIs, found by [`LiquidityPool::verify_consistency`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConsistencyViolation {
+    /// `larger` trades more tokens than `smaller` in the same direction but is quoted less
+    /// native currency.
+    NonMonotoneQuote { smaller: TradeOp, larger: TradeOp },
+    /// Executing `op` on a copy of the pool did not match its simulation.
+    SimulateExecuteMismatch { op: TradeOp },
+    /// Spending `native_amount` bought `token_amount` tokens, but quoting those tokens back
+    /// exceeds the budget, or leaves more of it unspent than one more token or one native
+    /// unit would cost, whichever is larger.
+    InverseMismatch {
+        native_amount: u64,
+        token_amount: u64,
+    },
+    /// [`calculate_tokens_needed_for_native`](LiquidityPool::calculate_tokens_needed_for_native)
+    /// asked to sell `token_amount` tokens for `native_amount`, but that sale pays less, or one
+    /// token fewer would already pay enough.
+    ExactOutMismatch {
+        native_amount: u64,
+        token_amount: u64,
+    },
+    /// [`calculate_tokens_received`](LiquidityPool::calculate_tokens_received) differs from
+    /// [`spend_native`](LiquidityPool::spend_native) by more than one token.
+    ExactInMismatch {
+        native_amount: u64,
+        calculated: u64,
+        spent: u64,
+    },
+}
+
+/// Powers of two from one up to `max`.
+fn sizes(max: u64) -> impl Iterator<Item = u64> {
+    (0..u64::BITS)
+        .map(|shift| 1u64 << shift)
+        .take_while(move |&size| size <= max)
+}
+
+impl LiquidityPool {
+    /// Checks cross-API identities on trades of geometrically increasing size: quotes grow with
+    /// trade size, simulations match executions, and exact-in and exact-out trades are inverses
+    /// within one unit. Returns every violation found, so custom configurations can be
+    /// asserted at startup.
+    pub fn verify_consistency(&self) -> Vec<ConsistencyViolation> {
+        let mut violations = Vec::new();
+        let buys = sizes(self.token_reserve / 2).map(|token_amount| TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        });
+        let sells = sizes(self.token_reserve).map(|token_amount| TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        });
+        for ops in [buys.collect::<Vec<_>>(), sells.collect()] {
+            let mut previous: Option<(TradeOp, u64)> = None;
+            for op in ops {
+                let simulated = self.simulate(&op);
+                let mut pool = self.clone();
+                let executed = pool.execute(&op);
+                let matches = match (&simulated, &executed) {
+                    (Ok(projection), Ok(native_amount)) => {
+                        projection.native_amount == *native_amount
+                            && projection.native_reserve == pool.native_reserve
+                            && projection.token_reserve == pool.token_reserve
+                    }
+                    (Err(simulated), Err(executed)) => simulated == executed,
+                    _ => false,
+                };
+                if !matches {
+                    violations.push(ConsistencyViolation::SimulateExecuteMismatch { op });
+                }
+                let Ok(projection) = simulated else { continue };
+                if let Some((smaller, quote)) = previous {
+                    if projection.native_amount < quote {
+                        violations.push(ConsistencyViolation::NonMonotoneQuote {
+                            smaller,
+                            larger: op,
+                        });
+                    }
+                }
+                previous = Some((op, projection.native_amount));
+            }
+        }
+
+        for native_amount in sizes(self.native_reserve) {
+            let Ok(spend) = self.simulate_spend_native(native_amount) else {
+                continue;
+            };
+            let quote = |token_amount| {
+                self.quote(&TradeOp::Buy {
+                    token_amount,
+                    max_native: None,
+                })
+            };
+            let spent = if spend.token_amount == 0 {
+                Ok(0)
+            } else {
+                quote(spend.token_amount)
+            };
+            // The unspent budget must not buy another token, give or take one native unit.
+            let consistent = spent.is_ok_and(|spent| {
+                let next_token_cost = quote(spend.token_amount + 1)
+                    .map_or(u64::MAX, |next| next.saturating_sub(spent));
+                spent <= native_amount && native_amount - spent <= next_token_cost.max(1)
+            });
+            if !consistent {
+                violations.push(ConsistencyViolation::InverseMismatch {
+                    native_amount,
+                    token_amount: spend.token_amount,
+                });
+            }
+            if let Ok(calculated) = self.calculate_tokens_received(native_amount) {
+                if calculated.abs_diff(spend.token_amount) > 1 {
+                    violations.push(ConsistencyViolation::ExactInMismatch {
+                        native_amount,
+                        calculated,
+                        spent: spend.token_amount,
+                    });
+                }
+            }
+        }
+        for native_amount in sizes(self.native_reserve - 1) {
+            let Ok(token_amount) = self.calculate_tokens_needed_for_native(native_amount) else {
+                continue;
+            };
+            let sell = |token_amount| {
+                self.quote(&TradeOp::Sell {
+                    token_amount,
+                    min_native: None,
+                })
+            };
+            let enough = sell(token_amount).is_ok_and(|received| received >= native_amount);
+            let minimal = token_amount == 1
+                || sell(token_amount - 1).is_ok_and(|received| received < native_amount);
+            if !enough || !minimal {
+                violations.push(ConsistencyViolation::ExactOutMismatch {
+                    native_amount,
+                    token_amount,
+                });
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_consistency() {
+        assert_eq!(LiquidityPool::default().verify_consistency(), Vec::new());
+        let mut pool = LiquidityPool::new(1_000, 7).unwrap();
+        pool.buy(3, None).unwrap();
+        assert_eq!(pool.verify_consistency(), Vec::new());
+        let expensive = LiquidityPool::new(10u64.pow(15), 1_000).unwrap();
+        assert_eq!(expensive.verify_consistency(), Vec::new());
+    }
+}
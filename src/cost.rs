@@ -0,0 +1,155 @@
+use crate::{Direction, LiquidityPool, Result, TradeOp};
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Execution costs charged on top of the curve by a given venue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CostModel {
+    /// Swap fee charged by the venue on top of the pool's own, in basis points of the native
+    /// amount traded.
+    pub fee_bps: u32,
+    /// Fixed cost paid per trade (network and priority fees), in native units.
+    pub fixed_cost: u64,
+}
+
+/// Breakdown of the all-in cost of a trade, in native units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CostReport {
+    pub direction: Direction,
+    pub token_amount: u64,
+    /// Native amount the trade would cost (buy) or return (sell) at the current spot price.
+    pub spot_native: u64,
+    /// Native amount the curve actually charges (buy) or pays (sell).
+    pub curve_native: u64,
+    /// Cost of moving along the curve compared to trading at the spot price.
+    pub slippage: u64,
+    /// Pool swap fee plus the venue fee.
+    pub fee: u64,
+    pub fixed_cost: u64,
+    /// Native paid in total (buy) or received net of all costs (sell).
+    pub total_native: u64,
+    /// `total_native / token_amount`, the all-in price per token unit.
+    pub effective_price: f64,
+}
+
+impl LiquidityPool {
+    /// Reports the all-in cost of trading `token_amount` tokens in `direction` on a venue
+    /// charging `cost_model`, combining curve slippage, swap fee and fixed costs.
+    pub fn total_cost_report(
+        &self,
+        token_amount: u64,
+        direction: Direction,
+        cost_model: CostModel,
+    ) -> Result<CostReport> {
+        let op = match direction {
+            Direction::BuyToken => TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            },
+            Direction::SellToken => TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            },
+        };
+        let projection = self.simulate(&op)?;
+        let curve_native = match direction {
+            Direction::BuyToken => projection.native_amount - projection.fee,
+            Direction::SellToken => projection.native_amount + projection.fee,
+        };
+        let spot_native = (token_amount as u128 * self.native_reserve as u128
+            / self.token_reserve as u128) as u64;
+        let fee = projection.fee
+            + (curve_native as u128 * cost_model.fee_bps as u128).div_ceil(BPS_DENOMINATOR) as u64;
+        let (slippage, total_native) = match direction {
+            Direction::BuyToken => (
+                curve_native.saturating_sub(spot_native),
+                curve_native
+                    .saturating_add(fee)
+                    .saturating_add(cost_model.fixed_cost),
+            ),
+            Direction::SellToken => (
+                spot_native.saturating_sub(curve_native),
+                curve_native
+                    .saturating_sub(fee)
+                    .saturating_sub(cost_model.fixed_cost),
+            ),
+        };
+        Ok(CostReport {
+            direction,
+            token_amount,
+            spot_native,
+            curve_native,
+            slippage,
+            fee,
+            fixed_cost: cost_model.fixed_cost,
+            total_native,
+            effective_price: total_native as f64 / token_amount as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buy_cost_breakdown() {
+        let pool = LiquidityPool::default();
+        let token_amount = 10_000_000 * 10u64.pow(6);
+        let cost_model = CostModel {
+            fee_bps: 30,
+            fixed_cost: 5_000,
+        };
+        let report = pool
+            .total_cost_report(token_amount, Direction::BuyToken, cost_model)
+            .unwrap();
+        assert_eq!(
+            report.curve_native,
+            pool.simulate_buy(token_amount, None).unwrap()
+        );
+        assert_eq!(report.spot_native, 10_000_000);
+        assert_eq!(report.slippage, report.curve_native - report.spot_native);
+        assert_eq!(report.fee, (report.curve_native * 30).div_ceil(10_000));
+        assert_eq!(
+            report.total_native,
+            report.curve_native + report.fee + report.fixed_cost
+        );
+        // A pool charging the fee itself reports the same breakdown.
+        let fee_pool = pool.with_fee(30).unwrap();
+        let venue = CostModel {
+            fee_bps: 0,
+            fixed_cost: 5_000,
+        };
+        assert_eq!(
+            fee_pool.total_cost_report(token_amount, Direction::BuyToken, venue),
+            Ok(report)
+        );
+    }
+
+    #[test]
+    fn test_sell_costs_reduce_proceeds() {
+        let pool = LiquidityPool::default();
+        let token_amount = 10_000_000 * 10u64.pow(6);
+        let free = pool
+            .total_cost_report(token_amount, Direction::SellToken, CostModel::default())
+            .unwrap();
+        let costly = pool
+            .total_cost_report(
+                token_amount,
+                Direction::SellToken,
+                CostModel {
+                    fee_bps: 100,
+                    fixed_cost: 1_000,
+                },
+            )
+            .unwrap();
+        assert_eq!(free.total_native, free.curve_native);
+        assert_eq!(
+            costly.total_native,
+            free.curve_native - costly.fee - costly.fixed_cost
+        );
+        assert!(costly.effective_price < free.effective_price);
+    }
+}
@@ -0,0 +1,421 @@
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::{Direction, PoolError};
+
+/// Reserves of a pool together with the invariant its trades preserve, as seen by a [`Curve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveState {
+    pub native_reserve: u64,
+    pub token_reserve: u64,
+    /// [`Curve::invariant`] of the reserves when they were last set by something other than a
+    /// trade, `k` for a constant-product curve.
+    pub invariant: u128,
+    /// Rounding the pool applies to trades. Curves may ignore it; [`ConstantProduct`] honours
+    /// it.
+    pub rounding: RoundingPolicy,
+}
+
+/// Direction in which an integer division is rounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum Rounding {
+    #[default]
+    Down,
+    Up,
+}
+
+impl Rounding {
+    /// Returns `num / den` rounded in this direction. Panics if `den` is zero.
+    pub fn div(self, num: u128, den: u128) -> u128 {
+        match self {
+            Rounding::Down => num / den,
+            Rounding::Up => num.div_ceil(den),
+        }
+    }
+}
+
+/// How a pool rounds the divisions of a trade, to reproduce the amounts of a given on-chain
+/// program exactly.
+///
+/// `buy` and `sell` round the reserve a trade leaves on the curve, the native reserve unless
+/// the trade is sized by its native amount. Rounding it [`Up`](Rounding::Up) makes buyers pay
+/// more and sellers receive less, so value never leaks out of the pool. `fee` rounds the swap
+/// fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct RoundingPolicy {
+    pub buy: Rounding,
+    pub sell: Rounding,
+    pub fee: Rounding,
+}
+
+impl RoundingPolicy {
+    /// Reserves rounded down, the fee rounded up: what pools do unless told otherwise.
+    pub const DEFAULT: Self = Self {
+        buy: Rounding::Down,
+        sell: Rounding::Down,
+        fee: Rounding::Up,
+    };
+    /// Every rounding at the trader's expense.
+    pub const FAVOR_POOL: Self = Self {
+        buy: Rounding::Up,
+        sell: Rounding::Up,
+        fee: Rounding::Up,
+    };
+    /// Every rounding in the trader's favour.
+    pub const FAVOR_TRADER: Self = Self {
+        buy: Rounding::Down,
+        sell: Rounding::Down,
+        fee: Rounding::Down,
+    };
+
+    /// Returns whether trades may round a reserve in `rounding`'s direction.
+    fn rounds(&self, rounding: Rounding) -> bool {
+        self.buy == rounding || self.sell == rounding
+    }
+}
+
+impl Default for RoundingPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Pricing function of a pool: how much of one reserve trades for an amount of the other.
+///
+/// Pools keep the invariant fixed between liquidity changes and pass it back through
+/// [`CurveState`], so rounding does not accumulate across trades. Pools check themselves that
+/// trades leave the reserves non-empty and within their real part.
+pub trait Curve: Clone + fmt::Debug + Send + Sync {
+    /// Returns the invariant of the given reserves.
+    fn invariant(&self, native_reserve: u64, token_reserve: u64) -> Result<u128, PoolError>;
+
+    /// Returns what paying `amount_in` into the pool returns: tokens for native currency when
+    /// buying, native currency for tokens when selling.
+    fn amount_out(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_in: u64,
+    ) -> Result<u64, PoolError>;
+
+    /// Returns what must be paid into the pool to take `amount_out` out of it: native currency
+    /// for tokens when buying, tokens for native currency when selling.
+    fn amount_in(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_out: u64,
+    ) -> Result<u64, PoolError>;
+
+    /// Returns the marginal price of one token in native currency.
+    fn spot_price(&self, state: &CurveState) -> f64;
+
+    /// Returns whether the reserves of `state` lie on the curve of its invariant, up to the
+    /// rounding applied by trades. By default, the invariant of the reserves may differ from
+    /// `state.invariant` by less than the token reserve, falling short of it only if
+    /// `state.rounding` rounds reserves down and exceeding it only if it rounds them up.
+    fn on_curve(&self, state: &CurveState) -> bool {
+        let slack = state.token_reserve as u128;
+        self.invariant(state.native_reserve, state.token_reserve)
+            .is_ok_and(|invariant| match invariant.cmp(&state.invariant) {
+                Ordering::Equal => true,
+                Ordering::Less => {
+                    state.rounding.rounds(Rounding::Down) && state.invariant - invariant < slack
+                }
+                Ordering::Greater => {
+                    state.rounding.rounds(Rounding::Up) && invariant - state.invariant < slack
+                }
+            })
+    }
+}
+
+/// Returns `a * b / den` rounded down, computed over 256 bits, or `None` if `den` is zero or
+/// the quotient does not fit in 128 bits.
+pub(crate) fn mul_div(a: u128, b: u128, den: u128) -> Option<u128> {
+    mul_div_rem(a, b, den).map(|(quotient, _)| quotient)
+}
+
+/// [`mul_div`] along with the remainder of the division.
+pub(crate) fn mul_div_rem(a: u128, b: u128, den: u128) -> Option<(u128, u128)> {
+    const LOW: u128 = u64::MAX as u128;
+    let (a_high, a_low, b_high, b_low) = (a >> 64, a & LOW, b >> 64, b & LOW);
+    let (low_low, low_high, high_low) = (a_low * b_low, a_low * b_high, a_high * b_low);
+    let middle = (low_low >> 64) + (low_high & LOW) + (high_low & LOW);
+    let low = (low_low & LOW) | (middle << 64);
+    let high = a_high * b_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    if den == 0 || high >= den {
+        return None;
+    }
+    // Long division of the low half, one bit at a time, as the high half is below `den`.
+    let (mut remainder, mut quotient) = (high, 0u128);
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = remainder << 1 | (low >> bit & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= den {
+            remainder = remainder.wrapping_sub(den);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+/// The `x * y = k` curve, rounding reserves after every trade as set by
+/// [`CurveState::rounding`]. Every step is checked, so reserves that would leave `u64` fail
+/// with [`PoolError::Overflow`] instead of truncating.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConstantProduct;
+
+impl Curve for ConstantProduct {
+    fn invariant(&self, native_reserve: u64, token_reserve: u64) -> Result<u128, PoolError> {
+        Ok(native_reserve as u128 * token_reserve as u128)
+    }
+
+    fn amount_out(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_in: u64,
+    ) -> Result<u64, PoolError> {
+        let (reserve_in, reserve_out, rounding) = match direction {
+            Direction::BuyToken => (
+                state.native_reserve,
+                state.token_reserve,
+                state.rounding.buy,
+            ),
+            Direction::SellToken => (
+                state.token_reserve,
+                state.native_reserve,
+                state.rounding.sell,
+            ),
+        };
+        let new_reserve_in = reserve_in
+            .checked_add(amount_in)
+            .ok_or(PoolError::Overflow)?;
+        let new_reserve_out = u64::try_from(rounding.div(state.invariant, new_reserve_in as u128))
+            .map_err(|_| PoolError::Overflow)?;
+        // Rounding up may leave the reserve where it was for tiny trades, which then pay out
+        // nothing.
+        Ok(reserve_out.saturating_sub(new_reserve_out))
+    }
+
+    fn amount_in(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_out: u64,
+    ) -> Result<u64, PoolError> {
+        match direction {
+            Direction::BuyToken => {
+                // The token reserve cannot be emptied, as the invariant would need an infinite
+                // native reserve.
+                let new_token_reserve = state
+                    .token_reserve
+                    .checked_sub(amount_out)
+                    .filter(|&reserve| reserve > 0)
+                    .ok_or(PoolError::InsufficientPoolFunds {
+                        requested: amount_out,
+                        available: state.token_reserve,
+                    })?;
+                let new_native_reserve = u64::try_from(
+                    state
+                        .rounding
+                        .buy
+                        .div(state.invariant, new_token_reserve as u128),
+                )
+                .map_err(|_| PoolError::Overflow)?;
+                // Reserves left above the curve by rounding up may already cover tiny trades.
+                Ok(new_native_reserve.saturating_sub(state.native_reserve))
+            }
+            Direction::SellToken => {
+                if amount_out >= state.native_reserve {
+                    return Err(PoolError::InsufficientPoolFunds {
+                        requested: amount_out,
+                        available: state.native_reserve,
+                    });
+                }
+                // Selling `t` tokens pays `native_reserve - floor(k / (token_reserve + t))`, which
+                // reaches `amount_out` once `token_reserve + t > k / (native_reserve - amount_out + 1)`,
+                // or with `ceil` once `token_reserve + t >= k / (native_reserve - amount_out)`.
+                let max_native_reserve = (state.native_reserve - amount_out) as u128;
+                let min_token_reserve = match state.rounding.sell {
+                    Rounding::Down => state.invariant / (max_native_reserve + 1) + 1,
+                    Rounding::Up => state.invariant.div_ceil(max_native_reserve),
+                };
+                min_token_reserve
+                    .checked_sub(state.token_reserve as u128)
+                    .and_then(|amount| u64::try_from(amount).ok())
+                    .ok_or(PoolError::Overflow)
+            }
+        }
+    }
+
+    fn spot_price(&self, state: &CurveState) -> f64 {
+        state.native_reserve as f64 / state.token_reserve as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityPool;
+
+    /// Trades one token for one native unit.
+    #[derive(Debug, Clone)]
+    struct ConstantSum;
+
+    impl Curve for ConstantSum {
+        fn invariant(&self, native_reserve: u64, token_reserve: u64) -> Result<u128, PoolError> {
+            Ok(native_reserve as u128 + token_reserve as u128)
+        }
+
+        fn amount_out(
+            &self,
+            _: &CurveState,
+            _: Direction,
+            amount_in: u64,
+        ) -> Result<u64, PoolError> {
+            Ok(amount_in)
+        }
+
+        fn amount_in(
+            &self,
+            _: &CurveState,
+            _: Direction,
+            amount_out: u64,
+        ) -> Result<u64, PoolError> {
+            Ok(amount_out)
+        }
+
+        fn spot_price(&self, _: &CurveState) -> f64 {
+            1.0
+        }
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Some(u128::MAX));
+        assert_eq!(mul_div(u128::MAX, 6, 3), None);
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 80), Some(1 << 120));
+        assert_eq!(mul_div(7, 3, 2), Some(10));
+        assert_eq!(mul_div(7, 3, 0), None);
+    }
+
+    #[test]
+    fn test_custom_curve() {
+        let mut pool = LiquidityPool::with_curve(ConstantSum, 1_000, 1_000).unwrap();
+        assert_eq!(pool.buy(400, None), Ok(400));
+        assert_eq!(pool.sell(100, None), Ok(100));
+        assert_eq!(pool.get_native_reserve(), 1_300);
+        assert_eq!(pool.spot_price(), 1.0);
+        assert_eq!(
+            pool.sell(1_000, None),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: 1_000,
+                available: 700
+            })
+        );
+        assert_eq!(pool.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_u64_boundaries() {
+        let mut pool = LiquidityPool::new(1, u64::MAX).unwrap();
+        assert_eq!(
+            pool.simulate_buy(u64::MAX, None),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: u64::MAX,
+                available: u64::MAX
+            })
+        );
+        // The native reserve ends exactly at `u64::MAX`.
+        assert_eq!(pool.clone().buy(u64::MAX - 1, None), Ok(u64::MAX - 1));
+        assert_eq!(
+            pool.calculate_tokens_received(u64::MAX),
+            Err(PoolError::Overflow)
+        );
+
+        // One more unit of native reserve and the same trade no longer fits.
+        let mut deeper = LiquidityPool::new(2, u64::MAX).unwrap();
+        assert_eq!(deeper.buy(u64::MAX - 1, None), Err(PoolError::Overflow));
+        assert_eq!(deeper.get_native_reserve(), 2);
+
+        pool = LiquidityPool::new(u64::MAX, u64::MAX).unwrap();
+        assert_eq!(pool.sell(u64::MAX, None), Err(PoolError::Overflow));
+        assert_eq!(pool.buy(u64::MAX / 2, None), Err(PoolError::Overflow));
+        assert_eq!(pool.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_constant_product_round_trips() {
+        let state = CurveState {
+            native_reserve: 1_000,
+            token_reserve: 1_000,
+            invariant: 1_000_000,
+            rounding: RoundingPolicy::DEFAULT,
+        };
+        let curve = ConstantProduct;
+        assert_eq!(curve.amount_in(&state, Direction::BuyToken, 500), Ok(1_000));
+        assert_eq!(
+            curve.amount_out(&state, Direction::BuyToken, 1_000),
+            Ok(500)
+        );
+        assert_eq!(
+            curve.amount_out(&state, Direction::SellToken, 1_000),
+            Ok(500)
+        );
+        assert_eq!(curve.amount_in(&state, Direction::SellToken, 500), Ok(997));
+        assert_eq!(
+            curve.amount_in(&state, Direction::SellToken, 1_000),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: 1_000,
+                available: 1_000
+            })
+        );
+        assert_eq!(curve.spot_price(&state), 1.0);
+    }
+
+    #[test]
+    fn test_rounding_policy() {
+        let mut state = CurveState {
+            native_reserve: 1_000,
+            token_reserve: 1_000,
+            invariant: 1_000_000,
+            rounding: RoundingPolicy::FAVOR_POOL,
+        };
+        let curve = ConstantProduct;
+        // 1_000_000 / 997 and 1_000_000 / 1_003 are rounded up rather than down.
+        assert_eq!(curve.amount_in(&state, Direction::BuyToken, 3), Ok(4));
+        assert_eq!(curve.amount_out(&state, Direction::SellToken, 3), Ok(2));
+        assert_eq!(curve.amount_in(&state, Direction::SellToken, 2), Ok(3));
+        state.rounding = RoundingPolicy::DEFAULT;
+        assert_eq!(curve.amount_in(&state, Direction::BuyToken, 3), Ok(3));
+        assert_eq!(curve.amount_out(&state, Direction::SellToken, 3), Ok(3));
+
+        let mut pool = LiquidityPool::default()
+            .with_fee(25)
+            .unwrap()
+            .with_rounding(RoundingPolicy::FAVOR_POOL);
+        let k = pool.get_constant_product();
+        for token_amount in [7, 10u64.pow(12) + 3, 123_457, 10u64.pow(9) + 1] {
+            let trader = pool.clone().with_rounding(RoundingPolicy::FAVOR_TRADER);
+            let paid = pool.buy(token_amount, None).unwrap();
+            assert!(paid >= trader.simulate_buy(token_amount, None).unwrap());
+            let trader = pool.clone().with_rounding(RoundingPolicy::FAVOR_TRADER);
+            let received = pool.sell(token_amount / 2 + 1, None).unwrap();
+            assert!(received <= trader.quote_sell(token_amount / 2 + 1).unwrap().amount_out);
+            assert!(pool.get_native_reserve() as u128 * pool.get_token_reserve() as u128 >= k);
+            assert_eq!(pool.check_invariants(), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{Curve, LiquidityPool, PoolError, Result};
+
+/// Largest number of decimals supported, as `10^19` is the largest power of ten in a `u64`.
+pub const MAX_DECIMALS: u8 = 19;
+
+/// One of the two reserves of a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Reserve {
+    Native,
+    Token,
+}
+
+fn unit(decimals: u8) -> f64 {
+    10u64.pow(decimals as u32) as f64
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Sets the number of decimals of the native currency and the token, used to convert raw
+    /// amounts to and from the amounts shown to users. Both default to 0. Fails with
+    /// [`PoolError::InvalidAmount`] above [`MAX_DECIMALS`].
+    pub fn with_decimals(mut self, native_decimals: u8, token_decimals: u8) -> Result<Self> {
+        if native_decimals > MAX_DECIMALS || token_decimals > MAX_DECIMALS {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.native_decimals = native_decimals;
+        self.token_decimals = token_decimals;
+        Ok(self)
+    }
+
+    pub fn native_decimals(&self) -> u8 {
+        self.native_decimals
+    }
+
+    pub fn token_decimals(&self) -> u8 {
+        self.token_decimals
+    }
+
+    fn decimals(&self, reserve: Reserve) -> u8 {
+        match reserve {
+            Reserve::Native => self.native_decimals,
+            Reserve::Token => self.token_decimals,
+        }
+    }
+
+    /// Converts a user-facing amount of `reserve`, such as `1.5` SOL, to raw units, rounded to
+    /// the nearest unit.
+    pub fn from_ui_amount(&self, reserve: Reserve, ui_amount: f64) -> Result<u64> {
+        if !ui_amount.is_finite() || ui_amount < 0.0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        // Adding one half before truncating rounds non-negative amounts without `std`.
+        let amount = ui_amount * unit(self.decimals(reserve)) + 0.5;
+        if amount >= u64::MAX as f64 {
+            return Err(PoolError::Overflow);
+        }
+        Ok(amount as u64)
+    }
+
+    /// Converts a raw amount of `reserve` to the amount shown to users.
+    pub fn to_ui_amount(&self, reserve: Reserve, amount: u64) -> f64 {
+        amount as f64 / unit(self.decimals(reserve))
+    }
+
+    /// Returns [`market_price`](Self::market_price) in whole native units per whole token.
+    pub fn market_price_ui(&self) -> f64 {
+        self.market_price() * unit(self.token_decimals) / unit(self.native_decimals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_amounts() {
+        let pool = LiquidityPool::new(30 * 10u64.pow(9), 1_000_000 * 10u64.pow(6))
+            .unwrap()
+            .with_decimals(9, 6)
+            .unwrap();
+        assert_eq!(pool.from_ui_amount(Reserve::Native, 1.5), Ok(1_500_000_000));
+        assert_eq!(pool.to_ui_amount(Reserve::Token, 2_500_000), 2.5);
+        assert!((pool.market_price_ui() - 30.0 / 1_000_000.0).abs() < 1e-15);
+        assert_eq!(
+            pool.from_ui_amount(Reserve::Token, -1.0),
+            Err(PoolError::InvalidAmount)
+        );
+        assert_eq!(
+            pool.from_ui_amount(Reserve::Native, 1e12),
+            Err(PoolError::Overflow)
+        );
+        assert_eq!(
+            pool.with_decimals(20, 6).map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Differential testing against an exact reference model.
+//!
+//! [`ReferencePool`] performs the constant product math with arbitrary-precision rationals,
+//! charging the same swap fee, fee split and token tax as the pool it models, so replaying the
+//! same trades through it and through [`LiquidityPool`] measures exactly how much the integer
+//! implementation loses to rounding.
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::{LiquidityPool, PoolError, TokenTax, TradeOp, MAX_FEE_BPS};
+
+/// A constant product pool computed with exact rational arithmetic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferencePool {
+    native_reserve: BigRational,
+    token_reserve: BigRational,
+    constant_product: BigRational,
+    fee_bps: u16,
+    protocol_fee_share_bps: u16,
+    token_tax: TokenTax,
+}
+
+impl ReferencePool {
+    /// Creates a reference model of a pool without fees or token tax.
+    pub fn new(native_reserve: u64, token_reserve: u64) -> Self {
+        let native_reserve = to_rational(native_reserve);
+        let token_reserve = to_rational(token_reserve);
+        let constant_product = &native_reserve * &token_reserve;
+        Self {
+            native_reserve,
+            token_reserve,
+            constant_product,
+            fee_bps: 0,
+            protocol_fee_share_bps: 0,
+            token_tax: TokenTax::default(),
+        }
+    }
+
+    /// Creates a reference model starting from the current state of `pool`, including its
+    /// constant product, which may differ slightly from the product of its reserves, its fee
+    /// and fee split and its token tax. Virtual reserves price trades like real ones, and the
+    /// limits they set are left to the pool.
+    pub fn from_pool(pool: &LiquidityPool) -> Self {
+        Self {
+            native_reserve: to_rational(pool.get_native_reserve()),
+            token_reserve: to_rational(pool.get_token_reserve()),
+            constant_product: BigRational::from_integer(BigInt::from(pool.get_constant_product())),
+            fee_bps: pool.fee_bps(),
+            protocol_fee_share_bps: pool.protocol_fee_share_bps(),
+            token_tax: pool.token_tax(),
+        }
+    }
+
+    pub fn native_reserve(&self) -> &BigRational {
+        &self.native_reserve
+    }
+
+    pub fn token_reserve(&self) -> &BigRational {
+        &self.token_reserve
+    }
+
+    /// Executes `op` exactly, ignoring slippage limits, and returns the native currency spent
+    /// (buy) or received (sell), fee included. Returns `None` if a buy would drain the token
+    /// reserve. As in the pool, the LPs' share of the fee stays in the native reserve and grows
+    /// the constant product.
+    pub fn execute(&mut self, op: &TradeOp) -> Option<BigRational> {
+        let (new_token_reserve, is_buy) = match *op {
+            TradeOp::Buy { token_amount, .. } => {
+                (&self.token_reserve - to_rational(token_amount), true)
+            }
+            TradeOp::Sell { token_amount, .. } => {
+                let token_amount = to_rational(token_amount);
+                let tokens_in = &token_amount - bps_of(self.token_tax.sell_bps, &token_amount);
+                (&self.token_reserve + tokens_in, false)
+            }
+        };
+        if !new_token_reserve.is_positive() {
+            return None;
+        }
+        let new_native_reserve = &self.constant_product / &new_token_reserve;
+        let curve_amount = if is_buy {
+            &new_native_reserve - &self.native_reserve
+        } else {
+            &self.native_reserve - &new_native_reserve
+        };
+        let fee = bps_of(self.fee_bps, &curve_amount);
+        let lp_fee = &fee - bps_of(self.protocol_fee_share_bps, &fee);
+        let native_amount = if is_buy {
+            &curve_amount + &fee
+        } else {
+            &curve_amount - &fee
+        };
+        self.native_reserve = new_native_reserve + &lp_fee;
+        self.token_reserve = new_token_reserve;
+        if lp_fee.is_positive() {
+            self.constant_product = &self.native_reserve * &self.token_reserve;
+        }
+        Some(native_amount)
+    }
+}
+
+/// Outcome of replaying a trade sequence through both implementations.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DifferentialReport {
+    /// Number of trades executed by both implementations.
+    pub trades: usize,
+    /// Largest absolute difference, in native units, between the amounts returned by
+    /// [`LiquidityPool`] and by the reference model for the same trade.
+    pub max_deviation: f64,
+    /// Index in the replayed sequence of the trade with the largest deviation.
+    pub max_deviation_index: Option<usize>,
+    /// Trades rejected by [`LiquidityPool`], which are skipped by both implementations.
+    pub errors: Vec<(usize, PoolError)>,
+}
+
+impl DifferentialReport {
+    /// Returns `true` if no trade deviated from the reference by more than `tolerance` native units.
+    pub fn is_within(&self, tolerance: f64) -> bool {
+        self.max_deviation <= tolerance
+    }
+}
+
+/// Replays `ops` against a copy of `pool` and against a [`ReferencePool`] with the same
+/// reserves, reporting the largest deviation between the two.
+pub fn replay(pool: &LiquidityPool, ops: &[TradeOp]) -> DifferentialReport {
+    let mut pool = pool.clone();
+    let mut reference = ReferencePool::from_pool(&pool);
+    let mut report = DifferentialReport::default();
+    for (index, op) in ops.iter().enumerate() {
+        let native_amount = match pool.execute(op) {
+            Ok(native_amount) => native_amount,
+            Err(err) => {
+                report.errors.push((index, err));
+                continue;
+            }
+        };
+        let Some(exact) = reference.execute(op) else {
+            continue;
+        };
+        report.trades += 1;
+        let deviation = (to_rational(native_amount) - exact)
+            .abs()
+            .to_f64()
+            .unwrap_or(f64::INFINITY);
+        if report.max_deviation_index.is_none() || deviation > report.max_deviation {
+            report.max_deviation = deviation;
+            report.max_deviation_index = Some(index);
+        }
+    }
+    report
+}
+
+fn to_rational(value: u64) -> BigRational {
+    BigRational::from_integer(BigInt::from(value))
+}
+
+/// Exact `bps` basis points of `amount`.
+fn bps_of(bps: u16, amount: &BigRational) -> BigRational {
+    amount * BigRational::new(BigInt::from(bps), BigInt::from(MAX_FEE_BPS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_matches_exact_trade() {
+        let mut reference = ReferencePool::new(100, 100);
+        let native = reference
+            .execute(&TradeOp::Buy {
+                token_amount: 50,
+                max_native: None,
+            })
+            .unwrap();
+        assert_eq!(native, to_rational(100));
+        assert_eq!(reference.token_reserve(), &to_rational(50));
+    }
+
+    fn trades() -> Vec<TradeOp> {
+        (1..=50u64)
+            .map(|i| {
+                let token_amount = i * 1_000_000 * 10u64.pow(6);
+                if i % 3 == 0 {
+                    TradeOp::Sell {
+                        token_amount,
+                        min_native: None,
+                    }
+                } else {
+                    TradeOp::Buy {
+                        token_amount,
+                        max_native: None,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_deviation_is_truncation_only() {
+        let ops = trades();
+        let report = replay(&LiquidityPool::default(), &ops);
+        assert_eq!(report.trades, ops.len());
+        assert!(report.errors.is_empty());
+        assert!(report.is_within(1.0));
+    }
+
+    #[test]
+    fn test_replay_with_fee_and_tax() {
+        let ops = trades();
+        for protocol_fee_share_bps in [0, 2_500, MAX_FEE_BPS] {
+            let pool = LiquidityPool::default()
+                .with_fee(30)
+                .unwrap()
+                .with_protocol_fee_share(protocol_fee_share_bps)
+                .unwrap()
+                .with_token_tax(TokenTax {
+                    buy_bps: 100,
+                    sell_bps: 200,
+                })
+                .unwrap();
+            let report = replay(&pool, &ops);
+            assert_eq!(report.trades, ops.len());
+            assert!(report.errors.is_empty());
+            assert!(report.is_within(2.0));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use alloc::vec::Vec;
+
+use crate::{Direction, LiquidityPool, PoolError, PoolSnapshot, Quote, Result, TradeOp};
+
+/// An order split into sequential chunks, as returned by
+/// [`LiquidityPool::plan_twap_execution`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionPlan {
+    pub direction: Direction,
+    /// Tokens traded by each chunk, in execution order.
+    pub token_amounts: Vec<u64>,
+    /// Quote of each chunk, in execution order, against the pool it would meet.
+    pub chunks: Vec<Quote>,
+    /// Native paid, fee included (buy) or received net of the fee (sell), across all chunks.
+    pub native_amount: u64,
+    /// Quote of the whole order executed at once on the current pool.
+    pub single_shot: Quote,
+    /// Native saved by chunking against the single shot: paid less on a buy, received more on
+    /// a sell. Negative if chunking costs more.
+    pub savings: i128,
+}
+
+impl LiquidityPool {
+    /// Plans trading `total_amount` tokens in `direction` as `n_chunks` equal sequential
+    /// trades on a copy of the pool, with no other flow in between. On a constant product curve
+    /// the chunks then cost about what the whole order does at once, slightly more as the LPs'
+    /// fee on each chunk grows the reserve the next one meets; see
+    /// [`plan_twap_execution_with_reversion`](Self::plan_twap_execution_with_reversion) for
+    /// flow absorbing the impact between chunks. Fails with [`PoolError::InvalidAmount`] for
+    /// an empty order or no chunks, and like the trades themselves if the pool cannot fill
+    /// them.
+    pub fn plan_twap_execution(
+        &self,
+        direction: Direction,
+        total_amount: u64,
+        n_chunks: usize,
+    ) -> Result<ExecutionPlan> {
+        self.plan_twap_execution_with_reversion(direction, total_amount, n_chunks, 0.0)
+    }
+
+    /// Plans chunks as [`plan_twap_execution`](Self::plan_twap_execution), assuming other
+    /// traders trade `reversion` of each chunk's tokens the opposite way before the next one,
+    /// from 0 (no flow) to 1 (the impact fully reverts). Fails with
+    /// [`PoolError::InvalidAmount`] for a reversion outside that range.
+    pub fn plan_twap_execution_with_reversion(
+        &self,
+        direction: Direction,
+        total_amount: u64,
+        n_chunks: usize,
+        reversion: f64,
+    ) -> Result<ExecutionPlan> {
+        if total_amount == 0 || n_chunks == 0 || !(0.0..=1.0).contains(&reversion) {
+            return Err(PoolError::InvalidAmount);
+        }
+        let n = n_chunks as u64;
+        // Spread the remainder over the first chunks.
+        let token_amounts = (0..n)
+            .map(|chunk| total_amount / n + u64::from(chunk < total_amount % n))
+            .filter(|&token_amount| token_amount > 0)
+            .collect();
+        self.execution_plan(direction, token_amounts, reversion)
+    }
+
+    /// Plans trading `total_amount` tokens in `direction` in as few sequential chunks as
+    /// possible while the [`price_impact`](Self::price_impact) of each stays within
+    /// `max_impact_bps` basis points, with no other flow in between. Every chunk but the last
+    /// is the largest the pool it meets allows, which yields the fewest chunks. Fails with
+    /// [`PoolError::InvalidAmount`] for an empty order and
+    /// [`PoolError::PriceImpactTooHigh`] once no trade fits within the limit.
+    pub fn plan_impact_limited_execution(
+        &self,
+        direction: Direction,
+        total_amount: u64,
+        max_impact_bps: u32,
+    ) -> Result<ExecutionPlan> {
+        if total_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let mut pool = PoolSnapshot::of(self).to_pool();
+        let mut token_amounts = Vec::new();
+        let mut remaining = total_amount;
+        while remaining > 0 {
+            let token_amount = pool
+                .max_amount_for_impact(direction, max_impact_bps)
+                .min(remaining);
+            if token_amount == 0 {
+                return Err(PoolError::PriceImpactTooHigh);
+            }
+            pool.execute(&trade(direction, token_amount))?;
+            token_amounts.push(token_amount);
+            remaining -= token_amount;
+        }
+        self.execution_plan(direction, token_amounts, 0.0)
+    }
+
+    /// Quotes `token_amounts` in turn on a copy of the pool, with `reversion` of each traded
+    /// back before the next, and compares them with a single shot.
+    fn execution_plan(
+        &self,
+        direction: Direction,
+        token_amounts: Vec<u64>,
+        reversion: f64,
+    ) -> Result<ExecutionPlan> {
+        let quote = |pool: &LiquidityPool, direction, token_amount| match direction {
+            Direction::BuyToken => pool.quote_buy(token_amount),
+            Direction::SellToken => pool.quote_sell(token_amount),
+        };
+        let opposite = match direction {
+            Direction::BuyToken => Direction::SellToken,
+            Direction::SellToken => Direction::BuyToken,
+        };
+        let single_shot = quote(self, direction, token_amounts.iter().sum())?;
+        let mut pool = PoolSnapshot::of(self).to_pool();
+        let mut chunks = Vec::with_capacity(token_amounts.len());
+        for (index, &token_amount) in token_amounts.iter().enumerate() {
+            chunks.push(quote(&pool, direction, token_amount)?);
+            pool.execute(&trade(direction, token_amount))?;
+            let reverted = (token_amount as f64 * reversion) as u64;
+            if reverted > 0 && index + 1 < token_amounts.len() {
+                pool.execute(&trade(opposite, reverted))?;
+            }
+        }
+        let native = |quote: &Quote| match direction {
+            Direction::BuyToken => quote.amount_in,
+            Direction::SellToken => quote.amount_out,
+        };
+        let native_amount = chunks.iter().map(native).sum();
+        let savings = match direction {
+            Direction::BuyToken => native(&single_shot) as i128 - native_amount as i128,
+            Direction::SellToken => native_amount as i128 - native(&single_shot) as i128,
+        };
+        Ok(ExecutionPlan {
+            direction,
+            token_amounts,
+            chunks,
+            native_amount,
+            single_shot,
+            savings,
+        })
+    }
+}
+
+fn trade(direction: Direction, token_amount: u64) -> TradeOp {
+    match direction {
+        Direction::BuyToken => TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        },
+        Direction::SellToken => TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_FEE_BPS;
+
+    #[test]
+    fn test_plan_twap_execution() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let total_amount = 10u64.pow(14) + 3;
+        let plan = pool
+            .plan_twap_execution(Direction::BuyToken, total_amount, 4)
+            .unwrap();
+        assert_eq!(plan.chunks.len(), 4);
+        assert_eq!(
+            plan.chunks
+                .iter()
+                .map(|quote| quote.amount_out)
+                .sum::<u64>(),
+            total_amount
+        );
+        assert!(plan
+            .chunks
+            .windows(2)
+            .all(|pair| pair[1].execution_price > pair[0].execution_price));
+        // The LPs' fee on each chunk grows the reserve the next one meets.
+        assert!(plan.savings < 0 && plan.savings > -(plan.native_amount as i128) / 1_000);
+        assert_eq!(plan.single_shot, pool.quote_buy(total_amount).unwrap());
+        // With fees kept apart from the reserves, chunks only differ by rounding.
+        let apart = pool
+            .clone()
+            .with_protocol_fee_share(MAX_FEE_BPS)
+            .unwrap()
+            .plan_twap_execution(Direction::BuyToken, total_amount, 4)
+            .unwrap();
+        assert!(apart.savings.abs() <= 8);
+
+        assert_eq!(
+            pool.plan_twap_execution(Direction::BuyToken, total_amount, 0),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn test_plan_impact_limited_execution() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let total_amount = 10u64.pow(14);
+        let plan = pool
+            .plan_impact_limited_execution(Direction::BuyToken, total_amount, 100)
+            .unwrap();
+        assert_eq!(plan.token_amounts.iter().sum::<u64>(), total_amount);
+        assert!(plan.chunks.iter().all(|quote| quote.price_impact <= 0.01));
+        // One fewer chunk than planned would have to exceed the limit somewhere.
+        let fewer = pool
+            .plan_twap_execution(Direction::BuyToken, total_amount, plan.chunks.len() - 1)
+            .unwrap();
+        assert!(fewer.chunks.iter().any(|quote| quote.price_impact > 0.01));
+
+        let single = pool
+            .plan_impact_limited_execution(Direction::SellToken, 10u64.pow(9), 100)
+            .unwrap();
+        assert_eq!(single.token_amounts, vec![10u64.pow(9)]);
+        assert_eq!(
+            pool.plan_impact_limited_execution(Direction::BuyToken, total_amount, 0),
+            Err(PoolError::PriceImpactTooHigh)
+        );
+    }
+
+    #[test]
+    fn test_reversion_rewards_chunking() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        for direction in [Direction::BuyToken, Direction::SellToken] {
+            let plan = pool
+                .plan_twap_execution_with_reversion(direction, 10u64.pow(14), 10, 0.5)
+                .unwrap();
+            assert!(plan.savings > 0);
+        }
+        assert_eq!(
+            pool.plan_twap_execution_with_reversion(Direction::SellToken, 1, 1, 1.5),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
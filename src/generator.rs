@@ -0,0 +1,156 @@
+use crate::rng::SplitMix64;
+use crate::TradeOp;
+
+/// Distribution of the token amounts produced by a [`TradeGenerator`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeDistribution {
+    /// Every trade has the same size.
+    Fixed(u64),
+    /// Sizes are uniformly distributed in `min..=max`.
+    Uniform { min: u64, max: u64 },
+    /// Sizes are log-uniformly distributed in `min..=max`: many small trades, few large ones.
+    LogUniform { min: u64, max: u64 },
+}
+
+/// Produces a reproducible stream of trades from a seed.
+///
+/// The same seed and parameters yield the same [`TradeOp`] sequence on every machine, so
+/// stress tests and benchmarks can share identical workloads.
+#[derive(Debug, Clone)]
+pub struct TradeGenerator {
+    rng: SplitMix64,
+    sizes: SizeDistribution,
+    buy_ratio: f64,
+}
+
+impl TradeGenerator {
+    /// Creates a generator where each trade is a buy with probability `buy_ratio` (clamped to
+    /// `[0, 1]`) and a sell otherwise. Sizes with a zero lower bound are raised to one token.
+    pub fn new(seed: u64, sizes: SizeDistribution, buy_ratio: f64) -> Self {
+        Self {
+            rng: SplitMix64::new(seed),
+            sizes,
+            buy_ratio: buy_ratio.clamp(0.0, 1.0),
+        }
+    }
+
+    fn next_size(&mut self) -> u64 {
+        match self.sizes {
+            SizeDistribution::Fixed(size) => size.max(1),
+            SizeDistribution::Uniform { min, max } => {
+                let min = min.max(1);
+                self.rng.range_u64(min, max.max(min))
+            }
+            SizeDistribution::LogUniform { min, max } => {
+                let min = min.max(1);
+                self.rng.log_range_u64(min, max.max(min))
+            }
+        }
+    }
+
+    /// Generates the next trade, without slippage limits.
+    pub fn next_op(&mut self) -> TradeOp {
+        let is_buy = self.rng.next_f64() < self.buy_ratio;
+        let token_amount = self.next_size();
+        if is_buy {
+            TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            }
+        } else {
+            TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            }
+        }
+    }
+}
+
+impl Iterator for TradeGenerator {
+    type Item = TradeOp;
+
+    fn next(&mut self) -> Option<TradeOp> {
+        Some(self.next_op())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_stream() {
+        let sizes = SizeDistribution::LogUniform {
+            min: 10u64.pow(6),
+            max: 10_000_000 * 10u64.pow(6),
+        };
+        let first: Vec<_> = TradeGenerator::new(42, sizes, 0.6).take(100).collect();
+        let second: Vec<_> = TradeGenerator::new(42, sizes, 0.6).take(100).collect();
+        let other: Vec<_> = TradeGenerator::new(43, sizes, 0.6).take(100).collect();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_stream_is_stable_across_platforms() {
+        let sizes = SizeDistribution::Uniform { min: 1, max: 1_000 };
+        let ops: Vec<_> = TradeGenerator::new(7, sizes, 0.5).take(3).collect();
+        assert_eq!(
+            ops,
+            vec![
+                TradeOp::Buy {
+                    token_amount: 17,
+                    max_native: None
+                },
+                TradeOp::Sell {
+                    token_amount: 583,
+                    min_native: None
+                },
+                TradeOp::Buy {
+                    token_amount: 250,
+                    max_native: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sizes_and_ratio() {
+        let sizes = SizeDistribution::Uniform { min: 100, max: 200 };
+        let ops: Vec<_> = TradeGenerator::new(1, sizes, 0.75).take(10_000).collect();
+        let buys = ops
+            .iter()
+            .filter(|op| matches!(op, TradeOp::Buy { .. }))
+            .count();
+        assert!((7_000..8_000).contains(&buys));
+        assert!(ops.iter().all(|op| match op {
+            TradeOp::Buy { token_amount, .. } | TradeOp::Sell { token_amount, .. } =>
+                (100..=200).contains(token_amount),
+        }));
+    }
+
+    #[test]
+    fn test_log_uniform_stream_is_stable_across_platforms() {
+        let sizes = SizeDistribution::LogUniform {
+            min: 1,
+            max: 10u64.pow(12),
+        };
+        let amounts: Vec<_> = TradeGenerator::new(7, sizes, 0.5)
+            .take(8)
+            .map(|op| op.token_amount())
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                1,
+                9_889_253,
+                984,
+                8_648,
+                90_718,
+                329_980_988_216,
+                28_574_663_085,
+                3_797_073
+            ]
+        );
+    }
+}
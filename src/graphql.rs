@@ -0,0 +1,191 @@
+//! An [`async_graphql`] schema over a [`PoolMap`], so dashboards can query pool states,
+//! recorded trades and derived metrics from a running simulation or mirror service.
+//!
+//! 128-bit values, which GraphQL integers cannot hold, are returned as decimal strings.
+
+use std::sync::Arc;
+
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+
+use crate::{LiquidityPool, PoolMap, Pressure, TradeOp, TradeRecord};
+
+pub type PoolSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Builds a read-only schema serving the pools currently stored in `pools`.
+pub fn schema(pools: Arc<PoolMap<String>>) -> PoolSchema {
+    Schema::new(QueryRoot { pools }, EmptyMutation, EmptySubscription)
+}
+
+pub struct QueryRoot {
+    pools: Arc<PoolMap<String>>,
+}
+
+#[Object]
+impl QueryRoot {
+    /// Every pool in the registry, ordered by id.
+    async fn pools(&self) -> Vec<PoolObject> {
+        let mut ids = self.pools.keys();
+        ids.sort_unstable();
+        ids.into_iter()
+            .filter_map(|id| self.pool_object(id))
+            .collect()
+    }
+
+    async fn pool(&self, id: String) -> Option<PoolObject> {
+        self.pool_object(id)
+    }
+}
+
+impl QueryRoot {
+    fn pool_object(&self, id: String) -> Option<PoolObject> {
+        // Copy the pool so resolvers never hold a shard lock across await points.
+        let pool = self.pools.get(&id)?;
+        Some(PoolObject { id, pool })
+    }
+}
+
+/// A pool as stored in the registry when the query ran.
+pub struct PoolObject {
+    id: String,
+    pool: LiquidityPool,
+}
+
+#[Object(name = "Pool")]
+impl PoolObject {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn native_reserve(&self) -> u64 {
+        self.pool.get_native_reserve()
+    }
+
+    async fn token_reserve(&self) -> u64 {
+        self.pool.get_token_reserve()
+    }
+
+    async fn constant_product(&self) -> String {
+        self.pool.get_constant_product().to_string()
+    }
+
+    async fn market_price(&self) -> f64 {
+        self.pool.market_price()
+    }
+
+    async fn spot_price(&self) -> f64 {
+        self.pool.spot_price()
+    }
+
+    async fn trade_count(&self) -> u64 {
+        self.pool.trade_count()
+    }
+
+    async fn lp_supply(&self) -> u64 {
+        self.pool.lp_supply()
+    }
+
+    async fn fee_bps(&self) -> u16 {
+        self.pool.fee_bps()
+    }
+
+    async fn fees_collected(&self) -> String {
+        self.pool.fees_collected().to_string()
+    }
+
+    /// Recorded trades, oldest first, limited to the most recent `last` if given.
+    async fn history(&self, last: Option<usize>) -> Vec<TradeObject> {
+        let records = self.pool.history();
+        let skip = last.map_or(0, |last| records.len().saturating_sub(last));
+        records[skip..].iter().map(TradeObject::from).collect()
+    }
+
+    /// Native paid in minus paid out over the last `window` recorded trades.
+    async fn net_flow(&self, window: usize) -> String {
+        self.pool.net_flow(window).to_string()
+    }
+
+    /// Buy/sell pressure over the last `window` recorded trades.
+    async fn pressure(&self, window: usize) -> PressureObject {
+        PressureObject::from(self.pool.pressure(window))
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Trade")]
+pub struct TradeObject {
+    seq: u64,
+    is_buy: bool,
+    token_amount: u64,
+    native_amount: u64,
+    fee: u64,
+    protocol_fee: u64,
+}
+
+impl From<&TradeRecord> for TradeObject {
+    fn from(record: &TradeRecord) -> Self {
+        Self {
+            seq: record.seq,
+            is_buy: matches!(record.op, TradeOp::Buy { .. }),
+            token_amount: record.op.token_amount(),
+            native_amount: record.native_amount,
+            fee: record.fee,
+            protocol_fee: record.protocol_fee,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Pressure")]
+pub struct PressureObject {
+    buy_volume: String,
+    sell_volume: String,
+    score: f64,
+}
+
+impl From<Pressure> for PressureObject {
+    fn from(pressure: Pressure) -> Self {
+        Self {
+            buy_volume: pressure.buy_volume.to_string(),
+            sell_volume: pressure.sell_volume.to_string(),
+            score: pressure.score(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::value;
+
+    #[tokio::test]
+    async fn test_query_pools() {
+        let pools = Arc::new(PoolMap::new());
+        let mut pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        pool.enable_history();
+        pool.buy(500, None).unwrap();
+        pools.insert("b".to_string(), pool);
+        pools.insert("a".to_string(), LiquidityPool::new(10, 10).unwrap());
+
+        let response = schema(pools)
+            .execute(
+                "{ pools { id } pool(id: \"b\") { nativeReserve tradeCount \
+                 history(last: 1) { isBuy tokenAmount nativeAmount } netFlow(window: 5) \
+                 pressure(window: 5) { score } } }",
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data,
+            value!({
+                "pools": [{ "id": "a" }, { "id": "b" }],
+                "pool": {
+                    "nativeReserve": 2000,
+                    "tradeCount": 1,
+                    "history": [{ "isBuy": true, "tokenAmount": 500, "nativeAmount": 1000 }],
+                    "netFlow": "1000",
+                    "pressure": { "score": 1.0 },
+                },
+            })
+        );
+    }
+}
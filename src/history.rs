@@ -0,0 +1,359 @@
+use core::iter::Sum;
+use core::ops::Add;
+
+use alloc::vec::Vec;
+
+use crate::{Curve, LiquidityChange, LiquidityPool, PoolError, PoolSnapshot, Result, TradeOp};
+
+/// A trade recorded by a pool with history enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct TradeRecord {
+    /// Value of [`LiquidityPool::trade_count`] once the trade was applied.
+    pub seq: u64,
+    pub op: TradeOp,
+    /// Native currency spent (buy) or received (sell), fee included.
+    pub native_amount: u64,
+    /// Swap fee charged on the trade, of which the liquidity providers' share stays in the
+    /// native reserve.
+    pub fee: u64,
+    /// Share of `fee` owed to the protocol, which leaves the reserves.
+    pub protocol_fee: u64,
+    /// Last oracle timestamp when the trade was applied, or `None` with the oracle disabled,
+    /// see [`LiquidityPool::enable_oracle`].
+    pub timestamp: Option<u64>,
+}
+
+/// Signed change of the pool reserves, positive when a reserve grew.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReserveDelta {
+    pub native: i128,
+    pub token: i128,
+}
+
+impl Add for ReserveDelta {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            native: self.native + other.native,
+            token: self.token + other.token,
+        }
+    }
+}
+
+impl Sum for ReserveDelta {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl TradeRecord {
+    /// Change the trade made to the pool reserves.
+    pub fn delta(&self) -> ReserveDelta {
+        let (native, protocol_fee) = (self.native_amount as i128, self.protocol_fee as i128);
+        let token = self.op.token_amount() as i128;
+        match self.op {
+            TradeOp::Buy { .. } => ReserveDelta {
+                native: native - protocol_fee,
+                token: -token,
+            },
+            TradeOp::Sell { .. } => ReserveDelta {
+                native: -(native + protocol_fee),
+                token,
+            },
+        }
+    }
+}
+
+/// A change of the initial token reserve used as the market price reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reanchor {
+    /// Value of [`LiquidityPool::trade_count`] when the pool was reanchored.
+    pub seq: u64,
+    pub previous_initial_token_reserve: u64,
+    pub initial_token_reserve: u64,
+}
+
+/// Every trade applied since history was enabled, with the state it started from.
+#[derive(Debug, Clone)]
+pub(crate) struct History {
+    pub(crate) origin: PoolSnapshot,
+    pub(crate) records: Vec<TradeRecord>,
+    pub(crate) reanchors: Vec<Reanchor>,
+    pub(crate) liquidity_changes: Vec<LiquidityChange>,
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Starts recording every trade applied to the pool. Any previous history is discarded.
+    pub fn enable_history(&mut self) {
+        self.history = Some(History {
+            origin: PoolSnapshot::of(self),
+            records: Vec::new(),
+            reanchors: Vec::new(),
+            liquidity_changes: Vec::new(),
+        });
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Returns the trades recorded since history was enabled, oldest first.
+    pub fn history(&self) -> &[TradeRecord] {
+        self.history
+            .as_ref()
+            .map_or(&[], |history| history.records.as_slice())
+    }
+
+    /// Returns the reanchors recorded since history was enabled, oldest first.
+    pub fn reanchors(&self) -> &[Reanchor] {
+        self.history
+            .as_ref()
+            .map_or(&[], |history| history.reanchors.as_slice())
+    }
+
+    /// Returns the liquidity changes recorded since history was enabled, oldest first.
+    pub fn liquidity_changes(&self) -> &[LiquidityChange] {
+        self.history
+            .as_ref()
+            .map_or(&[], |history| history.liquidity_changes.as_slice())
+    }
+
+    /// Returns the state the pool was in when history was enabled, if it is.
+    pub fn history_origin(&self) -> Option<PoolSnapshot> {
+        self.history.as_ref().map(|history| history.origin)
+    }
+
+    /// Combined reserve change of the last `window` recorded trades.
+    pub fn net_delta(&self, window: usize) -> ReserveDelta {
+        self.history()
+            .iter()
+            .rev()
+            .take(window)
+            .map(TradeRecord::delta)
+            .sum()
+    }
+
+    /// Native currency paid in by buys minus paid out to sells over the last `window` recorded
+    /// trades; positive values indicate buy pressure.
+    pub fn net_flow(&self, window: usize) -> i128 {
+        self.net_delta(window).native
+    }
+}
+
+impl LiquidityPool {
+    /// Reconstructs the state of the pool right after its `seq`-th trade.
+    ///
+    /// Replay starts from the latest checkpoint at or before `seq` that is covered by the
+    /// history, or from the state the history was enabled in. Fails with
+    /// [`PoolError::HistoryUnavailable`] if history is disabled or does not reach back to
+    /// `seq`, and with [`PoolError::InvalidAmount`] if `seq` lies in the future.
+    pub fn state_at(&self, seq: u64) -> Result<PoolSnapshot> {
+        if seq > self.trade_count {
+            return Err(PoolError::InvalidAmount);
+        }
+        let history = self.history.as_ref().ok_or(PoolError::HistoryUnavailable)?;
+        if seq < history.origin.trade_count() {
+            return Err(PoolError::HistoryUnavailable);
+        }
+        #[cfg(feature = "std")]
+        let base = self
+            .checkpoints()
+            .rev()
+            .map(|checkpoint| checkpoint.snapshot)
+            .find(|snapshot| (history.origin.trade_count()..=seq).contains(&snapshot.trade_count()))
+            .unwrap_or(history.origin);
+        #[cfg(not(feature = "std"))]
+        let base = history.origin;
+
+        let pool = history.replay(base, seq, |_, _| {})?;
+        Ok(PoolSnapshot::of(&pool))
+    }
+}
+
+impl History {
+    /// Replays the recorded trades, liquidity changes and reanchors from `base` up to the
+    /// `seq`-th trade, calling `visit` with each replayed trade and the pool right after it.
+    pub(crate) fn replay(
+        &self,
+        base: PoolSnapshot,
+        seq: u64,
+        mut visit: impl FnMut(&TradeRecord, &LiquidityPool),
+    ) -> Result<LiquidityPool> {
+        let mut pool = base.to_pool();
+        // Liquidity changes and reanchors recorded at trade count `n` happened between trades
+        // `n` and `n + 1`, so they are applied before the first replayed trade past `n`.
+        let changes = &self.liquidity_changes;
+        let reanchors = &self.reanchors;
+        let mut next_change = changes.partition_point(|change| change.seq < base.trade_count());
+        let mut next_reanchor =
+            reanchors.partition_point(|reanchor| reanchor.seq < base.trade_count());
+        let mut catch_up = |pool: &mut LiquidityPool, seq: u64| -> Result<()> {
+            while let Some(change) = changes.get(next_change).filter(|change| change.seq < seq) {
+                pool.apply_liquidity(change)?;
+                next_change += 1;
+            }
+            // Reanchoring pins the reference to the token reserve, whichever way it was
+            // ordered with liquidity changes between the same trades.
+            while reanchors
+                .get(next_reanchor)
+                .is_some_and(|reanchor| reanchor.seq < seq)
+            {
+                pool.initial_token_reserve = pool.token_reserve;
+                next_reanchor += 1;
+            }
+            Ok(())
+        };
+        for record in self
+            .records
+            .iter()
+            .skip_while(|record| record.seq <= base.trade_count())
+            .take_while(|record| record.seq <= seq)
+        {
+            catch_up(&mut pool, record.seq)?;
+            pool.execute(&record.op)?;
+            visit(record, &pool);
+        }
+        catch_up(&mut pool, seq)?;
+        Ok(pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CheckpointPolicy;
+
+    #[test]
+    fn test_state_at() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(10u64.pow(12), None).unwrap();
+        pool.enable_history();
+        pool.enable_checkpoints(CheckpointPolicy {
+            every_trades: Some(3),
+            every_interval: None,
+            capacity: 2,
+        });
+        let mut states = vec![PoolSnapshot::of(&pool)];
+        for i in 1..=10u64 {
+            if i % 4 == 0 {
+                pool.sell(i * 10u64.pow(12), None).unwrap();
+            } else {
+                pool.buy(i * 10u64.pow(12), None).unwrap();
+            }
+            states.push(PoolSnapshot::of(&pool));
+        }
+        assert_eq!(pool.history().len(), 10);
+        for (offset, expected) in states.iter().enumerate() {
+            let seq = 1 + offset as u64;
+            assert_eq!(pool.state_at(seq).unwrap(), *expected);
+        }
+        assert_eq!(pool.state_at(0), Err(PoolError::HistoryUnavailable));
+        assert_eq!(pool.state_at(12), Err(PoolError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_net_flow() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let token_amount = 10u64.pow(12);
+        let spent = pool.buy(3 * token_amount, None).unwrap();
+        let received = pool.sell(token_amount, None).unwrap();
+        assert_eq!(pool.net_flow(1), -(received as i128));
+        assert_eq!(pool.net_flow(10), spent as i128 - received as i128);
+        assert_eq!(
+            pool.net_delta(2),
+            ReserveDelta {
+                native: pool.get_native_reserve() as i128 - 10i128.pow(9),
+                token: -2 * token_amount as i128,
+            }
+        );
+        assert_eq!(pool.net_flow(0), 0);
+
+        // The liquidity providers' share of the fee stays in the reserve.
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        pool.enable_history();
+        let before = pool.get_native_reserve();
+        pool.buy(10u64.pow(12), None).unwrap();
+        assert_eq!(pool.net_flow(1), 1_004_005);
+        assert_eq!(
+            pool.net_flow(1),
+            (pool.get_native_reserve() - before) as i128
+        );
+    }
+
+    #[test]
+    fn test_state_at_across_reanchor() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        pool.buy(10u64.pow(12), None).unwrap();
+        let previous = pool.reanchor();
+        pool.buy(10u64.pow(12), None).unwrap();
+        assert_eq!(
+            pool.reanchors(),
+            &[Reanchor {
+                seq: 1,
+                previous_initial_token_reserve: previous,
+                initial_token_reserve: pool.get_initial_token_reserve(),
+            }]
+        );
+        assert_eq!(pool.state_at(1).unwrap().initial_token_reserve, previous);
+        assert_eq!(
+            pool.state_at(2).unwrap().initial_token_reserve,
+            pool.get_initial_token_reserve()
+        );
+    }
+
+    #[test]
+    fn test_state_at_across_liquidity_changes() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let mut states = Vec::new();
+        for i in 1..=4u64 {
+            pool.buy(i * 10u64.pow(12), None).unwrap();
+            states.push(PoolSnapshot::of(&pool));
+            if i % 2 == 1 {
+                pool.reanchor();
+                pool.add_liquidity(i * 10u64.pow(8), u64::MAX).unwrap();
+            } else {
+                pool.remove_liquidity(pool.lp_supply() / 3).unwrap();
+            }
+        }
+        assert_eq!(pool.liquidity_changes().len(), 4);
+        for (offset, expected) in states.iter().enumerate() {
+            assert_eq!(pool.state_at(1 + offset as u64).unwrap(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_state_at_liquidity_change_before_checkpoints() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        pool.buy(10u64.pow(12), None).unwrap();
+        pool.add_liquidity(10u64.pow(9), u64::MAX).unwrap();
+        pool.enable_checkpoints(CheckpointPolicy {
+            every_trades: Some(1),
+            every_interval: None,
+            capacity: 4,
+        });
+        pool.buy(10u64.pow(12), None).unwrap();
+        let state = pool.state_at(2).unwrap();
+        assert_eq!(state.native_reserve, 2_002_003_506);
+        assert_eq!(state, PoolSnapshot::of(&pool));
+        assert_eq!(pool.state_at(1).unwrap().native_reserve, 1_001_001_001);
+    }
+
+    #[test]
+    fn test_state_at_without_history() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(10u64.pow(12), None).unwrap();
+        assert_eq!(pool.state_at(1), Err(PoolError::HistoryUnavailable));
+    }
+}
@@ -0,0 +1,152 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{LiquidityPool, Result, TaggedTrade, TradeOp};
+
+/// Per-actor token balances built from the trades each actor made against a pool.
+///
+/// Balances start at zero, so an actor selling tokens it acquired elsewhere goes negative;
+/// distribution metrics only count actors with a positive balance.
+#[derive(Debug, Clone)]
+pub struct HolderBook<A> {
+    balances: HashMap<A, i128>,
+}
+
+impl<A: Eq + Hash + Clone> HolderBook<A> {
+    pub fn new() -> Self {
+        Self {
+            balances: HashMap::new(),
+        }
+    }
+
+    /// Executes `trade` against `pool` and, if it succeeds, credits or debits its actor.
+    pub fn execute(&mut self, pool: &mut LiquidityPool, trade: &TaggedTrade<A>) -> Result<u64> {
+        let native_amount = pool.execute(&trade.op)?;
+        self.record(trade);
+        Ok(native_amount)
+    }
+
+    /// Applies a trade that was already executed elsewhere.
+    pub fn record(&mut self, trade: &TaggedTrade<A>) {
+        let balance = self.balances.entry(trade.actor.clone()).or_default();
+        match trade.op {
+            TradeOp::Buy { token_amount, .. } => *balance += token_amount as i128,
+            TradeOp::Sell { token_amount, .. } => *balance -= token_amount as i128,
+        }
+    }
+
+    pub fn balance(&self, actor: &A) -> i128 {
+        self.balances.get(actor).copied().unwrap_or_default()
+    }
+
+    /// Positive balances, largest first.
+    pub fn holders(&self) -> Vec<(A, u128)> {
+        let mut holders: Vec<(A, u128)> = self
+            .balances
+            .iter()
+            .filter(|(_, balance)| **balance > 0)
+            .map(|(actor, balance)| (actor.clone(), *balance as u128))
+            .collect();
+        holders.sort_by_key(|(_, balance)| Reverse(*balance));
+        holders
+    }
+
+    /// Tokens held across all actors with a positive balance.
+    pub fn total_held(&self) -> u128 {
+        self.holders().iter().map(|(_, balance)| balance).sum()
+    }
+
+    /// Share of the held tokens owned by the `n` largest holders, from 0 to 1.
+    pub fn top_concentration(&self, n: usize) -> f64 {
+        let holders = self.holders();
+        let total: u128 = holders.iter().map(|(_, balance)| balance).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let top: u128 = holders.iter().take(n).map(|(_, balance)| balance).sum();
+        top as f64 / total as f64
+    }
+
+    /// Gini coefficient of the positive balances, 0 for perfect equality and approaching 1
+    /// when a single holder owns everything.
+    pub fn gini(&self) -> f64 {
+        let mut balances: Vec<u128> = self
+            .holders()
+            .into_iter()
+            .map(|(_, balance)| balance)
+            .collect();
+        balances.reverse();
+        let total: u128 = balances.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let n = balances.len() as f64;
+        let weighted: f64 = balances
+            .iter()
+            .enumerate()
+            .map(|(index, balance)| (index + 1) as f64 * *balance as f64)
+            .sum();
+        2.0 * weighted / (n * total as f64) - (n + 1.0) / n
+    }
+}
+
+impl<A: Eq + Hash + Clone> Default for HolderBook<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(actor: u32, token_amount: u64) -> TaggedTrade<u32> {
+        TaggedTrade {
+            actor,
+            op: TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_balances_follow_trades() {
+        let mut pool = LiquidityPool::default();
+        let mut book = HolderBook::new();
+        book.execute(&mut pool, &buy(1, 500)).unwrap();
+        book.execute(&mut pool, &buy(2, 300)).unwrap();
+        let sell = TaggedTrade {
+            actor: 1,
+            op: TradeOp::Sell {
+                token_amount: 200,
+                min_native: None,
+            },
+        };
+        book.execute(&mut pool, &sell).unwrap();
+        assert!(book.execute(&mut pool, &buy(3, 0)).is_err());
+        assert_eq!(book.balance(&1), 300);
+        assert_eq!(book.balance(&3), 0);
+        assert_eq!(book.total_held(), 600);
+        assert_eq!(
+            pool.get_token_reserve(),
+            LiquidityPool::default().get_token_reserve() - 600
+        );
+    }
+
+    #[test]
+    fn test_distribution_metrics() {
+        let mut book = HolderBook::new();
+        for actor in 0..20 {
+            book.record(&buy(actor, 100));
+        }
+        assert_eq!(book.top_concentration(10), 0.5);
+        assert!(book.gini().abs() < 1e-12);
+
+        book.record(&buy(0, 100_000));
+        assert!(book.top_concentration(1) > 0.98);
+        assert!(book.gini() > 0.9);
+        assert_eq!(HolderBook::<u32>::new().gini(), 0.0);
+    }
+}
@@ -0,0 +1,104 @@
+//! Impermanent loss of constant product LP positions against holding the deposited assets.
+//!
+//! A position along `x * y = k` is worth `2 * sqrt(r) / (1 + r)` of the same assets held
+//! aside once the price has moved by a factor `r`, whichever the direction, before the fees
+//! it earned.
+
+use crate::{PoolError, PoolSnapshot, Result};
+
+/// Value of an LP position against holding what it was worth at entry, in native currency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImpermanentLoss {
+    /// Current spot price over the spot price at entry.
+    pub price_ratio: f64,
+    /// Value per LP share of the native currency and tokens it held at entry, at the current
+    /// price.
+    pub hodl_value: f64,
+    /// Value per LP share of its current part of the reserves.
+    pub lp_value: f64,
+    /// `lp_value / hodl_value - 1`: negative when providing liquidity lost value, positive
+    /// when fees credited to the reserves outweigh the loss.
+    pub loss: f64,
+}
+
+/// Returns the impermanent loss after the price moved by a factor of `price_ratio`, as a
+/// non-positive fraction of the value held aside. Fails with [`PoolError::InvalidAmount`]
+/// unless the ratio is positive and finite.
+pub fn impermanent_loss(price_ratio: f64) -> Result<f64> {
+    if !(price_ratio > 0.0 && price_ratio.is_finite()) {
+        return Err(PoolError::InvalidAmount);
+    }
+    Ok(2.0 * price_ratio.sqrt() / (1.0 + price_ratio) - 1.0)
+}
+
+/// Compares a position taken when the pool was in the `entry` state with holding its assets,
+/// in the `current` state. Fees credited to the reserves show as a smaller loss. Fails with
+/// [`PoolError::ZeroLiquidity`] if either state has no LP shares.
+pub fn between(entry: &PoolSnapshot, current: &PoolSnapshot) -> Result<ImpermanentLoss> {
+    let per_share = |snapshot: &PoolSnapshot| {
+        if snapshot.lp_supply() == 0 {
+            return Err(PoolError::ZeroLiquidity);
+        }
+        let supply = snapshot.lp_supply() as f64;
+        Ok((
+            snapshot.native_reserve() as f64 / supply,
+            snapshot.token_reserve() as f64 / supply,
+        ))
+    };
+    let (entry_native, entry_token) = per_share(entry)?;
+    let (native, token) = per_share(current)?;
+    let price = current.spot_price();
+    let hodl_value = entry_native + entry_token * price;
+    let lp_value = native + token * price;
+    Ok(ImpermanentLoss {
+        price_ratio: price / entry.spot_price(),
+        hodl_value,
+        lp_value,
+        loss: lp_value / hodl_value - 1.0,
+    })
+}
+
+/// Returns `(multiple, loss)` for each price multiple, as [`impermanent_loss`], for charting.
+pub fn curve(multiples: &[f64]) -> Result<Vec<(f64, f64)>> {
+    multiples
+        .iter()
+        .map(|&multiple| impermanent_loss(multiple).map(|loss| (multiple, loss)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityPool;
+
+    #[test]
+    fn test_impermanent_loss() {
+        assert_eq!(impermanent_loss(1.0), Ok(0.0));
+        // The classic figures: -5.72% at 2x or 0.5x, -20% at 4x.
+        assert!((impermanent_loss(2.0).unwrap() + 0.057191).abs() < 1e-6);
+        assert!((impermanent_loss(0.5).unwrap() - impermanent_loss(2.0).unwrap()).abs() < 1e-12);
+        assert!((impermanent_loss(4.0).unwrap() + 0.2).abs() < 1e-12);
+        assert_eq!(impermanent_loss(0.0), Err(PoolError::InvalidAmount));
+        assert_eq!(
+            curve(&[1.0, 4.0]).unwrap()[1],
+            (4.0, impermanent_loss(4.0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_between_pool_states() {
+        let mut pool = LiquidityPool::default();
+        let entry = pool.snapshot();
+        // Doubling the native reserve quadruples the price.
+        pool.spend_native(10u64.pow(9)).unwrap();
+        let il = between(&entry, &pool.snapshot()).unwrap();
+        assert!((il.price_ratio - 4.0).abs() < 1e-6);
+        assert!((il.loss + 0.2).abs() < 1e-6);
+
+        let mut earning = LiquidityPool::default().with_fee(100).unwrap();
+        earning.spend_native(10u64.pow(9)).unwrap();
+        let with_fees = between(&entry, &earning.snapshot()).unwrap();
+        assert!(with_fees.loss > il.loss);
+    }
+}
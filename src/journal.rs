@@ -0,0 +1,109 @@
+use alloc::vec::Vec;
+
+use crate::{Direction, LiquidityPool, PoolError, PoolSnapshot, Result, TradeOp};
+
+/// A trade as seen by an auditor: what was traded and the reserves it left behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeEvent {
+    /// Value of [`LiquidityPool::trade_count`] once the trade was applied.
+    pub seq: u64,
+    pub direction: Direction,
+    pub token_amount: u64,
+    /// Native currency spent (buy) or received (sell), fee included.
+    pub native_amount: u64,
+    /// Native and token reserves right after the trade.
+    pub reserves_after: (u64, u64),
+}
+
+impl LiquidityPool {
+    /// Returns every trade recorded since history was enabled as a [`TradeEvent`], oldest
+    /// first. Fails with [`PoolError::HistoryUnavailable`] if history is disabled.
+    pub fn events(&self) -> Result<Vec<TradeEvent>> {
+        let history = self.history.as_ref().ok_or(PoolError::HistoryUnavailable)?;
+        let mut events = Vec::with_capacity(history.records.len());
+        history.replay(history.origin, self.trade_count, |record, pool| {
+            events.push(TradeEvent {
+                seq: record.seq,
+                direction: match record.op {
+                    TradeOp::Buy { .. } => Direction::BuyToken,
+                    TradeOp::Sell { .. } => Direction::SellToken,
+                },
+                token_amount: record.op.token_amount(),
+                native_amount: record.native_amount,
+                reserves_after: (pool.native_reserve, pool.token_reserve),
+            });
+        })?;
+        Ok(events)
+    }
+
+    /// Rebuilds a pool by executing `events` from `origin`, typically the
+    /// [`history_origin`](Self::history_origin) of the pool they were taken from.
+    ///
+    /// Every replayed trade must reproduce its event exactly, or the replay fails with
+    /// [`PoolError::ReplayDiverged`]. Events carry no liquidity changes, so sessions that
+    /// added or removed liquidity are rebuilt with [`state_at`](Self::state_at) instead.
+    pub fn replay(origin: &PoolSnapshot, events: &[TradeEvent]) -> Result<LiquidityPool> {
+        let mut pool = LiquidityPool::try_from(*origin)?;
+        for event in events {
+            let op = match event.direction {
+                Direction::BuyToken => TradeOp::Buy {
+                    token_amount: event.token_amount,
+                    max_native: None,
+                },
+                Direction::SellToken => TradeOp::Sell {
+                    token_amount: event.token_amount,
+                    min_native: None,
+                },
+            };
+            let native_amount = pool.execute(&op)?;
+            if pool.trade_count != event.seq
+                || native_amount != event.native_amount
+                || (pool.native_reserve, pool.token_reserve) != event.reserves_after
+            {
+                return Err(PoolError::ReplayDiverged);
+            }
+        }
+        Ok(pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_replay() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        pool.buy(10u64.pow(12), None).unwrap();
+        pool.enable_history();
+        pool.buy(5 * 10u64.pow(12), None).unwrap();
+        pool.reanchor();
+        pool.sell(2 * 10u64.pow(12), None).unwrap();
+
+        let events = pool.events().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].seq, 2);
+        assert_eq!(events[1].direction, Direction::SellToken);
+        assert_eq!(
+            events[1].reserves_after,
+            (pool.get_native_reserve(), pool.get_token_reserve())
+        );
+
+        let origin = pool.history_origin().unwrap();
+        let replayed = LiquidityPool::replay(&origin, &events).unwrap();
+        assert_eq!(replayed.get_native_reserve(), pool.get_native_reserve());
+        assert_eq!(replayed.fees_collected(), pool.fees_collected());
+
+        let mut tampered = events.clone();
+        tampered[0].native_amount -= 1;
+        assert_eq!(
+            LiquidityPool::replay(&origin, &tampered).map(|_| ()),
+            Err(PoolError::ReplayDiverged)
+        );
+        assert_eq!(
+            LiquidityPool::default().events(),
+            Err(PoolError::HistoryUnavailable)
+        );
+    }
+}
@@ -1,125 +1,720 @@
-use std::cmp::Ordering;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-type Result<T> = std::result::Result<T, PoolError>;
+extern crate alloc;
 
+#[cfg(feature = "std")]
+mod alert;
+#[cfg(feature = "typed-amounts")]
+mod amount;
+mod apr;
+#[cfg(feature = "std")]
+pub mod arbitrage;
+mod asset;
+#[cfg(feature = "async")]
+mod async_pool;
+#[cfg(feature = "std")]
+pub mod backtest;
+mod bonding;
+#[cfg(feature = "std")]
+pub mod calibration;
+pub mod candles;
+#[cfg(feature = "std")]
+mod checkpoint;
+pub mod compat;
+mod consistency;
+mod cost;
+mod curve;
+mod decimals;
+#[cfg(feature = "differential")]
+pub mod differential;
+mod execution;
+#[cfg(feature = "std")]
+mod generator;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+mod history;
+#[cfg(feature = "std")]
+mod holders;
+#[cfg(feature = "std")]
+pub mod il;
+mod journal;
+mod liquidity;
+mod manipulation;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "std")]
+mod mock;
+mod observer;
+mod oracle;
+mod phased;
+pub mod pnl;
+mod points;
+#[cfg(feature = "std")]
+mod pool_map;
+pub mod presets;
+mod pressure;
+mod price;
+#[cfg(kani)]
+mod proofs;
+mod quote;
+#[cfg(feature = "std")]
+mod risk;
+#[cfg(feature = "std")]
+mod rng;
+mod router;
+mod sandwich;
+#[cfg(feature = "std")]
+pub mod scenario;
+#[cfg(feature = "std")]
+mod sequencer;
+#[cfg(feature = "std")]
+pub mod sim;
+mod snapshot;
+mod source;
+#[cfg(feature = "std")]
+pub mod split;
+mod stable_swap;
+mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "std")]
+pub mod stress;
+mod swap;
+mod tax;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+mod trader;
+mod transaction;
+mod vesting;
+#[cfg(feature = "std")]
+pub mod wal;
+#[cfg(feature = "std")]
+mod wash;
+#[cfg(feature = "std")]
+mod weighted;
+mod wide;
+
+#[cfg(feature = "std")]
+use alert::Alerts;
+#[cfg(feature = "std")]
+pub use alert::{
+    CrossDirection, PriceAlert, ReserveAlert, ReserveChange, ReserveCondition, WhaleAlert,
+    WhaleCriteria,
+};
+#[cfg(feature = "typed-amounts")]
+pub use amount::{Native, Token};
+pub use apr::FeeAprPoint;
+pub use asset::{AssetId, AssetPool, AssetSwap};
+#[cfg(feature = "async")]
+pub use async_pool::AsyncPool;
+pub use bonding::{Graduation, GraduationTarget};
+#[cfg(feature = "std")]
+use checkpoint::Checkpoints;
+#[cfg(feature = "std")]
+pub use checkpoint::{Checkpoint, CheckpointPolicy};
+pub use consistency::ConsistencyViolation;
+pub use cost::{CostModel, CostReport};
+pub use curve::{ConstantProduct, Curve, CurveState, Rounding, RoundingPolicy};
+pub use decimals::{Reserve, MAX_DECIMALS};
+pub use execution::ExecutionPlan;
+#[cfg(feature = "std")]
+pub use generator::{SizeDistribution, TradeGenerator};
+use history::History;
+pub use history::{Reanchor, ReserveDelta, TradeRecord};
+#[cfg(feature = "std")]
+pub use holders::HolderBook;
+pub use journal::TradeEvent;
+pub use liquidity::{LiquidityChange, LiquidityKind, MINIMUM_LIQUIDITY};
+pub use manipulation::ManipulationCost;
+#[cfg(feature = "std")]
+pub use mock::{MockExchange, MockPoolSource};
+use observer::Observers;
+pub use observer::PoolObserver;
+pub use oracle::Observation;
+use oracle::Oracle;
+pub use phased::{Phase, PhasedPool};
+#[cfg(feature = "std")]
+pub use pool_map::PoolMap;
+pub use pressure::Pressure;
+pub use price::PriceQ64;
+pub use quote::{DepthLevel, FeeMode, PriceImpact, Quote};
+#[cfg(feature = "std")]
+pub use risk::{RiskReport, SupportLevel, SUPPORT_DRAWDOWNS};
+pub use router::{Route, Router, DEFAULT_MAX_HOPS};
+pub use sandwich::Sandwich;
+#[cfg(feature = "std")]
+pub use sequencer::{Receipt, Sequencer, SequencerHandle, SequencingPolicy};
+pub use snapshot::PoolSnapshot;
+#[cfg(feature = "async")]
+pub use source::AsyncPoolSource;
+pub use source::PoolSource;
+pub use stable_swap::StableSwap;
+pub use stats::PoolStats;
+pub use swap::{Amount, Slippage, SlippageLimit, SwapParams, SwapResult};
+pub use tax::TokenTax;
+pub use trader::Trader;
+pub use transaction::PoolTransaction;
+pub use vesting::{UnlockBehavior, UnlockImpact, UnlockReport};
+#[cfg(feature = "std")]
+pub use wash::{TaggedTrade, WashCriteria, WashSuspicion};
+#[cfg(feature = "std")]
+pub use weighted::Weighted;
+pub use wide::{ReserveInt, WidePool};
+
+type Result<T> = core::result::Result<T, PoolError>;
+
+/// Basis points in 100%, the exclusive upper bound of a swap fee.
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+/// A liquidity pool pricing trades along a [`Curve`], constant product by default.
+///
+/// With the `serde` feature, the constant-product pool serializes as its [`PoolSnapshot`]:
+/// recorded history, checkpoints, oracle observations, statistics, alert subscriptions and
+/// observers are runtime state and are not persisted. Deserialized states are validated like
+/// [`check_invariants`](Self::check_invariants).
 #[derive(Debug, Clone)]
-pub struct LiquidityPool {
+pub struct LiquidityPool<C: Curve = ConstantProduct> {
     initial_token_reserve: u64,
     native_reserve: u64,
     token_reserve: u64,
     constant_product: u128,
+    trade_count: u64,
+    native_volume: u128,
+    fee_bps: u16,
+    fees_collected: u128,
+    protocol_fee_share_bps: u16,
+    protocol_fees_owed: u128,
+    lp_supply: u64,
+    locked_liquidity: u64,
+    virtual_native: u64,
+    virtual_token: u64,
+    graduation: Option<Graduation>,
+    native_decimals: u8,
+    token_decimals: u8,
+    rounding: RoundingPolicy,
+    token_tax: TokenTax,
+    curve: C,
+    #[cfg(feature = "std")]
+    checkpoints: Option<Checkpoints>,
+    history: Option<History>,
+    oracle: Option<Oracle>,
+    stats: PoolStats,
+    #[cfg(feature = "std")]
+    alerts: Alerts,
+    observers: Observers<C>,
 }
 
-#[derive(Debug, thiserror::Error, PartialEq)]
+/// Errors returned by pool operations. New variants may be added in minor releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum PoolError {
-    #[error("Slippage too high")]
-    SlippageExceeded,
-    #[error("Invalid funds in the pool")]
-    InsufficientPoolFunds,
-    #[error("Invalid amount")]
+    /// The trade would pay or receive `actual` against a bound of `limit`.
+    SlippageExceeded {
+        limit: u64,
+        actual: u64,
+    },
+    /// `requested` units were asked of a reserve holding `available`, which may not be paid
+    /// out in full.
+    InsufficientPoolFunds {
+        requested: u64,
+        available: u64,
+    },
     InvalidAmount,
-    #[error("Overflow")]
     Overflow,
+    InvariantViolated,
+    PoolNotFound,
+    SourceUnavailable,
+    HistoryUnavailable,
+    ReplayDiverged,
+    CurveCompleted,
+    UnknownAsset,
+    /// A reserve is empty, so the pool cannot price trades.
+    ZeroLiquidity,
+    /// A trade would move the spot price further than allowed.
+    PriceImpactTooHigh,
+    /// `requested` units were asked of a trader's balance holding `available`.
+    InsufficientBalance {
+        requested: u64,
+        available: u64,
+    },
 }
 
-impl LiquidityPool {
-    pub fn new(native_reserve: u64, token_reserve: u64) -> Result<Self> {
+impl core::fmt::Display for PoolError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PoolError::SlippageExceeded { limit, actual } => {
+                write!(f, "Slippage too high: limit {limit}, actual {actual}")
+            }
+            PoolError::InsufficientPoolFunds {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Invalid funds in the pool: requested {requested}, available {available}"
+            ),
+            PoolError::InvalidAmount => f.write_str("Invalid amount"),
+            PoolError::Overflow => f.write_str("Overflow"),
+            PoolError::InvariantViolated => f.write_str("Pool invariant violated"),
+            PoolError::PoolNotFound => f.write_str("Pool not found"),
+            PoolError::SourceUnavailable => f.write_str("Pool source unavailable"),
+            PoolError::HistoryUnavailable => f.write_str("History unavailable"),
+            PoolError::ReplayDiverged => f.write_str("Replay diverged from the recorded events"),
+            PoolError::CurveCompleted => f.write_str("Bonding curve completed"),
+            PoolError::UnknownAsset => f.write_str("Asset not held by the pool"),
+            PoolError::ZeroLiquidity => f.write_str("Pool has no liquidity"),
+            PoolError::PriceImpactTooHigh => f.write_str("Price impact too high"),
+            PoolError::InsufficientBalance {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Insufficient balance: requested {requested}, available {available}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for PoolError {}
+
+/// Side of a trade, seen from the trader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum Direction {
+    /// Native currency in, tokens out.
+    BuyToken,
+    /// Tokens in, native currency out.
+    SellToken,
+}
+
+/// The projected outcome of a trade, as returned by [`LiquidityPool::simulate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Projection {
+    pub op: TradeOp,
+    /// Native currency that would be spent (buy) or received (sell), fee included.
+    pub native_amount: u64,
+    /// Swap fee charged on top of a buy or withheld from a sell.
+    pub fee: u64,
+    /// Part of `fee` owed to the protocol; the rest is added to the native reserve.
+    pub protocol_fee: u64,
+    /// Tokens taken by the token's transfer tax, see [`TokenTax`].
+    pub token_tax: u64,
+    /// Native reserve after the trade, the LPs' share of the fee included.
+    pub native_reserve: u64,
+    /// Token reserve after the trade.
+    pub token_reserve: u64,
+    /// Market price before the trade.
+    pub price_before: f64,
+    /// Market price after the trade.
+    pub price_after: f64,
+}
+
+/// Outcome of spending a native budget on tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NativeSpend {
+    /// Tokens bought.
+    pub token_amount: u64,
+    /// Native currency actually spent, never more than the budget.
+    pub native_spent: u64,
+    /// Remainder of the budget left unspent by integer rounding, to be refunded.
+    pub dust: u64,
+}
+
+/// A single trade against the pool, as accepted by [`LiquidityPool::execute`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum TradeOp {
+    /// Buys `token_amount` tokens, spending at most `max_native` if set.
+    Buy {
+        token_amount: u64,
+        max_native: Option<u64>,
+    },
+    /// Sells `token_amount` tokens, receiving at least `min_native` if set.
+    Sell {
+        token_amount: u64,
+        min_native: Option<u64>,
+    },
+}
+
+impl TradeOp {
+    pub fn token_amount(&self) -> u64 {
+        match *self {
+            TradeOp::Buy { token_amount, .. } | TradeOp::Sell { token_amount, .. } => token_amount,
+        }
+    }
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Creates a pool pricing trades along `curve`. Fails with [`PoolError::ZeroLiquidity`] if
+    /// either reserve is empty.
+    pub fn with_curve(curve: C, native_reserve: u64, token_reserve: u64) -> Result<Self> {
         if native_reserve == 0 || token_reserve == 0 {
-            return Err(PoolError::InvalidAmount);
+            return Err(PoolError::ZeroLiquidity);
         }
-        let constant_product = native_reserve as u128 * token_reserve as u128;
+        let constant_product = curve.invariant(native_reserve, token_reserve)?;
+        let (lp_supply, locked_liquidity) =
+            liquidity::initial_shares(native_reserve as u128 * token_reserve as u128);
         Ok(Self {
             initial_token_reserve: token_reserve,
             native_reserve,
             token_reserve,
             constant_product,
+            trade_count: 0,
+            native_volume: 0,
+            fee_bps: 0,
+            fees_collected: 0,
+            protocol_fee_share_bps: 0,
+            protocol_fees_owed: 0,
+            lp_supply,
+            locked_liquidity,
+            virtual_native: 0,
+            virtual_token: 0,
+            graduation: None,
+            native_decimals: 0,
+            token_decimals: 0,
+            rounding: RoundingPolicy::DEFAULT,
+            token_tax: TokenTax::default(),
+            curve,
+            #[cfg(feature = "std")]
+            checkpoints: None,
+            history: None,
+            oracle: None,
+            stats: PoolStats::default(),
+            #[cfg(feature = "std")]
+            alerts: Alerts::default(),
+            observers: Observers::default(),
         })
     }
 
+    /// Charges a swap fee of `fee_bps` basis points of the native amount of every trade, added
+    /// to what buyers pay and withheld from what sellers receive. As in Uniswap V2, the fee
+    /// stays in the native reserve for the LPs, growing the invariant, unless part of it goes
+    /// to the protocol, see [`with_protocol_fee_share`](Self::with_protocol_fee_share). Fails
+    /// with [`PoolError::InvalidAmount`] unless the fee is below 100%.
+    pub fn with_fee(mut self, fee_bps: u16) -> Result<Self> {
+        if fee_bps >= MAX_FEE_BPS {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.fee_bps = fee_bps;
+        Ok(self)
+    }
+
+    /// Returns the swap fee in basis points.
+    pub fn fee_bps(&self) -> u16 {
+        self.fee_bps
+    }
+
+    /// Returns the swap fees charged by all trades since the pool was created.
+    pub fn fees_collected(&self) -> u128 {
+        self.fees_collected
+    }
+
+    /// Splits every swap fee between the protocol, which receives `share_bps` basis points of
+    /// it rounded down, and the LPs, whose part is added to the native reserve so the invariant
+    /// grows. By default the whole fee goes to the LPs; a share of 100% keeps every fee apart
+    /// from the reserves and the curve unaffected.
+    /// Fails with [`PoolError::InvalidAmount`] for a share above 100%.
+    pub fn with_protocol_fee_share(mut self, share_bps: u16) -> Result<Self> {
+        if share_bps > MAX_FEE_BPS {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.protocol_fee_share_bps = share_bps;
+        Ok(self)
+    }
+
+    /// Returns the share of each swap fee owed to the protocol, in basis points.
+    pub fn protocol_fee_share_bps(&self) -> u16 {
+        self.protocol_fee_share_bps
+    }
+
+    /// Returns the protocol's part of the swap fees not yet collected.
+    pub fn protocol_fees_owed(&self) -> u128 {
+        self.protocol_fees_owed
+    }
+
+    /// Pays out the protocol fees owed, returning them.
+    pub fn collect_protocol_fees(&mut self) -> u128 {
+        core::mem::take(&mut self.protocol_fees_owed)
+    }
+
+    /// Part of `fee` owed to the protocol.
+    fn protocol_fee_on(&self, fee: u64) -> u64 {
+        (fee as u128 * self.protocol_fee_share_bps as u128 / MAX_FEE_BPS as u128) as u64
+    }
+
+    /// Rounds trades as `policy` sets instead of [`RoundingPolicy::DEFAULT`], to match the
+    /// amounts of a given on-chain program exactly. Curves other than [`ConstantProduct`] may
+    /// only honour the fee rounding.
+    pub fn with_rounding(mut self, policy: RoundingPolicy) -> Self {
+        self.rounding = policy;
+        self
+    }
+
+    pub fn rounding(&self) -> RoundingPolicy {
+        self.rounding
+    }
+
+    /// Fee charged on a trade moving `native_amount` through the reserves, rounded as the
+    /// rounding policy sets.
+    fn fee_on(&self, native_amount: u64) -> u64 {
+        self.rounding.fee.div(
+            native_amount as u128 * self.fee_bps as u128,
+            MAX_FEE_BPS as u128,
+        ) as u64
+    }
+
+    /// Largest native amount that can move through the reserves when `budget` must also cover
+    /// the fee.
+    fn native_before_fee(&self, budget: u64) -> u64 {
+        let total_bps = MAX_FEE_BPS as u128 + self.fee_bps as u128;
+        let mut native_amount = (budget as u128 * MAX_FEE_BPS as u128 / total_bps) as u64;
+        // A fee rounded up may push the estimate one unit over the budget, and one rounded down
+        // may leave room for one more.
+        while native_amount > 0 && native_amount + self.fee_on(native_amount) > budget {
+            native_amount -= 1;
+        }
+        while native_amount < budget && native_amount + 1 + self.fee_on(native_amount + 1) <= budget
+        {
+            native_amount += 1;
+        }
+        native_amount
+    }
+
+    /// Smallest native amount that must leave the reserves for a seller to receive `net`
+    /// after the fee, if any.
+    fn native_after_fee(&self, net: u64) -> Option<u64> {
+        let net_bps = (MAX_FEE_BPS - self.fee_bps) as u128;
+        let mut native_amount =
+            u64::try_from((net as u128 * MAX_FEE_BPS as u128).div_ceil(net_bps)).ok()?;
+        // A fee rounded up may leave the estimate one unit short, and one rounded down may make
+        // it one unit more than needed.
+        while native_amount - self.fee_on(native_amount) < net {
+            native_amount = native_amount.checked_add(1)?;
+        }
+        while native_amount > net && native_amount - 1 - self.fee_on(native_amount - 1) >= net {
+            native_amount -= 1;
+        }
+        Some(native_amount)
+    }
+
+    /// Returns the native reserve, including any virtual part, see
+    /// [`with_virtual_reserves`](Self::with_virtual_reserves).
     pub fn get_native_reserve(&self) -> u64 {
         self.native_reserve
     }
+    /// Returns the token reserve, including any virtual part.
     pub fn get_token_reserve(&self) -> u64 {
         self.token_reserve
     }
 
+    /// Returns the invariant preserved by trades, `k` for a constant-product pool.
     pub fn get_constant_product(&self) -> u128 {
         self.constant_product
     }
 
-    /// Returns the current market price of tokens in terms of native currency.
+    pub fn curve(&self) -> &C {
+        &self.curve
+    }
+
+    /// Returns the reserves and invariant handed to the curve.
+    pub fn curve_state(&self) -> CurveState {
+        CurveState {
+            native_reserve: self.native_reserve,
+            token_reserve: self.token_reserve,
+            invariant: self.constant_product,
+            rounding: self.rounding,
+        }
+    }
+
+    /// Returns the token reserve used as the reference for [`market_price`](Self::market_price).
+    pub fn get_initial_token_reserve(&self) -> u64 {
+        self.initial_token_reserve
+    }
+
+    /// Resets the market price reference to the current token reserve, returning the previous
+    /// reference. The change is recorded in the history when it is enabled.
+    pub fn reanchor(&mut self) -> u64 {
+        let previous = core::mem::replace(&mut self.initial_token_reserve, self.token_reserve);
+        if let Some(history) = &mut self.history {
+            history.reanchors.push(Reanchor {
+                seq: self.trade_count,
+                previous_initial_token_reserve: previous,
+                initial_token_reserve: self.initial_token_reserve,
+            });
+        }
+        previous
+    }
+
+    /// Checks that the reserves lie on the curve, up to the rounding applied by trades (see
+    /// [`Curve::on_curve`]): `k - token_reserve < native_reserve * token_reserve <= k` for a
+    /// constant-product pool with the default [`RoundingPolicy`].
+    pub fn check_invariants(&self) -> Result<()> {
+        if self.invariant_holds(self.native_reserve, self.token_reserve) {
+            Ok(())
+        } else {
+            Err(PoolError::InvariantViolated)
+        }
+    }
+
+    fn invariant_holds(&self, native_reserve: u64, token_reserve: u64) -> bool {
+        native_reserve > 0
+            && token_reserve > 0
+            && self.curve.on_curve(&CurveState {
+                native_reserve,
+                token_reserve,
+                invariant: self.constant_product,
+                rounding: self.rounding,
+            })
+    }
+
+    /// Stores the reserves resulting from a trade and records it.
+    fn commit_trade(&mut self, projection: &Projection) -> Result<()> {
+        #[cfg(feature = "std")]
+        let alert_state = Alerts::capture(self);
+        self.apply_trade(projection)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            seq = self.trade_count,
+            token_amount = projection.op.token_amount(),
+            native_amount = projection.native_amount,
+            native_reserve = self.native_reserve,
+            token_reserve = self.token_reserve,
+            market_price = self.market_price(),
+            "trade committed"
+        );
+        let record = TradeRecord {
+            seq: self.trade_count,
+            op: projection.op,
+            native_amount: projection.native_amount,
+            fee: projection.fee,
+            protocol_fee: projection.protocol_fee,
+            timestamp: self.oracle.as_ref().map(|oracle| oracle.last().timestamp),
+        };
+        if let Some(history) = &mut self.history {
+            history.records.push(record);
+        }
+        #[cfg(feature = "std")]
+        if let Some(mut checkpoints) = self.checkpoints.take() {
+            checkpoints.observe(self);
+            self.checkpoints = Some(checkpoints);
+        }
+        #[cfg(feature = "std")]
+        if !self.alerts.is_empty() {
+            self.notify_detached(
+                |pool| &mut pool.alerts,
+                |alerts, pool| alerts.notify(alert_state, pool, record),
+            );
+        }
+        if !self.observers.is_empty() {
+            self.notify_detached(
+                |pool| &mut pool.observers,
+                |observers, pool| observers.notify_trade(&record, pool),
+            );
+        }
+        Ok(())
+    }
+
+    /// Stores the reserves resulting from a trade and counts it, without recording it. With
+    /// the `strict-invariants` feature the new state is validated first, and rejected instead
+    /// of corrupting the pool.
+    pub(crate) fn apply_trade(&mut self, projection: &Projection) -> Result<()> {
+        // The LPs' share of the fee lands off the curve, which it moves by growing the
+        // invariant.
+        let lp_fee = projection.fee - projection.protocol_fee;
+        #[cfg(feature = "strict-invariants")]
+        if !self.invariant_holds(projection.native_reserve - lp_fee, projection.token_reserve) {
+            return Err(PoolError::InvariantViolated);
+        }
+        if lp_fee > 0 {
+            self.constant_product = self
+                .curve
+                .invariant(projection.native_reserve, projection.token_reserve)?;
+        }
+        self.native_reserve = projection.native_reserve;
+        self.token_reserve = projection.token_reserve;
+        self.trade_count += 1;
+        self.native_volume += projection.native_amount as u128;
+        self.fees_collected += projection.fee as u128;
+        self.protocol_fees_owed += projection.protocol_fee as u128;
+        self.stats.record(projection);
+        Ok(())
+    }
+
+    /// Returns the number of trades applied to the pool since it was created.
+    pub fn trade_count(&self) -> u64 {
+        self.trade_count
+    }
+
+    /// Returns the native currency spent and received by all trades since the pool was created.
+    pub fn native_volume(&self) -> u128 {
+        self.native_volume
+    }
+
+    /// Returns the native reserve per token of the reference token reserve, which tracks the
+    /// native reserve rather than the marginal price. Same as
+    /// [`anchored_price`](Self::anchored_price); see [`spot_price`](Self::spot_price) for the
+    /// price of the next token.
     pub fn market_price(&self) -> f64 {
+        self.anchored_price()
+    }
+
+    /// Returns the native reserve divided by the token reserve recorded at creation or by the
+    /// last [`reanchor`](Self::reanchor).
+    pub fn anchored_price(&self) -> f64 {
         self.native_reserve as f64 / self.initial_token_reserve as f64
     }
 
+    /// Returns the marginal price of one token in native currency, from the current reserves.
+    pub fn spot_price(&self) -> f64 {
+        self.curve.spot_price(&self.curve_state())
+    }
+
+    /// Returns the marginal number of tokens per native unit, the inverse of
+    /// [`spot_price`](Self::spot_price).
+    pub fn price_inverse(&self) -> f64 {
+        1.0 / self.spot_price()
+    }
+
     /// Buys `token_amount` tokens from the pool, checking if the native currency spent does not exceed `max_native`.
     pub fn buy(&mut self, token_amount: u64, max_native: Option<u64>) -> Result<u64> {
-        if token_amount == 0 {
-            return Err(PoolError::InvalidAmount);
-        }
-        let new_token_reserve = self
-            .token_reserve
-            .checked_sub(token_amount)
-            .ok_or(PoolError::InsufficientPoolFunds)?;
-        let new_native_reserve = self
-            .constant_product
-            .checked_div(new_token_reserve as u128)
-            .ok_or(PoolError::Overflow)? as u64;
-        let native_sold = new_native_reserve - self.native_reserve;
-        if let Some(max_native) = max_native {
-            if native_sold > max_native {
-                return Err(PoolError::SlippageExceeded);
-            }
-        }
-        self.native_reserve = new_native_reserve;
-        self.token_reserve = new_token_reserve;
-        Ok(native_sold)
+        self.execute(&TradeOp::Buy {
+            token_amount,
+            max_native,
+        })
     }
 
     /// Sells `token_amount` tokens to the pool, checking if the native currency received is at least `min_native`.
     pub fn sell(&mut self, token_amount: u64, min_native: Option<u64>) -> Result<u64> {
-        if token_amount == 0 {
-            return Err(PoolError::InvalidAmount);
-        }
-        if token_amount > self.token_reserve {
-            return Err(PoolError::InsufficientPoolFunds);
-        }
-        let new_token_reserve = self
-            .token_reserve
-            .checked_add(token_amount)
-            .ok_or(PoolError::Overflow)?;
-        let new_native_reserve = self
-            .constant_product
-            .checked_div(new_token_reserve as u128)
-            .ok_or(PoolError::Overflow)? as u64;
-        let native_bought = self.native_reserve - new_native_reserve;
-        if let Some(min_native) = min_native {
-            if native_bought < min_native {
-                return Err(PoolError::SlippageExceeded);
-            }
-        }
-        self.native_reserve = new_native_reserve;
-        self.token_reserve = new_token_reserve;
-        Ok(native_bought)
+        self.execute(&TradeOp::Sell {
+            token_amount,
+            min_native,
+        })
     }
 
     /// Simulates buying `token_amount` tokens and calculates the native currency that would be spent.
     pub fn simulate_buy(&self, token_amount: u64, min_native: Option<u64>) -> Result<u64> {
-        if token_amount == 0 {
-            return Err(PoolError::InvalidAmount);
-        }
-        let new_token_reserve = self
-            .token_reserve
-            .checked_sub(token_amount)
-            .ok_or(PoolError::InsufficientPoolFunds)?;
-        let new_native_reserve = self
-            .constant_product
-            .checked_div(new_token_reserve as u128)
-            .ok_or(PoolError::Overflow)? as u64;
-        let native_sold = new_native_reserve - self.native_reserve;
+        let native_sold = self
+            .simulate(&TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            })?
+            .native_amount;
         if let Some(min_native) = min_native {
             if native_sold < min_native {
-                return Err(PoolError::SlippageExceeded);
+                return Err(PoolError::SlippageExceeded {
+                    limit: min_native,
+                    actual: native_sold,
+                });
             }
         }
         Ok(native_sold)
@@ -127,40 +722,234 @@ impl LiquidityPool {
 
     /// Simulates selling `token_amount` tokens and calculates the native currency that would be received.
     pub fn simulate_sell(&self, token_amount: u64, max_native: Option<u64>) -> Result<u64> {
-        if token_amount == 0 {
-            return Err(PoolError::InvalidAmount);
-        }
-        if token_amount > self.token_reserve {
-            return Err(PoolError::InsufficientPoolFunds);
-        }
-        let new_token_reserve = self
-            .token_reserve
-            .checked_add(token_amount)
-            .ok_or(PoolError::Overflow)?;
-        let new_native_reserve = self
-            .constant_product
-            .checked_div(new_token_reserve as u128)
-            .ok_or(PoolError::Overflow)? as u64;
-        let native_sold = self.native_reserve - new_native_reserve;
+        let native_sold = self
+            .simulate(&TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            })?
+            .native_amount;
         if let Some(max_native) = max_native {
             if native_sold > max_native {
-                return Err(PoolError::SlippageExceeded);
+                return Err(PoolError::SlippageExceeded {
+                    limit: max_native,
+                    actual: native_sold,
+                });
             }
         }
         Ok(native_sold)
     }
 
+    /// Projects the state of the pool after `op` without mutating it, enforcing its slippage limit.
+    /// Never allocates, so it is cheap enough for screeners quoting at high rates.
+    pub fn simulate(&self, op: &TradeOp) -> Result<Projection> {
+        if self.is_graduated() {
+            return Err(PoolError::CurveCompleted);
+        }
+        self.project(&self.curve_state(), op)
+    }
+
+    /// Projects `op` from `state`, the current [`curve_state`](Self::curve_state), on a pool
+    /// that has not graduated.
+    pub(crate) fn project(&self, state: &CurveState, op: &TradeOp) -> Result<Projection> {
+        let (native_amount, fee, token_tax, new_native_reserve, new_token_reserve) = match *op {
+            TradeOp::Buy {
+                token_amount,
+                max_native,
+            } => {
+                if token_amount == 0 {
+                    return Err(PoolError::InvalidAmount);
+                }
+                // Virtual tokens price the trade but cannot be bought.
+                let new_token_reserve = self
+                    .token_reserve
+                    .checked_sub(token_amount)
+                    .filter(|&reserve| reserve >= self.virtual_token)
+                    .ok_or(PoolError::InsufficientPoolFunds {
+                        requested: token_amount,
+                        available: self.real_token_reserve(),
+                    })?;
+                let native_in = self
+                    .curve
+                    .amount_in(state, Direction::BuyToken, token_amount)?;
+                let new_native_reserve = self
+                    .native_reserve
+                    .checked_add(native_in)
+                    .ok_or(PoolError::Overflow)?;
+                let fee = self.fee_on(native_in);
+                let native_sold = native_in.checked_add(fee).ok_or(PoolError::Overflow)?;
+                if let Some(max_native) = max_native {
+                    if native_sold > max_native {
+                        return Err(PoolError::SlippageExceeded {
+                            limit: max_native,
+                            actual: native_sold,
+                        });
+                    }
+                }
+                let token_tax = tax::tax_on(self.token_tax.buy_bps, token_amount);
+                (
+                    native_sold,
+                    fee,
+                    token_tax,
+                    new_native_reserve,
+                    new_token_reserve,
+                )
+            }
+            TradeOp::Sell {
+                token_amount,
+                min_native,
+            } => {
+                if token_amount == 0 {
+                    return Err(PoolError::InvalidAmount);
+                }
+                if token_amount > self.token_reserve {
+                    return Err(PoolError::InsufficientPoolFunds {
+                        requested: token_amount,
+                        available: self.token_reserve,
+                    });
+                }
+                // Only what the transfer tax leaves reaches the pool.
+                let token_tax = tax::tax_on(self.token_tax.sell_bps, token_amount);
+                let tokens_in = token_amount - token_tax;
+                if tokens_in == 0 {
+                    return Err(PoolError::InvalidAmount);
+                }
+                let new_token_reserve = self
+                    .token_reserve
+                    .checked_add(tokens_in)
+                    .ok_or(PoolError::Overflow)?;
+                let native_out = self
+                    .curve
+                    .amount_out(state, Direction::SellToken, tokens_in)?;
+                // Virtual native currency prices the trade but cannot be paid out.
+                let new_native_reserve = self
+                    .native_reserve
+                    .checked_sub(native_out)
+                    .filter(|&reserve| reserve >= self.virtual_native)
+                    .ok_or(PoolError::InsufficientPoolFunds {
+                        requested: native_out,
+                        available: self.real_native_reserve(),
+                    })?;
+                let fee = self.fee_on(native_out);
+                let native_bought = native_out - fee;
+                if let Some(min_native) = min_native {
+                    if native_bought < min_native {
+                        return Err(PoolError::SlippageExceeded {
+                            limit: min_native,
+                            actual: native_bought,
+                        });
+                    }
+                }
+                (
+                    native_bought,
+                    fee,
+                    token_tax,
+                    new_native_reserve,
+                    new_token_reserve,
+                )
+            }
+        };
+        let protocol_fee = self.protocol_fee_on(fee);
+        let new_native_reserve = new_native_reserve
+            .checked_add(fee - protocol_fee)
+            .ok_or(PoolError::Overflow)?;
+        Ok(Projection {
+            op: *op,
+            native_amount,
+            fee,
+            protocol_fee,
+            token_tax,
+            native_reserve: new_native_reserve,
+            token_reserve: new_token_reserve,
+            price_before: self.market_price(),
+            price_after: new_native_reserve as f64 / self.initial_token_reserve as f64,
+        })
+    }
+
+    /// Returns the native currency `op` would spend (buy) or receive (sell), enforcing its slippage limit.
+    /// Like [`simulate`](Self::simulate), it never allocates.
+    pub fn quote(&self, op: &TradeOp) -> Result<u64> {
+        self.simulate(op).map(|projection| projection.native_amount)
+    }
+
+    /// Executes `op` against the pool and returns the native currency spent (buy) or received (sell).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(native_reserve = self.native_reserve, token_reserve = self.token_reserve),
+            err(level = "debug")
+        )
+    )]
+    pub fn execute(&mut self, op: &TradeOp) -> Result<u64> {
+        let projection = self.simulate(op)?;
+        self.commit_trade(&projection)?;
+        Ok(projection.native_amount)
+    }
+}
+
+impl LiquidityPool {
+    pub fn new(native_reserve: u64, token_reserve: u64) -> Result<Self> {
+        Self::with_curve(ConstantProduct, native_reserve, token_reserve)
+    }
+
+    #[cfg(feature = "std")]
+    /// Starts snapshotting the pool according to `policy`, right after the trades it applies
+    /// from now on. Any previously recorded checkpoints are discarded.
+    pub fn enable_checkpoints(&mut self, policy: CheckpointPolicy) {
+        self.checkpoints = Some(Checkpoints::new(policy, self));
+    }
+
+    #[cfg(feature = "std")]
+    pub fn disable_checkpoints(&mut self) {
+        self.checkpoints = None;
+    }
+
+    #[cfg(feature = "std")]
+    /// Returns the recorded checkpoints, oldest first.
+    pub fn checkpoints(&self) -> impl DoubleEndedIterator<Item = &Checkpoint> {
+        self.checkpoints.iter().flat_map(Checkpoints::iter)
+    }
+
+    /// Returns a fresh pool with both reserves multiplied by `factor`, at the same price, for
+    /// "what if this pool had 10x liquidity" scenarios. The constant product is recomputed and
+    /// nothing else (history, checkpoints, alerts) is carried over.
+    #[cfg(feature = "std")]
+    pub fn scaled(&self, factor: f64) -> Result<Self> {
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let scale = |value: u64| {
+            let scaled = (value as f64 * factor).round();
+            if scaled >= u64::MAX as f64 {
+                Err(PoolError::Overflow)
+            } else {
+                Ok(scaled as u64)
+            }
+        };
+        let mut pool = Self::new(scale(self.native_reserve)?, scale(self.token_reserve)?)?;
+        pool.fee_bps = self.fee_bps;
+        pool.protocol_fee_share_bps = self.protocol_fee_share_bps;
+        pool.initial_token_reserve = scale(self.initial_token_reserve)?.max(1);
+        pool.virtual_native = scale(self.virtual_native)?.min(pool.native_reserve);
+        pool.virtual_token = scale(self.virtual_token)?.min(pool.token_reserve);
+        pool.native_decimals = self.native_decimals;
+        pool.token_decimals = self.token_decimals;
+        pool.rounding = self.rounding;
+        pool.token_tax = self.token_tax;
+        Ok(pool)
+    }
+
     /// Calculates the amount of tokens that would be received for spending a specific amount of native currency.
     pub fn calculate_tokens_received(&self, native_amount: u64) -> Result<u64> {
         if native_amount == 0 {
             return Err(PoolError::InvalidAmount);
         }
-        let new_native_reserve = self.native_reserve + native_amount;
-        let new_token_reserve = self
-            .constant_product
-            .checked_div(new_native_reserve as u128)
-            .ok_or(PoolError::Overflow)? as u64;
-        Ok(self.token_reserve - new_token_reserve)
+        self.curve.amount_out(
+            &self.curve_state(),
+            Direction::BuyToken,
+            self.native_before_fee(native_amount),
+        )
     }
 
     /// Buys tokens using a specified amount of native currency.
@@ -173,6 +962,151 @@ impl LiquidityPool {
         Ok(token_amount)
     }
 
+    /// Simulates spending at most `native_amount` on tokens, see [`spend_native`](Self::spend_native).
+    pub fn simulate_spend_native(&self, native_amount: u64) -> Result<NativeSpend> {
+        if native_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        // The smallest token reserve reachable without the native reserve exceeding its budget.
+        let max_native_reserve =
+            self.native_reserve as u128 + self.native_before_fee(native_amount) as u128;
+        let min_token_reserve = self.constant_product.div_ceil(max_native_reserve);
+        let token_amount = (self.token_reserve as u128).saturating_sub(min_token_reserve) as u64;
+        let native_spent = self
+            .simulate(&TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            })?
+            .native_amount;
+        Ok(NativeSpend {
+            token_amount,
+            native_spent,
+            dust: native_amount - native_spent,
+        })
+    }
+
+    /// Buys as many tokens as `native_amount` can pay for, never spending more than the budget.
+    /// The part of the budget that cannot buy a whole token unit is returned as `dust`.
+    pub fn spend_native(&mut self, native_amount: u64) -> Result<NativeSpend> {
+        let spend = self.simulate_spend_native(native_amount)?;
+        self.buy(spend.token_amount, Some(spend.native_spent))?;
+        Ok(spend)
+    }
+
+    /// Calculates the smallest amount of tokens to sell to receive at least `native_amount`.
+    pub fn calculate_tokens_needed_for_native(&self, native_amount: u64) -> Result<u64> {
+        if native_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let native_out = self
+            .native_after_fee(native_amount)
+            .filter(|&native_out| {
+                native_out < self.native_reserve
+                    && self.native_reserve - native_out >= self.virtual_native
+            })
+            .ok_or(PoolError::InsufficientPoolFunds {
+                requested: native_amount,
+                available: self.real_native_reserve(),
+            })?;
+        let token_amount =
+            self.curve
+                .amount_in(&self.curve_state(), Direction::SellToken, native_out)?;
+        if token_amount > self.token_reserve {
+            return Err(PoolError::InsufficientPoolFunds {
+                requested: token_amount,
+                available: self.token_reserve,
+            });
+        }
+        Ok(token_amount)
+    }
+
+    /// Sells however many tokens are needed to receive at least `native_amount`, failing with
+    /// [`PoolError::SlippageExceeded`] if that is more than `max_tokens`. Returns the tokens
+    /// sold.
+    pub fn sell_tokens_for_exact_native(
+        &mut self,
+        native_amount: u64,
+        max_tokens: Option<u64>,
+    ) -> Result<u64> {
+        let params = SwapParams {
+            direction: Direction::SellToken,
+            amount: Amount::ExactOut(native_amount),
+            limit: max_tokens.map_or(SlippageLimit::None, SlippageLimit::MaxIn),
+        };
+        Ok(self.swap(&params)?.amount_in)
+    }
+
+    /// Simulates `op` clamped to the largest trade the pool can fill, instead of rejecting it.
+    ///
+    /// Buys are capped so at least one token remains, the native reserve fits in a `u64` and
+    /// `max_native` is respected; sells are capped by the token reserve and so at least one
+    /// native unit remains. The returned [`Projection::op`] is the trade actually achievable.
+    pub fn simulate_saturating(&self, op: &TradeOp) -> Result<Projection> {
+        let clamped = match *op {
+            TradeOp::Buy {
+                token_amount,
+                max_native,
+            } => {
+                let mut min_token_reserve = self
+                    .constant_product
+                    .div_ceil(u64::MAX as u128)
+                    .max(self.virtual_token.max(1) as u128);
+                if let Some(max_native) = max_native {
+                    let max_native_reserve =
+                        self.native_reserve as u128 + self.native_before_fee(max_native) as u128;
+                    min_token_reserve =
+                        min_token_reserve.max(self.constant_product.div_ceil(max_native_reserve));
+                }
+                let max_amount = (self.token_reserve as u128).saturating_sub(min_token_reserve);
+                TradeOp::Buy {
+                    token_amount: token_amount.min(max_amount as u64),
+                    max_native,
+                }
+            }
+            TradeOp::Sell {
+                token_amount,
+                min_native,
+            } => {
+                // Beyond `k / virtual_native` tokens the native reserve would round down below
+                // its virtual part, or to zero without one.
+                let max_token_reserve = (self.constant_product / self.virtual_native.max(1) as u128)
+                    .min(u64::MAX as u128) as u64;
+                let max_amount = self
+                    .token_reserve
+                    .min(max_token_reserve.saturating_sub(self.token_reserve));
+                TradeOp::Sell {
+                    token_amount: token_amount.min(max_amount),
+                    min_native,
+                }
+            }
+        };
+        if clamped.token_amount() == 0 {
+            // Nothing is achievable, so report why the original request fails.
+            return self.simulate(op);
+        }
+        self.simulate(&clamped)
+    }
+
+    /// Executes `op` clamped to the largest trade the pool can fill, see
+    /// [`simulate_saturating`](Self::simulate_saturating).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(native_reserve = self.native_reserve, token_reserve = self.token_reserve),
+            err(level = "debug")
+        )
+    )]
+    pub fn execute_saturating(&mut self, op: &TradeOp) -> Result<Projection> {
+        let projection = self.simulate_saturating(op)?;
+        self.commit_trade(&projection)?;
+        Ok(projection)
+    }
+
+    /// Returns the relative move from the market price to the spot price after buying
+    /// `token_amount` tokens. Panics if the pool cannot fill the purchase; see
+    /// [`price_impact`](Self::price_impact) for either direction with checked math.
     pub fn calculate_price_impact(&self, token_amount: u64) -> f64 {
         let initial_price = self.market_price();
         let new_token_reserve = self.token_reserve - token_amount;
@@ -181,78 +1115,179 @@ impl LiquidityPool {
         (new_price - initial_price) / initial_price
     }
 
-    /// Calculates the number of additional tokens required to reach a desired native currency amount.
+    /// Calculates how many tokens to buy first so that selling `sell_tokens` afterwards
+    /// receives at least `desired_native`, fee deducted. Buying one token fewer falls short.
+    ///
+    /// Along `x * y = k`, selling `s` tokens from a token reserve `y` pays `k * s / (y * (y + s))`,
+    /// so the token reserve to reach is solved for directly and only refined by exact quotes
+    /// where integer rounding moves the answer. Rounding can make the received amount flicker
+    /// by one native unit near the target, so the result is minimal up to that unit. Fails with
+    /// [`PoolError::InsufficientPoolFunds`] if no purchase the pool can fill is enough.
     pub fn calculate_additional_tokens_for_desired_native(
-        &mut self,
+        &self,
         sell_tokens: u64,
         desired_native: u64,
     ) -> Result<u64> {
         if sell_tokens == 0 || desired_native == 0 {
             return Err(PoolError::InvalidAmount);
         }
+        // Tokens left in the pool must still cover the sale.
+        let max_tokens = self
+            .token_reserve
+            .checked_sub(sell_tokens)
+            .ok_or(PoolError::InsufficientPoolFunds {
+                requested: sell_tokens,
+                available: self.token_reserve,
+            })?
+            .min(self.token_reserve - self.virtual_token.max(1));
+        let received = |tokens_to_buy: u64| -> Result<u64> {
+            let mut state = self.curve_state();
+            if tokens_to_buy > 0 {
+                let bought = self.simulate(&TradeOp::Buy {
+                    token_amount: tokens_to_buy,
+                    max_native: None,
+                })?;
+                state.native_reserve = bought.native_reserve;
+                state.token_reserve = bought.token_reserve;
+                // As when the buy is applied, the LPs' share of its fee grows the invariant.
+                if bought.fee > bought.protocol_fee {
+                    state.invariant = self
+                        .curve
+                        .invariant(bought.native_reserve, bought.token_reserve)?;
+                }
+            }
+            let native_out = self
+                .curve
+                .amount_out(&state, Direction::SellToken, sell_tokens)?;
+            Ok(native_out - self.fee_on(native_out))
+        };
+        let enough =
+            |tokens_to_buy: u64| received(tokens_to_buy).is_ok_and(|n| n >= desired_native);
+        if !enough(max_tokens) {
+            return Err(PoolError::InsufficientPoolFunds {
+                requested: desired_native,
+                available: received(max_tokens).unwrap_or(0),
+            });
+        }
 
-        let mut low = 0u64;
-        let mut high = self.token_reserve;
-        let mut best_guess = high;
-
-        // Binary search to find the best amount of tokens to buy
-        while low <= high {
-            let mid = low + (high - low) / 2;
-
-            // Clone the pool to simulate the impact of buying and selling without affecting the actual pool
-            let mut temp_pool = self.clone();
-
-            // Attempt to buy `mid` tokens
-            temp_pool.buy(mid, None)?;
-
-            // Simulate selling `sell_tokens` tokens to see how much native currency would be received
-            let native_received = temp_pool.simulate_sell(sell_tokens, None)?;
+        // Largest token reserve `y` with `y * (y + s) <= k * s / native_out`.
+        let native_out =
+            self.native_after_fee(desired_native)
+                .ok_or(PoolError::InsufficientPoolFunds {
+                    requested: desired_native,
+                    available: self.real_native_reserve(),
+                })? as u128;
+        let (k, s) = (self.constant_product, sell_tokens as u128);
+        let bound = k
+            .checked_mul(s)
+            .map_or_else(|| (k / native_out).saturating_mul(s), |ks| ks / native_out);
+        let half = s / 2;
+        let token_reserve = (half * half).saturating_add(bound).isqrt() - half;
+        let guess = (self.token_reserve as u128)
+            .saturating_sub(token_reserve)
+            .min(max_tokens as u128) as u64;
 
-            match native_received.cmp(&desired_native) {
-                Ordering::Equal => return Ok(mid),
-                Ordering::Less => {
-                    // Not enough native currency received, increase the number of tokens to buy
-                    low = mid.checked_add(1).ok_or(PoolError::Overflow)?;
+        // Bracket the answer around the guess with growing steps, then bisect.
+        let mut step = 1;
+        let (mut low, mut high) = if enough(guess) {
+            let mut high = guess;
+            loop {
+                let probe = high.saturating_sub(step);
+                if probe == high {
+                    break (0, 0);
                 }
-                Ordering::Greater => {
-                    // Too much native currency received, decrease the number of tokens to buy
-                    best_guess = mid;
-                    high = mid.checked_sub(1).ok_or(PoolError::Overflow)?;
+                if !enough(probe) {
+                    break (probe + 1, high);
                 }
+                high = probe;
+                step *= 2;
             }
-            // Exit the loop when the search range is narrowed sufficiently
-            if high - low <= 1 {
-                break;
+        } else {
+            let mut short = guess;
+            loop {
+                let probe = short.saturating_add(step).min(max_tokens);
+                if enough(probe) {
+                    break (short + 1, probe);
+                }
+                short = probe;
+                step *= 2;
+            }
+        };
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if enough(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
             }
         }
+        Ok(high)
+    }
+}
 
-        Ok(best_guess)
+#[cfg(any(test, feature = "test-utils"))]
+impl LiquidityPool {
+    /// Checks the integrity of the pool by comparing the constant product after operations.
+    pub fn check_pool_integrity(&self) -> Result<f64> {
+        if self.native_reserve == 0 || self.token_reserve == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let k = self.native_reserve as u128 * self.token_reserve as u128;
+        Ok((k as f64 - self.constant_product as f64) / self.constant_product as f64)
+    }
+}
+
+/// The reference test pool: 1 native (9 decimals) against 1 billion tokens (6 decimals).
+#[cfg(any(test, feature = "test-utils"))]
+impl Default for LiquidityPool {
+    fn default() -> Self {
+        let native_reserve = 10u64.pow(9);
+        let token_reserve = 1_000_000_000 * 10u64.pow(6);
+        Self::new(native_reserve, token_reserve).unwrap()
+    }
+}
+
+/// Generates pools with arbitrary non-zero reserves, as accepted by [`LiquidityPool::new`].
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for LiquidityPool {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let native_reserve = u.int_in_range(1..=u64::MAX)?;
+        let token_reserve = u.int_in_range(1..=u64::MAX)?;
+        Ok(Self::new(native_reserve, token_reserve).expect("reserves are non-zero"))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <(u64, u64) as arbitrary::Arbitrary>::size_hint(depth)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
 
-    impl LiquidityPool {
-        /// Checks the integrity of the pool by comparing the constant product after operations.
-        fn check_pool_integrity(&self) -> Result<f64> {
-            if self.native_reserve == 0 || self.token_reserve == 0 {
-                return Err(PoolError::InvalidAmount);
-            }
-            let k = self.native_reserve as u128 * self.token_reserve as u128;
-            Ok((k as f64 - self.constant_product as f64) / self.constant_product as f64)
-        }
+    /// Counts allocations per thread, so tests running in parallel do not interfere.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
     }
 
-    impl Default for LiquidityPool {
-        fn default() -> Self {
-            let native_reserve = 10u64.pow(9);
-            let token_reserve = 1_000_000_000 * 10u64.pow(6);
-            Self::new(native_reserve, token_reserve).unwrap()
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
         }
     }
 
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
     #[test]
     fn test_buy() {
         let mut pool = LiquidityPool::default();
@@ -350,13 +1385,166 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_spend_native_returns_dust() {
+        let mut pool = LiquidityPool::new(3 * 10u64.pow(9), 7_000_000_007).unwrap();
+        let native_amount = 12_345_678_901;
+        let quoted = pool.simulate_spend_native(native_amount).unwrap();
+        let spend = pool.spend_native(native_amount).unwrap();
+        assert_eq!(spend, quoted);
+        assert_eq!(spend.native_spent + spend.dust, native_amount);
+        assert!(spend.dust > 0);
+        // One more token unit would exceed the budget.
+        let next = pool.simulate_buy(1, None).unwrap();
+        assert!(next > spend.dust);
+        assert_eq!(
+            pool.get_native_reserve(),
+            3 * 10u64.pow(9) + spend.native_spent
+        );
+    }
+
+    #[test]
+    fn test_swap_fee() {
+        // Fees kept apart from the reserves, so the curve matches a pool without fees.
+        let mut pool = LiquidityPool::default()
+            .with_fee(30)
+            .unwrap()
+            .with_protocol_fee_share(MAX_FEE_BPS)
+            .unwrap();
+        let mut free = LiquidityPool::default();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let spent = pool.buy(token_amount, None).unwrap();
+        let spent_free = free.buy(token_amount, None).unwrap();
+        let buy_fee = spent - spent_free;
+        assert_eq!(buy_fee, (spent_free * 30).div_ceil(10_000));
+        assert_eq!(pool.get_native_reserve(), free.get_native_reserve());
+
+        let received = pool.sell(token_amount, None).unwrap();
+        let received_free = free.sell(token_amount, None).unwrap();
+        let sell_fee = received_free - received;
+        assert_eq!(sell_fee, (received_free * 30).div_ceil(10_000));
+        assert_eq!(pool.fees_collected(), (buy_fee + sell_fee) as u128);
+
+        assert_eq!(pool.verify_consistency(), Vec::new());
+        assert!(LiquidityPool::default().with_fee(MAX_FEE_BPS).is_err());
+
+        // By default the whole fee stays in the native reserve.
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        let spent = pool.buy(token_amount, None).unwrap();
+        assert_eq!(
+            pool.get_native_reserve(),
+            LiquidityPool::default().get_native_reserve() + spent
+        );
+        assert_eq!(pool.protocol_fees_owed(), 0);
+    }
+
+    #[test]
+    fn test_protocol_fee_split() {
+        let mut pool = LiquidityPool::default()
+            .with_fee(30)
+            .unwrap()
+            .with_protocol_fee_share(1_000)
+            .unwrap();
+        let k = pool.get_constant_product();
+        let token_amount = 10u64.pow(12);
+        let op = TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+        let projection = pool.simulate(&op).unwrap();
+        pool.execute(&op).unwrap();
+        assert_eq!(projection.protocol_fee, projection.fee / 10);
+        let lp_fee = projection.fee - projection.protocol_fee;
+        assert_eq!(
+            pool.get_native_reserve(),
+            10u64.pow(9) + projection.native_amount - projection.protocol_fee
+        );
+        assert!(pool.get_constant_product() > k);
+        pool.sell(token_amount, None).unwrap();
+        assert_eq!(pool.check_invariants(), Ok(()));
+        assert!(pool.get_native_reserve() > 10u64.pow(9) + lp_fee);
+
+        let owed = pool.protocol_fees_owed();
+        assert!(owed > projection.protocol_fee as u128 && owed < pool.fees_collected());
+        assert_eq!(pool.collect_protocol_fees(), owed);
+        assert_eq!(pool.protocol_fees_owed(), 0);
+        assert!(LiquidityPool::default()
+            .with_protocol_fee_share(MAX_FEE_BPS + 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_sell_tokens_for_exact_native() {
+        for pool in [
+            LiquidityPool::default(),
+            LiquidityPool::new(3 * 10u64.pow(9), 7_000_000_007)
+                .unwrap()
+                .with_fee(30)
+                .unwrap(),
+        ] {
+            let native_amount = 12_345_678;
+            let token_amount = pool
+                .calculate_tokens_needed_for_native(native_amount)
+                .unwrap();
+            assert!(pool.simulate_sell(token_amount, None).unwrap() >= native_amount);
+            assert!(pool.simulate_sell(token_amount - 1, None).unwrap() < native_amount);
+
+            let mut sold = pool.clone();
+            assert_eq!(
+                sold.sell_tokens_for_exact_native(native_amount, Some(token_amount - 1)),
+                Err(PoolError::SlippageExceeded {
+                    limit: token_amount - 1,
+                    actual: token_amount
+                })
+            );
+            assert_eq!(
+                sold.sell_tokens_for_exact_native(native_amount, Some(token_amount)),
+                Ok(token_amount)
+            );
+            assert_eq!(
+                sold.get_token_reserve(),
+                pool.get_token_reserve() + token_amount
+            );
+        }
+        let pool = LiquidityPool::default();
+        assert_eq!(
+            pool.calculate_tokens_needed_for_native(pool.get_native_reserve()),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: pool.get_native_reserve(),
+                available: pool.get_native_reserve()
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_context() {
+        assert_eq!(
+            LiquidityPool::new(0, 1).unwrap_err(),
+            PoolError::ZeroLiquidity
+        );
+        let error = PoolError::InsufficientPoolFunds {
+            requested: 5,
+            available: 3,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Invalid funds in the pool: requested 5, available 3"
+        );
+    }
+
     #[test]
     fn test_buy_invalid_slippage() {
         let mut pool = LiquidityPool::default();
         let token_amount = 1_000_000 * 10u64.pow(6);
         let native_cost = pool.simulate_buy(token_amount, None).unwrap();
         let result = pool.buy(token_amount, Some(native_cost - 1));
-        assert_eq!(result, Err(PoolError::SlippageExceeded));
+        assert_eq!(
+            result,
+            Err(PoolError::SlippageExceeded {
+                limit: native_cost - 1,
+                actual: native_cost
+            })
+        );
     }
 
     #[test]
@@ -365,7 +1553,148 @@ mod tests {
         let token_amount = 1_000_000 * 10u64.pow(6);
         let native_gain = pool.simulate_sell(token_amount, None).unwrap();
         let result = pool.sell(token_amount, Some(native_gain + 1));
-        assert_eq!(result, Err(PoolError::SlippageExceeded));
+        assert_eq!(
+            result,
+            Err(PoolError::SlippageExceeded {
+                limit: native_gain + 1,
+                actual: native_gain
+            })
+        );
+    }
+
+    #[test]
+    fn test_quote_matches_execute() {
+        let mut pool = LiquidityPool::default();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let buy = TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+        let quoted = pool.quote(&buy).unwrap();
+        let too_tight = TradeOp::Buy {
+            token_amount,
+            max_native: Some(quoted - 1),
+        };
+        assert!(matches!(
+            pool.quote(&too_tight),
+            Err(PoolError::SlippageExceeded { .. })
+        ));
+        assert_eq!(pool.execute(&buy).unwrap(), quoted);
+
+        let sell = TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        };
+        let quoted = pool.quote(&sell).unwrap();
+        assert_eq!(pool.execute(&sell).unwrap(), quoted);
+    }
+
+    #[test]
+    fn test_reanchor() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(100_000_000 * 10u64.pow(6), None).unwrap();
+        let skewed = pool.market_price();
+        assert_eq!(
+            pool.reanchor(),
+            LiquidityPool::default().get_token_reserve()
+        );
+        assert_eq!(pool.get_initial_token_reserve(), pool.get_token_reserve());
+        assert!(pool.market_price() > skewed);
+    }
+
+    #[test]
+    fn test_spot_price() {
+        let mut pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        pool.buy(500, None).unwrap();
+        assert_eq!(pool.spot_price(), 4.0);
+        assert_eq!(pool.price_inverse(), 0.25);
+        assert_eq!(pool.anchored_price(), 2.0);
+        assert_eq!(pool.market_price(), pool.anchored_price());
+    }
+
+    #[test]
+    fn test_scaled() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(50_000_000 * 10u64.pow(6), None).unwrap();
+        let deep = pool.scaled(10.0).unwrap();
+        assert_eq!(deep.get_native_reserve(), pool.get_native_reserve() * 10);
+        assert_eq!(deep.get_token_reserve(), pool.get_token_reserve() * 10);
+        assert_eq!(deep.market_price(), pool.market_price());
+        assert_eq!(
+            deep.get_constant_product(),
+            deep.get_native_reserve() as u128 * deep.get_token_reserve() as u128
+        );
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        assert!(
+            deep.simulate_buy(token_amount, None).unwrap()
+                < pool.simulate_buy(token_amount, None).unwrap()
+        );
+        assert_eq!(pool.scaled(0.0).unwrap_err(), PoolError::InvalidAmount);
+        assert_eq!(pool.scaled(1e12).unwrap_err(), PoolError::Overflow);
+    }
+
+    #[test]
+    fn test_saturating_clamps_to_feasible_trade() {
+        let mut pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        let projection = pool
+            .execute_saturating(&TradeOp::Buy {
+                token_amount: u64::MAX,
+                max_native: None,
+            })
+            .unwrap();
+        assert_eq!(projection.op.token_amount(), 999);
+        assert_eq!(pool.get_token_reserve(), 1);
+        pool.check_invariants().unwrap();
+
+        let mut pool = LiquidityPool::default();
+        let budget = 10u64.pow(8);
+        let projection = pool
+            .simulate_saturating(&TradeOp::Buy {
+                token_amount: u64::MAX,
+                max_native: Some(budget),
+            })
+            .unwrap();
+        assert!(projection.native_amount <= budget);
+        assert_eq!(
+            projection.op.token_amount(),
+            pool.simulate_spend_native(budget).unwrap().token_amount
+        );
+
+        let projection = pool
+            .execute_saturating(&TradeOp::Sell {
+                token_amount: u64::MAX,
+                min_native: None,
+            })
+            .unwrap();
+        assert_eq!(projection.op.token_amount(), 10u64.pow(15));
+        pool.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_saturating_reports_unachievable_trade() {
+        let pool = LiquidityPool::new(1_000, 1).unwrap();
+        let buy = TradeOp::Buy {
+            token_amount: 1,
+            max_native: None,
+        };
+        assert!(pool.simulate_saturating(&buy).is_err());
+        assert_eq!(pool.simulate_saturating(&buy), pool.simulate(&buy));
+    }
+
+    #[test]
+    fn test_simulate_projects_execution() {
+        let mut pool = LiquidityPool::default();
+        let op = TradeOp::Sell {
+            token_amount: 5_000_000 * 10u64.pow(6),
+            min_native: None,
+        };
+        let projection = pool.simulate(&op).unwrap();
+        assert_eq!(pool.trade_count(), 0);
+        assert_eq!(pool.execute(&op).unwrap(), projection.native_amount);
+        assert_eq!(pool.get_native_reserve(), projection.native_reserve);
+        assert_eq!(pool.get_token_reserve(), projection.token_reserve);
+        assert_eq!(pool.market_price(), projection.price_after);
+        assert!(projection.price_after < projection.price_before);
     }
 
     #[test]
@@ -373,6 +1702,17 @@ mod tests {
         let mut pool = LiquidityPool::default();
         let tokens_to_buy = 50_000_000 * 10u64.pow(6);
         let native_spent = pool.buy(tokens_to_buy, None).unwrap();
+        assert_eq!(
+            pool.calculate_additional_tokens_for_desired_native(tokens_to_buy, native_spent / 2),
+            Ok(0)
+        );
+        assert!(matches!(
+            pool.calculate_additional_tokens_for_desired_native(tokens_to_buy, u64::MAX),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: u64::MAX,
+                ..
+            })
+        ));
 
         let additional_native_needed = native_spent + 10u64.pow(9);
         let missing_tokens = pool
@@ -387,6 +1727,192 @@ mod tests {
         assert!(native_received <= additional_native_needed + 1);
     }
 
+    /// The binary search `calculate_additional_tokens_for_desired_native` used before it was
+    /// solved in closed form.
+    fn search_additional_tokens(
+        pool: &LiquidityPool,
+        sell_tokens: u64,
+        desired_native: u64,
+    ) -> Result<u64> {
+        let mut low = 0u64;
+        let mut high = pool.get_token_reserve();
+        let mut best_guess = high;
+        while low <= high {
+            let mid = low + (high - low) / 2;
+            let mut temp_pool = pool.clone();
+            temp_pool.buy(mid, None)?;
+            let native_received = temp_pool.simulate_sell(sell_tokens, None)?;
+            match native_received.cmp(&desired_native) {
+                core::cmp::Ordering::Equal => return Ok(mid),
+                core::cmp::Ordering::Less => low = mid + 1,
+                core::cmp::Ordering::Greater => {
+                    best_guess = mid;
+                    high = mid - 1;
+                }
+            }
+            if high - low <= 1 {
+                break;
+            }
+        }
+        Ok(best_guess)
+    }
+
+    /// Native received by buying `tokens_to_buy` then selling `sell_tokens`, if both fill.
+    fn round_trip(pool: &LiquidityPool, tokens_to_buy: u64, sell_tokens: u64) -> Option<u64> {
+        let mut pool = pool.clone();
+        if tokens_to_buy > 0 {
+            pool.buy(tokens_to_buy, None).ok()?;
+        }
+        pool.sell(sell_tokens, None).ok()
+    }
+
+    /// Checks the closed form against the search: it only fails when the search finds no
+    /// purchase that is enough either, and otherwise returns a purchase that is enough, one token
+    /// more than falls short, and receives at most a unit more than the search's.
+    fn check_additional_tokens(pool: &LiquidityPool, sell_tokens: u64, desired_native: u64) {
+        let result =
+            pool.calculate_additional_tokens_for_desired_native(sell_tokens, desired_native);
+        let searched = search_additional_tokens(pool, sell_tokens, desired_native)
+            .ok()
+            .and_then(|searched| round_trip(pool, searched, sell_tokens))
+            .filter(|&native| native >= desired_native);
+        let Ok(tokens) = result else {
+            assert_eq!(searched, None, "{result:?}");
+            return;
+        };
+        let received = round_trip(pool, tokens, sell_tokens);
+        assert!(received.is_some_and(|native| native >= desired_native));
+        assert!(
+            tokens == 0
+                || round_trip(pool, tokens - 1, sell_tokens)
+                    .is_none_or(|native| native < desired_native)
+        );
+        // The search may stop short of any purchase that is enough, or settle on one that was
+        // not needed, and buying a couple of tokens can shift the sale's rounding down by one
+        // unit, so its proceeds may fall a unit short of the closed form's.
+        if let Some(searched) = searched {
+            assert!(received.unwrap() <= searched + 1);
+        }
+    }
+
+    #[test]
+    fn test_additional_tokens_edge_reserves() {
+        for (native_reserve, token_reserve) in [
+            (1_000, 1_000),
+            (1_000, 10u64.pow(18)),
+            (10u64.pow(15), 1_000),
+            (10u64.pow(15), 10u64.pow(18)),
+            (928_016_183_558_545, 59_049_485_951_161_203),
+        ] {
+            for fee_bps in [0, 1, 327, 999] {
+                let pool = LiquidityPool::new(native_reserve, token_reserve)
+                    .unwrap()
+                    .with_fee(fee_bps)
+                    .unwrap();
+                for sell_percent in [1, 38, 49] {
+                    for target_percent in [1, 12, 99, 299] {
+                        let sell_tokens = (token_reserve / 100 * sell_percent).max(1);
+                        let desired_native = (native_reserve / 100 * target_percent).max(1);
+                        check_additional_tokens(&pool, sell_tokens, desired_native);
+                    }
+                }
+            }
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_additional_tokens_match_search(
+            native_reserve in 1_000..=10u64.pow(15),
+            token_reserve in 1_000..=10u64.pow(18),
+            fee_bps in 0..MAX_FEE_BPS / 10,
+            protocol_fee_share_bps in 0..=MAX_FEE_BPS,
+            sell_percent in 1u64..50,
+            target_percent in 1u64..300,
+        ) {
+            let pool = LiquidityPool::new(native_reserve, token_reserve)
+                .unwrap()
+                .with_fee(fee_bps)
+                .unwrap()
+                .with_protocol_fee_share(protocol_fee_share_bps)
+                .unwrap();
+            let sell_tokens = (token_reserve / 100 * sell_percent).max(1);
+            let desired_native = (native_reserve / 100 * target_percent).max(1);
+            check_additional_tokens(&pool, sell_tokens, desired_native);
+        }
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(3_000_000 * 10u64.pow(6), None).unwrap();
+        pool.sell(7_000_000 * 10u64.pow(6), None).unwrap();
+        assert_eq!(pool.check_invariants(), Ok(()));
+        pool.native_reserve += 1;
+        assert_eq!(pool.check_invariants(), Err(PoolError::InvariantViolated));
+    }
+
+    #[cfg(feature = "strict-invariants")]
+    #[test]
+    fn test_strict_invariants_reject_corrupt_state() {
+        let mut pool = LiquidityPool::default();
+        let native_reserve = pool.get_native_reserve();
+        let mut projection = pool
+            .simulate(&TradeOp::Sell {
+                token_amount: 10u64.pow(6),
+                min_native: None,
+            })
+            .unwrap();
+        projection.native_reserve = native_reserve + 1;
+        assert_eq!(
+            pool.commit_trade(&projection),
+            Err(PoolError::InvariantViolated)
+        );
+        assert_eq!(pool.get_native_reserve(), native_reserve);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_pool() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut u = Unstructured::new(&[0u8; 32]);
+        let pool = LiquidityPool::arbitrary(&mut u).unwrap();
+        assert!(pool.get_native_reserve() > 0);
+        assert!(pool.get_token_reserve() > 0);
+        let mut u = Unstructured::new(&[0xff; 64]);
+        let op = TradeOp::arbitrary(&mut u).unwrap();
+        let _ = pool.quote(&op);
+    }
+
+    #[test]
+    fn test_quoting_does_not_allocate() {
+        let pool = LiquidityPool::default();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let buy = TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+        let sell = TradeOp::Sell {
+            token_amount,
+            min_native: Some(1),
+        };
+        let before = ALLOCATIONS.with(Cell::get);
+        for _ in 0..1_000 {
+            std::hint::black_box(pool.quote(&buy).unwrap());
+            std::hint::black_box(pool.simulate(&sell).unwrap());
+            std::hint::black_box(pool.simulate_saturating(&buy).unwrap());
+            std::hint::black_box(pool.simulate_spend_native(10u64.pow(9)).unwrap());
+            std::hint::black_box(pool.calculate_tokens_received(10u64.pow(9)).unwrap());
+            std::hint::black_box(pool.quote(&TradeOp::Buy {
+                token_amount: u64::MAX,
+                max_native: None,
+            }))
+            .unwrap_err();
+        }
+        assert_eq!(ALLOCATIONS.with(Cell::get), before);
+    }
+
     #[test]
     fn test_many_operations() {
         let mut pool = LiquidityPool::default();
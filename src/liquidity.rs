@@ -0,0 +1,277 @@
+#[cfg(feature = "std")]
+use crate::alert::{Alerts, ReserveChange};
+use crate::{LiquidityPool, PoolError, Result};
+
+/// LP shares locked forever when a pool is created, so the share supply can never return to
+/// zero and the first provider cannot inflate the share price.
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// Whether liquidity entered or left the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum LiquidityKind {
+    Add,
+    Remove,
+}
+
+/// A deposit or withdrawal of liquidity, as applied to the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct LiquidityChange {
+    /// Value of [`LiquidityPool::trade_count`] when the change was applied.
+    pub seq: u64,
+    pub kind: LiquidityKind,
+    /// Native currency deposited or withdrawn.
+    pub native_amount: u64,
+    /// Tokens deposited or withdrawn.
+    pub token_amount: u64,
+    /// LP shares minted or burned.
+    pub shares: u64,
+}
+
+/// Share supply of a freshly created pool and the part of it locked forever.
+pub(crate) fn initial_shares(constant_product: u128) -> (u64, u64) {
+    let supply = constant_product.isqrt() as u64;
+    (supply, supply.min(MINIMUM_LIQUIDITY))
+}
+
+impl LiquidityPool {
+    /// Returns the LP shares in existence, locked ones included.
+    pub fn lp_supply(&self) -> u64 {
+        self.lp_supply
+    }
+
+    /// Returns the LP shares locked at creation, which can never be removed.
+    pub fn locked_liquidity(&self) -> u64 {
+        self.locked_liquidity
+    }
+
+    /// Returns the native currency and tokens `shares` LP shares are a claim on, rounded down
+    /// as [`remove_liquidity`](Self::remove_liquidity) would pay them out.
+    pub fn position_value(&self, shares: u64) -> Result<(u64, u64)> {
+        // Virtual reserves back no LP position.
+        if self.has_virtual_reserves() {
+            return Err(PoolError::InvalidAmount);
+        }
+        if shares > self.lp_supply {
+            return Err(PoolError::InsufficientPoolFunds {
+                requested: shares,
+                available: self.lp_supply,
+            });
+        }
+        let supply = self.lp_supply as u128;
+        Ok((
+            (shares as u128 * self.native_reserve as u128 / supply) as u64,
+            (shares as u128 * self.token_reserve as u128 / supply) as u64,
+        ))
+    }
+
+    /// Returns the value of `shares` LP shares in native currency, with their tokens valued at
+    /// the spot price and rounded down.
+    pub fn position_value_in_native(&self, shares: u64) -> Result<u64> {
+        let (native, token) = self.position_value(shares)?;
+        let token_value = token as u128 * self.native_reserve as u128 / self.token_reserve as u128;
+        u64::try_from(native as u128 + token_value).map_err(|_| PoolError::Overflow)
+    }
+
+    /// Deposits at most `native_amount` and `token_amount` at the current reserve ratio and
+    /// mints LP shares in proportion. The limiting side is deposited in full, the other only
+    /// as far as the ratio requires, rounded up in favour of existing providers.
+    pub fn add_liquidity(
+        &mut self,
+        native_amount: u64,
+        token_amount: u64,
+    ) -> Result<LiquidityChange> {
+        if native_amount == 0 || token_amount == 0 || self.has_virtual_reserves() {
+            return Err(PoolError::InvalidAmount);
+        }
+        let supply = self.lp_supply as u128;
+        let shares = (native_amount as u128 * supply / self.native_reserve as u128)
+            .min(token_amount as u128 * supply / self.token_reserve as u128);
+        if shares == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let change = LiquidityChange {
+            seq: self.trade_count,
+            kind: LiquidityKind::Add,
+            native_amount: u64::try_from((shares * self.native_reserve as u128).div_ceil(supply))
+                .map_err(|_| PoolError::Overflow)?,
+            token_amount: u64::try_from((shares * self.token_reserve as u128).div_ceil(supply))
+                .map_err(|_| PoolError::Overflow)?,
+            shares: u64::try_from(shares).map_err(|_| PoolError::Overflow)?,
+        };
+        self.commit_liquidity(change)?;
+        Ok(change)
+    }
+
+    /// Burns `shares` LP shares and withdraws the matching fraction of both reserves, rounded
+    /// down in favour of the remaining providers. Locked shares cannot be removed.
+    pub fn remove_liquidity(&mut self, shares: u64) -> Result<LiquidityChange> {
+        // Virtual reserves cannot be withdrawn, so bonding curves have no liquidity to remove.
+        if shares == 0 || self.has_virtual_reserves() {
+            return Err(PoolError::InvalidAmount);
+        }
+        if shares > self.lp_supply - self.locked_liquidity {
+            return Err(PoolError::InsufficientPoolFunds {
+                requested: shares,
+                available: self.lp_supply - self.locked_liquidity,
+            });
+        }
+        let supply = self.lp_supply as u128;
+        let change = LiquidityChange {
+            seq: self.trade_count,
+            kind: LiquidityKind::Remove,
+            native_amount: (shares as u128 * self.native_reserve as u128 / supply) as u64,
+            token_amount: (shares as u128 * self.token_reserve as u128 / supply) as u64,
+            shares,
+        };
+        if change.native_amount == 0 || change.token_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.commit_liquidity(change)?;
+        Ok(change)
+    }
+
+    /// Applies a liquidity change, records it and notifies reserve alerts and observers.
+    fn commit_liquidity(&mut self, change: LiquidityChange) -> Result<()> {
+        #[cfg(feature = "std")]
+        let alert_state = Alerts::capture(self);
+        self.apply_liquidity(&change)?;
+        if let Some(history) = &mut self.history {
+            history.liquidity_changes.push(change);
+        }
+        #[cfg(feature = "std")]
+        if !self.alerts.is_empty() {
+            self.notify_detached(
+                |pool| &mut pool.alerts,
+                |alerts, pool| {
+                    alerts.notify_reserves(alert_state, pool, ReserveChange::Liquidity(change))
+                },
+            );
+        }
+        if !self.observers.is_empty() {
+            self.notify_detached(
+                |pool| &mut pool.observers,
+                |observers, pool| observers.notify_liquidity(&change, pool),
+            );
+        }
+        Ok(())
+    }
+
+    /// Moves the reserves and share supply by `change` and resets the constant product to
+    /// the new reserves. The market price reference is scaled with the token reserve so
+    /// [`market_price`](Self::market_price) only reflects trades.
+    pub(crate) fn apply_liquidity(&mut self, change: &LiquidityChange) -> Result<()> {
+        let (native_reserve, token_reserve, lp_supply) = match change.kind {
+            LiquidityKind::Add => (
+                self.native_reserve.checked_add(change.native_amount),
+                self.token_reserve.checked_add(change.token_amount),
+                self.lp_supply.checked_add(change.shares),
+            ),
+            LiquidityKind::Remove => (
+                self.native_reserve.checked_sub(change.native_amount),
+                self.token_reserve.checked_sub(change.token_amount),
+                self.lp_supply.checked_sub(change.shares),
+            ),
+        };
+        let (Some(native_reserve), Some(token_reserve), Some(lp_supply)) =
+            (native_reserve, token_reserve, lp_supply)
+        else {
+            return Err(PoolError::Overflow);
+        };
+        let initial_token_reserve = (self.initial_token_reserve as u128 * token_reserve as u128)
+            .div_ceil(self.token_reserve as u128);
+        self.initial_token_reserve = u64::try_from(initial_token_reserve)
+            .map_err(|_| PoolError::Overflow)?
+            .max(1);
+        self.native_reserve = native_reserve;
+        self.token_reserve = token_reserve;
+        self.constant_product = native_reserve as u128 * token_reserve as u128;
+        self.lp_supply = lp_supply;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove_liquidity() {
+        let mut pool = LiquidityPool::default();
+        let supply = pool.lp_supply();
+        assert_eq!(supply as u128, pool.get_constant_product().isqrt());
+        let price = pool.market_price();
+
+        let added = pool.add_liquidity(10u64.pow(9), u64::MAX).unwrap();
+        assert_eq!(added.native_amount, 10u64.pow(9));
+        assert_eq!(added.token_amount, 1_000_000_000 * 10u64.pow(6));
+        assert_eq!(added.shares, supply);
+        assert_eq!(pool.lp_supply(), 2 * supply);
+        assert_eq!(
+            pool.get_constant_product(),
+            4 * LiquidityPool::default().get_constant_product()
+        );
+        assert!((pool.market_price() - price).abs() < 1e-12);
+
+        let removed = pool.remove_liquidity(added.shares).unwrap();
+        assert_eq!(removed.native_amount, added.native_amount);
+        assert_eq!(removed.token_amount, added.token_amount);
+        assert_eq!(pool.lp_supply(), supply);
+    }
+
+    #[test]
+    fn test_position_value() {
+        let mut pool = LiquidityPool::default();
+        let supply = pool.lp_supply();
+        assert_eq!(
+            pool.position_value(supply).unwrap(),
+            (pool.get_native_reserve(), pool.get_token_reserve())
+        );
+        assert_eq!(
+            pool.position_value_in_native(supply / 2).unwrap(),
+            pool.get_native_reserve()
+        );
+        pool.buy(10u64.pow(14), None).unwrap();
+        let (native, token) = pool.position_value(supply / 4).unwrap();
+        let value = pool.position_value_in_native(supply / 4).unwrap();
+        assert!(value.abs_diff(2 * native) <= 1);
+        assert!(token < pool.get_token_reserve() / 4 + 1);
+        assert_eq!(
+            pool.position_value(supply + 1),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: supply + 1,
+                available: supply
+            })
+        );
+    }
+
+    #[test]
+    fn test_locked_liquidity() {
+        let mut pool = LiquidityPool::new(10u64.pow(6), 10u64.pow(6)).unwrap();
+        assert_eq!(pool.locked_liquidity(), MINIMUM_LIQUIDITY);
+        let removable = pool.lp_supply() - MINIMUM_LIQUIDITY;
+        assert_eq!(
+            pool.remove_liquidity(removable + 1),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: removable + 1,
+                available: removable
+            })
+        );
+        pool.remove_liquidity(removable).unwrap();
+        assert_eq!(pool.get_native_reserve(), 1_000);
+        assert_eq!(pool.get_constant_product(), 10u128.pow(6));
+        assert_eq!(
+            LiquidityPool::default().add_liquidity(1, 1),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
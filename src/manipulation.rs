@@ -0,0 +1,143 @@
+use crate::{Direction, LiquidityPool, PoolError, PoolSnapshot, Result, TradeOp, MAX_FEE_BPS};
+
+/// What it takes to push the spot price to a target, as returned by
+/// [`LiquidityPool::manipulation_cost`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManipulationCost {
+    /// Buys to push the price up, sells to push it down.
+    pub direction: Direction,
+    pub token_amount: u64,
+    pub target_price: f64,
+    /// Native committed to the trade: paid for the tokens, fee included, when buying, or the
+    /// value of the tokens sold at the spot price before the trade when selling.
+    pub capital: u64,
+    /// Native lost by trading the same tokens straight back once the price is reached, to the
+    /// fees and rounding.
+    pub round_trip_loss: u64,
+}
+
+impl LiquidityPool {
+    /// Returns the smallest trade moving the spot price by `move_bps` basis points, up if
+    /// positive and down if negative, with the capital it ties up and what undoing it costs.
+    /// Fails with [`PoolError::InvalidAmount`] for a move to a non-positive price, and like
+    /// [`amount_to_reach_price`](Self::amount_to_reach_price) if the pool cannot fill it.
+    pub fn manipulation_cost(&self, move_bps: i32) -> Result<ManipulationCost> {
+        self.cost_to_reach(self.spot_price() * (1.0 + move_bps as f64 / MAX_FEE_BPS as f64))
+    }
+
+    /// Returns the cheapest trade moving a time-weighted average price over `window` seconds by
+    /// `move_bps` basis points, holding the moved spot price for the last `hold` seconds of it.
+    /// The shorter the hold, the further the spot price must move. No arbitrage is assumed to
+    /// pull the price back while it is held, so the cost is a lower bound. Fails with
+    /// [`PoolError::InvalidAmount`] unless `hold` is within `1..=window`, and like
+    /// [`manipulation_cost`](Self::manipulation_cost) otherwise.
+    pub fn twap_manipulation_cost(
+        &self,
+        move_bps: i32,
+        window: u64,
+        hold: u64,
+    ) -> Result<ManipulationCost> {
+        if hold == 0 || hold > window {
+            return Err(PoolError::InvalidAmount);
+        }
+        // The average weighs the moved price by hold / window.
+        let spot_move = move_bps as f64 * window as f64 / (hold as f64 * MAX_FEE_BPS as f64);
+        self.cost_to_reach(self.spot_price() * (1.0 + spot_move))
+    }
+
+    fn cost_to_reach(&self, target_price: f64) -> Result<ManipulationCost> {
+        let (direction, token_amount) = self.amount_to_reach_price(target_price)?;
+        if token_amount == 0 {
+            return Ok(ManipulationCost {
+                direction,
+                token_amount,
+                target_price,
+                capital: 0,
+                round_trip_loss: 0,
+            });
+        }
+        let buy = TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+        let sell = TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        };
+        let (push, undo) = match direction {
+            Direction::BuyToken => (buy, sell),
+            Direction::SellToken => (sell, buy),
+        };
+        let mut pool = PoolSnapshot::of(self).to_pool();
+        let pushed = pool.simulate(&push)?;
+        pool.apply_trade(&pushed)?;
+        let undone = pool.simulate(&undo)?.native_amount;
+        let (capital, round_trip_loss) = match direction {
+            Direction::BuyToken => (
+                pushed.native_amount,
+                pushed.native_amount.saturating_sub(undone),
+            ),
+            Direction::SellToken => (
+                (token_amount as f64 * self.spot_price()) as u64,
+                undone.saturating_sub(pushed.native_amount),
+            ),
+        };
+        Ok(ManipulationCost {
+            direction,
+            token_amount,
+            target_price,
+            capital,
+            round_trip_loss,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manipulation_cost() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let up = pool.manipulation_cost(1_000).unwrap();
+        assert_eq!(up.direction, Direction::BuyToken);
+        let mut pushed = pool.clone();
+        pushed.buy(up.token_amount, None).unwrap();
+        assert!(pushed.spot_price() >= pool.spot_price() * 1.1);
+        assert_eq!(
+            up.capital,
+            pool.simulate_buy(up.token_amount, None).unwrap()
+        );
+        // About twice the 0.3% fee on the capital.
+        assert!(up.round_trip_loss > up.capital / 200 && up.round_trip_loss < up.capital / 100);
+
+        let down = pool.manipulation_cost(-1_000).unwrap();
+        assert_eq!(down.direction, Direction::SellToken);
+        assert!(down.round_trip_loss > 0);
+        assert_eq!(
+            pool.manipulation_cost(-10_000),
+            Err(PoolError::InvalidAmount)
+        );
+        assert_eq!(pool.manipulation_cost(0).unwrap().capital, 0);
+    }
+
+    #[test]
+    fn test_twap_manipulation_cost() {
+        let pool = LiquidityPool::default();
+        let spot = pool.manipulation_cost(500).unwrap();
+        assert_eq!(pool.twap_manipulation_cost(500, 600, 600), Ok(spot));
+        // Holding for a tenth of the window takes a 50% spot move.
+        let short = pool.twap_manipulation_cost(500, 600, 60).unwrap();
+        assert_eq!(short, pool.manipulation_cost(5_000).unwrap());
+        assert!(short.capital > spot.capital);
+        assert_eq!(
+            pool.twap_manipulation_cost(500, 600, 0),
+            Err(PoolError::InvalidAmount)
+        );
+        assert_eq!(
+            pool.twap_manipulation_cost(-2_000, 600, 60),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Renders the pools of a [`PoolMap`] in the Prometheus text exposition format, so a
+//! monitoring stack can scrape a running simulation or mirror service.
+
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+
+use crate::{LiquidityPool, PoolMap};
+
+type Sample = fn(&LiquidityPool) -> String;
+
+const METRICS: [(&str, &str, &str, Sample); 9] = [
+    (
+        "consta_pool_market_price",
+        "gauge",
+        "Native reserve per initial token.",
+        |pool| pool.market_price().to_string(),
+    ),
+    (
+        "consta_pool_spot_price",
+        "gauge",
+        "Marginal price of one token in native currency.",
+        |pool| pool.spot_price().to_string(),
+    ),
+    (
+        "consta_pool_native_reserve",
+        "gauge",
+        "Native currency held by the pool.",
+        |pool| pool.get_native_reserve().to_string(),
+    ),
+    (
+        "consta_pool_token_reserve",
+        "gauge",
+        "Tokens held by the pool.",
+        |pool| pool.get_token_reserve().to_string(),
+    ),
+    (
+        "consta_pool_lp_supply",
+        "gauge",
+        "LP shares in existence, locked ones included.",
+        |pool| pool.lp_supply().to_string(),
+    ),
+    (
+        "consta_pool_trades_total",
+        "counter",
+        "Trades executed since the pool was created.",
+        |pool| pool.trade_count().to_string(),
+    ),
+    (
+        "consta_pool_native_volume_total",
+        "counter",
+        "Native currency spent and received by all trades.",
+        |pool| pool.native_volume().to_string(),
+    ),
+    (
+        "consta_pool_fees_total",
+        "counter",
+        "Swap fees charged by all trades, in native currency.",
+        |pool| pool.fees_collected().to_string(),
+    ),
+    (
+        "consta_pool_constant_product_drift",
+        "gauge",
+        "Product of the reserves minus the constant product, caused by rounding.",
+        |pool| {
+            let product = pool.get_native_reserve() as i128 * pool.get_token_reserve() as i128;
+            (product - pool.get_constant_product() as i128).to_string()
+        },
+    ),
+];
+
+/// Renders every pool in `pools`, ordered by key, with the key as the `pool` label.
+pub fn render<K: Eq + Hash + Ord + Clone + Display>(pools: &PoolMap<K>) -> String {
+    let mut keys = pools.keys();
+    keys.sort_unstable();
+    let labelled: Vec<_> = keys
+        .into_iter()
+        .filter_map(|key| Some((escape(&key.to_string()), pools.get(&key)?)))
+        .collect();
+
+    let mut out = String::new();
+    for (name, kind, help, sample) in METRICS {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for (label, pool) in &labelled {
+            let _ = writeln!(out, "{name}{{pool=\"{label}\"}} {}", sample(pool));
+        }
+    }
+    out
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let pools = PoolMap::new();
+        let mut pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        pool.buy(500, None).unwrap();
+        pool.sell(100, None).unwrap();
+        pools.insert("a\"b".to_string(), pool);
+
+        let text = render(&pools);
+        assert!(text.contains("# TYPE consta_pool_trades_total counter\n"));
+        assert!(text.contains("consta_pool_native_reserve{pool=\"a\\\"b\"} 1666\n"));
+        assert!(text.contains("consta_pool_trades_total{pool=\"a\\\"b\"} 2\n"));
+        assert!(text.contains("consta_pool_native_volume_total{pool=\"a\\\"b\"} 1334\n"));
+        assert!(text.contains("consta_pool_constant_product_drift{pool=\"a\\\"b\"} -400\n"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::hash::Hash;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use crate::rng::SplitMix64;
+use crate::{LiquidityPool, PoolError, PoolMap, PoolSource, Result, TradeOp};
+
+/// An in-memory stand-in for a remote exchange, for integration tests of code that normally
+/// reads pools over the network.
+///
+/// Every call sleeps for the configured latency and fails with
+/// [`PoolError::SourceUnavailable`] at the configured rate. Failures are drawn from a seeded
+/// generator, so a test sees the same pattern of failures on every run.
+#[derive(Debug)]
+pub struct MockExchange<K> {
+    pools: PoolMap<K>,
+    latency: Duration,
+    jitter: Duration,
+    failure_rate: f64,
+    rng: Mutex<SplitMix64>,
+}
+
+/// A [`PoolSource`] reading a single pool from a [`MockExchange`].
+#[derive(Debug, Clone, Copy)]
+pub struct MockPoolSource<'a, K> {
+    exchange: &'a MockExchange<K>,
+    key: &'a K,
+}
+
+impl<K: Eq + Hash> MockExchange<K> {
+    /// Creates an exchange without latency or failures.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            pools: PoolMap::new(),
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            failure_rate: 0.0,
+            rng: Mutex::new(SplitMix64::new(seed)),
+        }
+    }
+
+    /// Delays every call by `latency` plus a random extra delay of up to `jitter`.
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    /// Makes each call fail with probability `failure_rate` (clamped to `[0, 1]`).
+    pub fn with_failure_rate(mut self, failure_rate: f64) -> Self {
+        self.failure_rate = failure_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn insert_pool(&self, key: K, pool: LiquidityPool) -> Option<LiquidityPool> {
+        self.pools.insert(key, pool)
+    }
+
+    /// Simulates a round trip to the exchange.
+    fn round_trip(&self) -> Result<()> {
+        let (delay, failed) = {
+            let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+            let jitter = rng.range_u64(0, self.jitter.as_nanos() as u64);
+            let failed = rng.next_f64() < self.failure_rate;
+            (self.latency + Duration::from_nanos(jitter), failed)
+        };
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        if failed {
+            return Err(PoolError::SourceUnavailable);
+        }
+        Ok(())
+    }
+
+    /// Fetches the current state of the pool stored under `key`.
+    pub fn fetch(&self, key: &K) -> Result<LiquidityPool> {
+        self.round_trip()?;
+        self.pools.get(key).ok_or(PoolError::PoolNotFound)
+    }
+
+    /// Executes `op` on the pool stored under `key`. Simulated failures happen before the
+    /// trade reaches the pool, which is left untouched.
+    pub fn execute(&self, key: &K, op: &TradeOp) -> Result<u64> {
+        self.round_trip()?;
+        self.pools.execute(key, op).ok_or(PoolError::PoolNotFound)?
+    }
+
+    /// Returns a [`PoolSource`] reading the pool stored under `key`.
+    pub fn source<'a>(&'a self, key: &'a K) -> MockPoolSource<'a, K> {
+        MockPoolSource {
+            exchange: self,
+            key,
+        }
+    }
+}
+
+impl<K: Eq + Hash> PoolSource for MockPoolSource<'_, K> {
+    fn fetch_pool(&self) -> Result<LiquidityPool> {
+        self.exchange.fetch(self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_fetch_and_execute() {
+        let exchange = MockExchange::new(0);
+        exchange.insert_pool("pool", LiquidityPool::default());
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let op = TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+        exchange.execute(&"pool", &op).unwrap();
+        let pool = exchange.source(&"pool").fetch_pool().unwrap();
+        assert_eq!(
+            pool.get_token_reserve(),
+            LiquidityPool::default().get_token_reserve() - token_amount
+        );
+        assert_eq!(
+            exchange.fetch(&"missing").unwrap_err(),
+            PoolError::PoolNotFound
+        );
+    }
+
+    #[test]
+    fn test_failures_are_reproducible() {
+        let outcomes = |seed| {
+            let exchange = MockExchange::new(seed).with_failure_rate(0.3);
+            exchange.insert_pool(0u8, LiquidityPool::default());
+            (0..100)
+                .map(|_| exchange.fetch(&0).is_ok())
+                .collect::<Vec<_>>()
+        };
+        let first = outcomes(9);
+        assert_eq!(first, outcomes(9));
+        let failures = first.iter().filter(|ok| !**ok).count();
+        assert!((15..45).contains(&failures));
+    }
+
+    #[test]
+    fn test_latency() {
+        let exchange =
+            MockExchange::new(0).with_latency(Duration::from_millis(5), Duration::from_millis(5));
+        exchange.insert_pool(0u8, LiquidityPool::default());
+        let start = Instant::now();
+        exchange.fetch(&0).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,163 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{ConstantProduct, Curve, LiquidityChange, LiquidityPool, TradeOp, TradeRecord};
+
+/// Hooks called after every mutation of a pool it is subscribed to, with the pool in its new
+/// state. Every hook does nothing by default. Observers must be thread-safe, as pools are
+/// shared across threads by pool maps and the sequencer.
+pub trait PoolObserver<C: Curve = ConstantProduct>: Send + Sync {
+    fn on_buy(&mut self, trade: &TradeRecord, pool: &LiquidityPool<C>) {
+        let _ = (trade, pool);
+    }
+
+    fn on_sell(&mut self, trade: &TradeRecord, pool: &LiquidityPool<C>) {
+        let _ = (trade, pool);
+    }
+
+    fn on_liquidity_change(&mut self, change: &LiquidityChange, pool: &LiquidityPool<C>) {
+        let _ = (change, pool);
+    }
+}
+
+/// Observers subscribed to a pool.
+///
+/// Like alert subscriptions, observers belong to the pool they were subscribed to: clones of
+/// the pool start without any.
+pub(crate) struct Observers<C: Curve = ConstantProduct>(Vec<Box<dyn PoolObserver<C>>>);
+
+impl<C: Curve> Default for Observers<C> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<C: Curve> Clone for Observers<C> {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl<C: Curve> fmt::Debug for Observers<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl<C: Curve> Observers<C> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn notify_trade(&mut self, trade: &TradeRecord, pool: &LiquidityPool<C>) {
+        for observer in &mut self.0 {
+            match trade.op {
+                TradeOp::Buy { .. } => observer.on_buy(trade, pool),
+                TradeOp::Sell { .. } => observer.on_sell(trade, pool),
+            }
+        }
+    }
+
+    pub(crate) fn notify_liquidity(&mut self, change: &LiquidityChange, pool: &LiquidityPool<C>) {
+        for observer in &mut self.0 {
+            observer.on_liquidity_change(change, pool);
+        }
+    }
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Subscribes `observer` to every later trade and liquidity change of the pool.
+    pub fn subscribe(&mut self, observer: Box<dyn PoolObserver<C>>) {
+        self.observers.0.push(observer);
+    }
+
+    /// Takes the subscriptions in `slot` out of the pool to `notify` them of its new state, and
+    /// puts them back afterwards, even if one of their callbacks panics.
+    pub(crate) fn notify_detached<T: Default>(
+        &mut self,
+        slot: fn(&mut Self) -> &mut T,
+        notify: impl FnOnce(&mut T, &Self),
+    ) {
+        struct Detached<'a, P, T: Default> {
+            pool: &'a mut P,
+            slot: fn(&mut P) -> &mut T,
+            subscriptions: T,
+        }
+
+        impl<P, T: Default> Drop for Detached<'_, P, T> {
+            fn drop(&mut self) {
+                *(self.slot)(self.pool) = core::mem::take(&mut self.subscriptions);
+            }
+        }
+
+        let subscriptions = core::mem::take(slot(self));
+        let mut detached = Detached {
+            pool: self,
+            slot,
+            subscriptions,
+        };
+        let Detached {
+            pool,
+            subscriptions,
+            ..
+        } = &mut detached;
+        notify(subscriptions, pool);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counts {
+        buys: u64,
+        sells: u64,
+        liquidity_changes: u64,
+        last_native_reserve: u64,
+    }
+
+    struct Counter(Arc<Mutex<Counts>>);
+
+    impl PoolObserver for Counter {
+        fn on_buy(&mut self, _: &TradeRecord, pool: &LiquidityPool) {
+            let mut counts = self.0.lock().unwrap();
+            counts.buys += 1;
+            counts.last_native_reserve = pool.get_native_reserve();
+        }
+
+        fn on_sell(&mut self, _: &TradeRecord, _: &LiquidityPool) {
+            self.0.lock().unwrap().sells += 1;
+        }
+
+        fn on_liquidity_change(&mut self, _: &LiquidityChange, _: &LiquidityPool) {
+            self.0.lock().unwrap().liquidity_changes += 1;
+        }
+    }
+
+    #[test]
+    fn test_observers_see_every_mutation() {
+        let counts = Arc::new(Mutex::new(Counts::default()));
+        let mut pool = LiquidityPool::default();
+        pool.subscribe(Box::new(Counter(counts.clone())));
+
+        pool.buy(10u64.pow(12), None).unwrap();
+        pool.sell(10u64.pow(11), None).unwrap();
+        pool.add_liquidity(10u64.pow(9), u64::MAX).unwrap();
+        assert!(pool.sell(u64::MAX, None).is_err());
+        let mut scratch = pool.clone();
+        scratch.buy(10u64.pow(12), None).unwrap();
+
+        let counts = counts.lock().unwrap();
+        assert_eq!(
+            (counts.buys, counts.sells, counts.liquidity_changes),
+            (1, 1, 1)
+        );
+        assert!(counts.last_native_reserve > 10u64.pow(9));
+    }
+}
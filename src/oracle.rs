@@ -0,0 +1,183 @@
+use alloc::collections::VecDeque;
+
+use crate::{LiquidityPool, PoolError, PriceQ64, Result};
+
+/// Fewest observations an oracle keeps: a TWAP needs one on each side of its window.
+const MIN_OBSERVATIONS: usize = 2;
+
+/// Cumulative spot price as of a timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Observation {
+    pub timestamp: u64,
+    /// Sum of the Q64.64 spot price over every second up to `timestamp`, wrapping on overflow
+    /// as only differences between observations are meaningful.
+    pub price_cumulative: u128,
+}
+
+/// Uniswap V2-style price accumulator, enabled with [`LiquidityPool::enable_oracle`].
+#[derive(Debug, Clone)]
+pub(crate) struct Oracle {
+    observations: VecDeque<Observation>,
+    capacity: usize,
+}
+
+impl Oracle {
+    fn new(timestamp: u64, capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_OBSERVATIONS);
+        let mut observations = VecDeque::with_capacity(capacity);
+        observations.push_back(Observation {
+            timestamp,
+            price_cumulative: 0,
+        });
+        Self {
+            observations,
+            capacity,
+        }
+    }
+
+    pub(crate) fn last(&self) -> Observation {
+        *self
+            .observations
+            .back()
+            .expect("an oracle always holds an observation")
+    }
+
+    /// Returns the cumulative price at `timestamp`, interpolated between the observations
+    /// around it, along which the price was constant or averaged.
+    fn cumulative_at(&self, timestamp: u64) -> Option<u128> {
+        let index = self
+            .observations
+            .partition_point(|observation| observation.timestamp <= timestamp);
+        let before = self.observations.get(index.checked_sub(1)?)?;
+        if before.timestamp == timestamp {
+            return Some(before.price_cumulative);
+        }
+        let after = self.observations.get(index)?;
+        let average = after.price_cumulative.wrapping_sub(before.price_cumulative)
+            / (after.timestamp - before.timestamp) as u128;
+        Some(
+            before
+                .price_cumulative
+                .wrapping_add(average.wrapping_mul((timestamp - before.timestamp) as u128)),
+        )
+    }
+}
+
+impl LiquidityPool {
+    /// Starts accumulating the spot price from `now`, a timestamp in seconds, keeping the last
+    /// `capacity` observations (at least two). Any previous observations are discarded.
+    ///
+    /// The price is accumulated for the time elapsed since the previous timestamp whenever
+    /// [`tick`](Self::tick), [`buy_at`](Self::buy_at) or [`sell_at`](Self::sell_at) is called,
+    /// before the trade, as in Uniswap V2. Trades applied without a timestamp count as if made
+    /// right after the last one.
+    pub fn enable_oracle(&mut self, now: u64, capacity: usize) {
+        self.oracle = Some(Oracle::new(now, capacity));
+    }
+
+    pub fn disable_oracle(&mut self) {
+        self.oracle = None;
+    }
+
+    /// Accumulates the current spot price up to `now`. Fails with
+    /// [`PoolError::HistoryUnavailable`] if the oracle is disabled and
+    /// [`PoolError::InvalidAmount`] if `now` precedes the last timestamp.
+    pub fn tick(&mut self, now: u64) -> Result<()> {
+        let price = self.spot_price_q64();
+        let oracle = self.oracle.as_mut().ok_or(PoolError::HistoryUnavailable)?;
+        let last = oracle.last();
+        if now < last.timestamp {
+            return Err(PoolError::InvalidAmount);
+        }
+        if now == last.timestamp {
+            return Ok(());
+        }
+        if oracle.observations.len() == oracle.capacity {
+            oracle.observations.pop_front();
+        }
+        oracle.observations.push_back(Observation {
+            timestamp: now,
+            price_cumulative: last
+                .price_cumulative
+                .wrapping_add(price.0.wrapping_mul((now - last.timestamp) as u128)),
+        });
+        Ok(())
+    }
+
+    /// [`tick`](Self::tick)s to `now`, then [`buy`](Self::buy)s.
+    pub fn buy_at(&mut self, now: u64, token_amount: u64, max_native: Option<u64>) -> Result<u64> {
+        self.tick(now)?;
+        self.buy(token_amount, max_native)
+    }
+
+    /// [`tick`](Self::tick)s to `now`, then [`sell`](Self::sell)s.
+    pub fn sell_at(&mut self, now: u64, token_amount: u64, min_native: Option<u64>) -> Result<u64> {
+        self.tick(now)?;
+        self.sell(token_amount, min_native)
+    }
+
+    /// Returns the latest observation, or `None` if the oracle is disabled.
+    pub fn price_cumulative(&self) -> Option<Observation> {
+        self.oracle.as_ref().map(Oracle::last)
+    }
+
+    /// Returns the time-weighted average spot price over the `window` seconds up to the last
+    /// timestamp. Fails with [`PoolError::InvalidAmount`] for an empty window and
+    /// [`PoolError::HistoryUnavailable`] if the oracle is disabled or its observations do not
+    /// reach back far enough.
+    pub fn twap(&self, window: u64) -> Result<PriceQ64> {
+        if window == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let oracle = self.oracle.as_ref().ok_or(PoolError::HistoryUnavailable)?;
+        let last = oracle.last();
+        let start = last
+            .timestamp
+            .checked_sub(window)
+            .and_then(|start| oracle.cumulative_at(start))
+            .ok_or(PoolError::HistoryUnavailable)?;
+        Ok(PriceQ64(
+            last.price_cumulative.wrapping_sub(start) / window as u128,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twap() {
+        let mut pool = LiquidityPool::new(10u64.pow(12), 10u64.pow(12)).unwrap();
+        assert_eq!(pool.tick(0), Err(PoolError::HistoryUnavailable));
+        pool.enable_oracle(1_000, 8);
+        // Price 1 for 100 seconds, then about 4 for 100 seconds.
+        pool.buy_at(1_100, 10u64.pow(12) / 2, None).unwrap();
+        let pumped = pool.spot_price_q64();
+        pool.tick(1_200).unwrap();
+
+        assert_eq!(pool.twap(100), Ok(pumped));
+        let twap = pool.twap(200).unwrap();
+        assert_eq!(twap.0, (PriceQ64::ONE.0 + pumped.0) / 2);
+        // Halfway through the first interval.
+        assert_eq!(
+            pool.twap(150).unwrap().0,
+            (PriceQ64::ONE.0 * 50 + pumped.0 * 100) / 150
+        );
+        assert_eq!(pool.twap(201), Err(PoolError::HistoryUnavailable));
+        assert_eq!(pool.tick(1_199), Err(PoolError::InvalidAmount));
+        assert_eq!(pool.price_cumulative().unwrap().timestamp, 1_200);
+    }
+
+    #[test]
+    fn test_observations_are_bounded() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_oracle(0, 2);
+        for now in 1..=10 {
+            pool.tick(now * 10).unwrap();
+        }
+        assert!(pool.twap(10).is_ok());
+        assert_eq!(pool.twap(11), Err(PoolError::HistoryUnavailable));
+    }
+}
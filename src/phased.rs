@@ -0,0 +1,237 @@
+use alloc::vec::Vec;
+
+use crate::{LiquidityPool, PoolError, PoolSnapshot, Result, TradeOp};
+
+/// One segment of a [`PhasedPool`]: a constant-product curve used until its native reserve
+/// reaches `native_threshold`.
+#[derive(Debug, Clone)]
+pub struct Phase {
+    pub pool: LiquidityPool,
+    /// Native reserve at which trading moves on to the next phase; `None` for the last phase.
+    pub native_threshold: Option<u64>,
+}
+
+/// A launch curve made of consecutive phases with different parameters, e.g. a shallow,
+/// steep initial segment followed by a deep, flat one acting as a price floor.
+///
+/// Buys that push the current phase's native reserve to its threshold continue in the next
+/// phase; sells that bring a phase back to its starting state continue in the previous one.
+#[derive(Debug, Clone)]
+pub struct PhasedPool {
+    phases: Vec<Phase>,
+    starts: Vec<PoolSnapshot>,
+    current: usize,
+}
+
+impl PhasedPool {
+    /// Fails with [`PoolError::InvalidAmount`] unless there is at least one phase, every
+    /// phase but the last has a threshold above its native reserve, and the last has none.
+    pub fn new(phases: Vec<Phase>) -> Result<Self> {
+        let Some((last, rest)) = phases.split_last() else {
+            return Err(PoolError::InvalidAmount);
+        };
+        let valid = last.native_threshold.is_none()
+            && rest.iter().all(|phase| {
+                phase
+                    .native_threshold
+                    .is_some_and(|threshold| threshold > phase.pool.get_native_reserve())
+            });
+        if !valid {
+            return Err(PoolError::InvalidAmount);
+        }
+        let starts = phases
+            .iter()
+            .map(|phase| PoolSnapshot::of(&phase.pool))
+            .collect();
+        Ok(Self {
+            phases,
+            starts,
+            current: 0,
+        })
+    }
+
+    /// Index of the phase trades are currently applied to.
+    pub fn current_phase(&self) -> usize {
+        self.current
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    /// Market price of the current phase.
+    pub fn market_price(&self) -> f64 {
+        self.phases[self.current].pool.market_price()
+    }
+
+    /// Returns the native currency `op` would spend (buy) or receive (sell) across phases,
+    /// enforcing its slippage limit.
+    pub fn quote(&self, op: &TradeOp) -> Result<u64> {
+        self.clone().execute(op)
+    }
+
+    /// Executes `op`, moving between phases as thresholds are crossed. The trade is applied
+    /// entirely or not at all.
+    pub fn execute(&mut self, op: &TradeOp) -> Result<u64> {
+        let mut next = self.clone();
+        let native_amount = match *op {
+            TradeOp::Buy {
+                token_amount,
+                max_native,
+            } => {
+                let spent = next.buy(token_amount)?;
+                if let Some(max_native) = max_native.filter(|&max_native| spent > max_native) {
+                    return Err(PoolError::SlippageExceeded {
+                        limit: max_native,
+                        actual: spent,
+                    });
+                }
+                spent
+            }
+            TradeOp::Sell {
+                token_amount,
+                min_native,
+            } => {
+                let received = next.sell(token_amount)?;
+                if let Some(min_native) = min_native.filter(|&min_native| received < min_native) {
+                    return Err(PoolError::SlippageExceeded {
+                        limit: min_native,
+                        actual: received,
+                    });
+                }
+                received
+            }
+        };
+        *self = next;
+        Ok(native_amount)
+    }
+
+    fn buy(&mut self, mut token_amount: u64) -> Result<u64> {
+        if token_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let mut spent = 0u64;
+        loop {
+            let phase = &mut self.phases[self.current];
+            // Tokens purchasable before the native reserve would exceed the threshold.
+            let available = match phase.native_threshold {
+                Some(threshold) => {
+                    let min_token_reserve = phase
+                        .pool
+                        .get_constant_product()
+                        .div_ceil(threshold as u128);
+                    (phase.pool.get_token_reserve() as u128).saturating_sub(min_token_reserve)
+                        as u64
+                }
+                None => u64::MAX,
+            };
+            let fill = token_amount.min(available);
+            if fill > 0 {
+                let native = phase.pool.buy(fill, None)?;
+                spent = spent.checked_add(native).ok_or(PoolError::Overflow)?;
+                token_amount -= fill;
+            }
+            if token_amount == 0 {
+                return Ok(spent);
+            }
+            self.current += 1;
+        }
+    }
+
+    fn sell(&mut self, mut token_amount: u64) -> Result<u64> {
+        if token_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let mut received = 0u64;
+        loop {
+            let phase = &mut self.phases[self.current];
+            // Earlier phases take over once this one is back to its starting reserves.
+            let available = if self.current == 0 {
+                u64::MAX
+            } else {
+                self.starts[self.current].token_reserve() - phase.pool.get_token_reserve()
+            };
+            let fill = token_amount.min(available);
+            if fill > 0 {
+                let native = phase.pool.sell(fill, None)?;
+                received = received.checked_add(native).ok_or(PoolError::Overflow)?;
+                token_amount -= fill;
+            }
+            if token_amount == 0 {
+                return Ok(received);
+            }
+            self.current -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_phases() -> PhasedPool {
+        let steep = LiquidityPool::new(10u64.pow(9), 10u64.pow(15)).unwrap();
+        let flat = LiquidityPool::new(10u64.pow(11), 5 * 10u64.pow(16)).unwrap();
+        PhasedPool::new(vec![
+            Phase {
+                pool: steep,
+                native_threshold: Some(2 * 10u64.pow(9)),
+            },
+            Phase {
+                pool: flat,
+                native_threshold: None,
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_transitions_across_phases() {
+        let mut pool = two_phases();
+        let half = 5 * 10u64.pow(14);
+        let extra = 10u64.pow(12);
+        let spent = pool
+            .execute(&TradeOp::Buy {
+                token_amount: half + extra,
+                max_native: None,
+            })
+            .unwrap();
+        assert_eq!(pool.current_phase(), 1);
+        assert_eq!(pool.phases()[0].pool.get_native_reserve(), 2 * 10u64.pow(9));
+        assert_eq!(
+            pool.phases()[1].pool.get_token_reserve(),
+            5 * 10u64.pow(16) - extra
+        );
+        assert_eq!(
+            spent,
+            10u64.pow(9) + pool.phases()[1].pool.get_native_reserve() - 10u64.pow(11)
+        );
+
+        pool.execute(&TradeOp::Sell {
+            token_amount: half + extra,
+            min_native: None,
+        })
+        .unwrap();
+        assert_eq!(pool.current_phase(), 0);
+        assert_eq!(pool.phases()[0].pool.get_token_reserve(), 10u64.pow(15));
+    }
+
+    #[test]
+    fn test_failed_trade_is_atomic() {
+        let mut pool = two_phases();
+        let op = TradeOp::Buy {
+            token_amount: 6 * 10u64.pow(14),
+            max_native: Some(10u64.pow(9)),
+        };
+        assert!(matches!(
+            pool.execute(&op),
+            Err(PoolError::SlippageExceeded {
+                limit: 1_000_000_000,
+                ..
+            })
+        ));
+        assert_eq!(pool.current_phase(), 0);
+        assert_eq!(pool.phases()[0].pool.get_native_reserve(), 10u64.pow(9));
+        assert!(PhasedPool::new(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Realized and unrealized PnL of a sequence of trades, such as a pool's recorded history or
+//! the trades a bot made.
+//!
+//! Tokens bought form the position; each sell realizes the native currency received against
+//! the cost of the tokens it gave up, as set by the [`CostMethod`]. Amounts are taken as
+//! recorded, fees included and transfer taxes not deducted.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{PoolError, Result, TradeOp, TradeRecord};
+
+/// How the cost of the tokens a sell gives up is determined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CostMethod {
+    /// Sells give up the oldest tokens bought first, at what they cost.
+    Fifo,
+    /// Sells give up tokens at the average cost of the position.
+    #[default]
+    AverageCost,
+}
+
+/// A trade and the state of the position once it was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LedgerEntry {
+    pub seq: u64,
+    pub op: TradeOp,
+    /// Native currency spent (buy) or received (sell), fee included.
+    pub native_amount: u64,
+    pub fee: u64,
+    /// PnL realized by the trade, zero for buys.
+    pub realized_pnl: i128,
+    /// Tokens held after the trade.
+    pub token_balance: u64,
+    /// Cost of the tokens held after the trade.
+    pub cost_basis: u128,
+}
+
+/// PnL of a sequence of trades, with a ledger entry per trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PnlReport {
+    pub method: CostMethod,
+    pub entries: Vec<LedgerEntry>,
+    /// Sum of the PnL realized by every sell.
+    pub realized_pnl: i128,
+    /// Tokens still held.
+    pub token_balance: u64,
+    /// Cost of the tokens still held.
+    pub cost_basis: u128,
+    /// Swap fees paid across all trades.
+    pub fees: u128,
+}
+
+impl PnlReport {
+    /// Value of the tokens held at `spot_price` beyond their cost.
+    pub fn unrealized_pnl(&self, spot_price: f64) -> f64 {
+        self.token_balance as f64 * spot_price - self.cost_basis as f64
+    }
+
+    /// Realized and unrealized PnL at `spot_price`.
+    pub fn total_pnl(&self, spot_price: f64) -> f64 {
+        self.realized_pnl as f64 + self.unrealized_pnl(spot_price)
+    }
+
+    /// Native currency paid per token held, or `None` without tokens.
+    pub fn average_entry_price(&self) -> Option<f64> {
+        (self.token_balance > 0).then(|| self.cost_basis as f64 / self.token_balance as f64)
+    }
+}
+
+/// Accounts for `records` in order with `method`. Fails with
+/// [`PoolError::InsufficientBalance`] if a sell gives up more tokens than were bought before
+/// it, since their cost is unknown.
+pub fn report(records: &[TradeRecord], method: CostMethod) -> Result<PnlReport> {
+    // Tokens bought and what they cost, oldest first, for FIFO.
+    let mut lots: VecDeque<(u64, u128)> = VecDeque::new();
+    let mut report = PnlReport {
+        method,
+        entries: Vec::with_capacity(records.len()),
+        realized_pnl: 0,
+        token_balance: 0,
+        cost_basis: 0,
+        fees: 0,
+    };
+    for record in records {
+        let token_amount = record.op.token_amount();
+        let native_amount = record.native_amount as u128;
+        let realized_pnl = match record.op {
+            TradeOp::Buy { .. } => {
+                report.token_balance = report
+                    .token_balance
+                    .checked_add(token_amount)
+                    .ok_or(PoolError::Overflow)?;
+                report.cost_basis += native_amount;
+                if method == CostMethod::Fifo {
+                    lots.push_back((token_amount, native_amount));
+                }
+                0
+            }
+            TradeOp::Sell { .. } => {
+                if token_amount > report.token_balance {
+                    return Err(PoolError::InsufficientBalance {
+                        requested: token_amount,
+                        available: report.token_balance,
+                    });
+                }
+                let cost = match method {
+                    CostMethod::Fifo => take_lots(&mut lots, token_amount),
+                    CostMethod::AverageCost => {
+                        report.cost_basis * token_amount as u128 / report.token_balance as u128
+                    }
+                };
+                report.token_balance -= token_amount;
+                report.cost_basis -= cost;
+                native_amount as i128 - cost as i128
+            }
+        };
+        report.realized_pnl += realized_pnl;
+        report.fees += record.fee as u128;
+        report.entries.push(LedgerEntry {
+            seq: record.seq,
+            op: record.op,
+            native_amount: record.native_amount,
+            fee: record.fee,
+            realized_pnl,
+            token_balance: report.token_balance,
+            cost_basis: report.cost_basis,
+        });
+    }
+    Ok(report)
+}
+
+/// Removes `token_amount` tokens from the oldest lots and returns their cost, splitting the
+/// last lot pro rata.
+fn take_lots(lots: &mut VecDeque<(u64, u128)>, mut token_amount: u64) -> u128 {
+    let mut cost = 0;
+    while token_amount > 0 {
+        let Some(lot) = lots.front_mut() else {
+            break;
+        };
+        if lot.0 <= token_amount {
+            token_amount -= lot.0;
+            cost += lot.1;
+            lots.pop_front();
+        } else {
+            let part = lot.1 * token_amount as u128 / lot.0 as u128;
+            lot.0 -= token_amount;
+            lot.1 -= part;
+            cost += part;
+            token_amount = 0;
+        }
+    }
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(seq: u64, buy: bool, token_amount: u64, native_amount: u64) -> TradeRecord {
+        let op = if buy {
+            TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            }
+        } else {
+            TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            }
+        };
+        TradeRecord {
+            seq,
+            op,
+            native_amount,
+            fee: 1,
+            protocol_fee: 0,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_fifo_and_average_cost() {
+        let records = [
+            record(1, true, 100, 1_000),
+            record(2, true, 100, 3_000),
+            record(3, false, 150, 3_000),
+        ];
+        let fifo = report(&records, CostMethod::Fifo).unwrap();
+        // The first lot and half the second: 1,000 + 1,500.
+        assert_eq!(fifo.realized_pnl, 500);
+        assert_eq!((fifo.token_balance, fifo.cost_basis), (50, 1_500));
+        assert_eq!(fifo.fees, 3);
+
+        let average = report(&records, CostMethod::AverageCost).unwrap();
+        // 150 tokens at an average of 20.
+        assert_eq!(average.realized_pnl, 0);
+        assert_eq!(average.cost_basis, 1_000);
+        assert_eq!(average.average_entry_price(), Some(20.0));
+        assert_eq!(average.unrealized_pnl(30.0), 500.0);
+        assert_eq!(average.total_pnl(30.0), 500.0);
+        assert_eq!(average.entries[2].realized_pnl, 0);
+        assert_eq!(average.entries[1].cost_basis, 4_000);
+
+        assert_eq!(
+            report(&records[2..], CostMethod::Fifo),
+            Err(PoolError::InsufficientBalance {
+                requested: 150,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_report_pool_history() {
+        let mut pool = crate::LiquidityPool::default().with_fee(30).unwrap();
+        pool.enable_history();
+        let paid = pool.buy(10u64.pow(12), None).unwrap();
+        let received = pool.sell(10u64.pow(12), None).unwrap();
+        let report = report(pool.history(), CostMethod::default()).unwrap();
+        assert_eq!(report.realized_pnl, received as i128 - paid as i128);
+        assert_eq!(report.token_balance, 0);
+        assert_eq!(report.fees, pool.fees_collected());
+        assert_eq!(report.entries.len(), 2);
+    }
+}
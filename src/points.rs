@@ -0,0 +1,92 @@
+use crate::{Curve, CurveState, Direction, LiquidityPool, TradeOp};
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Samples the curve at `n` token reserves evenly spaced from the current one down to the
+    /// last one buying can reach, yielding `(token_reserve, spot_price)` for plotting. Stops
+    /// early at the first reserve the curve cannot price.
+    pub fn curve_points(&self, n: usize) -> impl Iterator<Item = (u64, f64)> + '_ {
+        let state = self.curve_state();
+        let span = self.token_reserve - self.lowest_token_reserve();
+        (0..n).map_while(move |i| {
+            let bought = sample(span, i, n.saturating_sub(1));
+            let native_in = self
+                .curve
+                .amount_in(&state, Direction::BuyToken, bought)
+                .ok()?;
+            let point = CurveState {
+                native_reserve: state.native_reserve.checked_add(native_in)?,
+                token_reserve: state.token_reserve - bought,
+                ..state
+            };
+            Some((point.token_reserve, self.curve.spot_price(&point)))
+        })
+    }
+
+    /// Samples buys of `n` sizes evenly spaced up to the largest the pool can fill, yielding
+    /// `(token_amount, execution_price)` with the price in native currency per token, fee
+    /// included. Stops early at the first size the pool cannot quote.
+    pub fn execution_points(&self, n: usize) -> impl Iterator<Item = (u64, f64)> + '_ {
+        let span = self.token_reserve - self.lowest_token_reserve();
+        (1..=n).map_while(move |i| {
+            let token_amount = sample(span, i, n);
+            let projection = self
+                .simulate(&TradeOp::Buy {
+                    token_amount,
+                    max_native: None,
+                })
+                .ok()?;
+            Some((
+                token_amount,
+                projection.native_amount as f64 / token_amount as f64,
+            ))
+        })
+    }
+
+    /// Smallest token reserve a buy can leave: the virtual part, and never an empty reserve.
+    fn lowest_token_reserve(&self) -> u64 {
+        self.virtual_token.max(1).min(self.token_reserve)
+    }
+}
+
+/// The `i`-th of `steps` even steps across `span`, rounded down.
+fn sample(span: u64, i: usize, steps: usize) -> u64 {
+    if steps == 0 {
+        return 0;
+    }
+    (span as u128 * i as u128 / steps as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_points() {
+        let pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        let points: Vec<_> = pool.curve_points(4).collect();
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0], (1_000, 1.0));
+        assert_eq!(points[3].0, 1);
+        // A third of the 999 tokens that can be bought.
+        assert_eq!(points[1].0, 667);
+        assert!(points.windows(2).all(|pair| pair[1].1 > pair[0].1));
+        assert_eq!(pool.curve_points(1).collect::<Vec<_>>(), vec![(1_000, 1.0)]);
+        assert_eq!(pool.curve_points(0).count(), 0);
+    }
+
+    #[test]
+    fn test_execution_points() {
+        let pool = LiquidityPool::new(1_000, 1_000)
+            .unwrap()
+            .with_fee(100)
+            .unwrap();
+        let points: Vec<_> = pool.execution_points(3).collect();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].0, 333);
+        assert_eq!(points[2].0, 999);
+        for (token_amount, price) in points {
+            let paid = pool.simulate_buy(token_amount, None).unwrap();
+            assert_eq!(price, paid as f64 / token_amount as f64);
+        }
+    }
+}
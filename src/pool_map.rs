@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{LiquidityPool, Result, TradeOp};
+
+const DEFAULT_SHARD_COUNT: usize = 16;
+
+type Shard<K> = RwLock<HashMap<K, LiquidityPool>>;
+
+/// A concurrent collection of pools, split across independently locked shards.
+///
+/// Readers and writers only contend when their keys hash to the same shard, so a live
+/// feed can keep updating pools while other threads quote against them.
+#[derive(Debug)]
+pub struct PoolMap<K> {
+    shards: Box<[Shard<K>]>,
+    hasher: RandomState,
+}
+
+impl<K: Eq + Hash> PoolMap<K> {
+    /// Creates an empty map with the default number of shards.
+    pub fn new() -> Self {
+        Self::with_shard_count(DEFAULT_SHARD_COUNT)
+    }
+
+    /// Creates an empty map with `shard_count` shards (at least one).
+    pub fn with_shard_count(shard_count: usize) -> Self {
+        let shards = (0..shard_count.max(1))
+            .map(|_| RwLock::new(HashMap::new()))
+            .collect();
+        Self {
+            shards,
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard(&self, key: &K) -> &Shard<K> {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        &self.shards[index]
+    }
+
+    // Shards are only written by the map operations below and by `execute`. The only user code
+    // `execute` runs is alert callbacks and observers, after the trade is committed and with
+    // the pool putting its subscriptions back if one panics, so a poisoned shard still holds
+    // consistent pools and poisoning can be ignored. Callbacks after the panicking one miss
+    // that trade.
+    fn read_shard(shard: &Shard<K>) -> RwLockReadGuard<'_, HashMap<K, LiquidityPool>> {
+        shard.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_shard(shard: &Shard<K>) -> RwLockWriteGuard<'_, HashMap<K, LiquidityPool>> {
+        shard.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Inserts a pool, returning the one previously stored under `key`, if any.
+    pub fn insert(&self, key: K, pool: LiquidityPool) -> Option<LiquidityPool> {
+        Self::write_shard(self.shard(&key)).insert(key, pool)
+    }
+
+    pub fn remove(&self, key: &K) -> Option<LiquidityPool> {
+        Self::write_shard(self.shard(key)).remove(key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        Self::read_shard(self.shard(key)).contains_key(key)
+    }
+
+    /// Returns a copy of the pool stored under `key`.
+    pub fn get(&self, key: &K) -> Option<LiquidityPool> {
+        Self::read_shard(self.shard(key)).get(key).cloned()
+    }
+
+    /// Runs `f` against the pool under a shared lock, e.g. to quote a trade.
+    pub fn read<R>(&self, key: &K, f: impl FnOnce(&LiquidityPool) -> R) -> Option<R> {
+        Self::read_shard(self.shard(key)).get(key).map(f)
+    }
+
+    /// Executes `op` against the pool under an exclusive lock, as
+    /// [`LiquidityPool::execute`]. Other changes go through [`get`](Self::get) and
+    /// [`insert`](Self::insert).
+    pub fn execute(&self, key: &K, op: &TradeOp) -> Option<Result<u64>> {
+        Self::write_shard(self.shard(key))
+            .get_mut(key)
+            .map(|pool| pool.execute(op))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| Self::read_shard(shard).len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards
+            .iter()
+            .all(|shard| Self::read_shard(shard).is_empty())
+    }
+
+    /// Returns the keys currently stored. Shards are locked one at a time, so concurrent
+    /// inserts and removals may or may not be reflected.
+    pub fn keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.shards
+            .iter()
+            .flat_map(|shard| Self::read_shard(shard).keys().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+impl<K: Eq + Hash> Default for PoolMap<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::{PoolObserver, TradeRecord};
+
+    /// Counts buys, panicking on the first one.
+    struct FlakyObserver(Arc<AtomicU64>);
+
+    impl PoolObserver for FlakyObserver {
+        fn on_buy(&mut self, _: &TradeRecord, _: &LiquidityPool) {
+            if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("observer failure");
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let map = PoolMap::new();
+        assert!(map.is_empty());
+        assert!(map.insert("a", LiquidityPool::default()).is_none());
+        assert!(map.contains_key(&"a"));
+        assert_eq!(map.len(), 1);
+        let pool = map.get(&"a").unwrap();
+        assert_eq!(
+            pool.get_token_reserve(),
+            LiquidityPool::default().get_token_reserve()
+        );
+        assert!(map.remove(&"a").is_some());
+        assert!(map.get(&"a").is_none());
+    }
+
+    #[test]
+    fn test_concurrent_updates_and_quotes() {
+        let map = Arc::new(PoolMap::with_shard_count(4));
+        for key in 0..64u32 {
+            map.insert(key, LiquidityPool::default());
+        }
+        let token_amount = 1_000 * 10u64.pow(6);
+        let buy = TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+
+        let handles: Vec<_> = (0..8)
+            .map(|thread_index| {
+                let map = Arc::clone(&map);
+                thread::spawn(move || {
+                    for key in 0..64u32 {
+                        if thread_index % 2 == 0 {
+                            map.execute(&key, &buy).unwrap().unwrap();
+                        } else {
+                            map.read(&key, |pool| pool.simulate_sell(token_amount, None))
+                                .unwrap()
+                                .unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let expected = LiquidityPool::default().get_token_reserve() - 4 * token_amount;
+        for key in map.keys() {
+            assert_eq!(
+                map.read(&key, |pool| pool.get_token_reserve()),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_panicking_observer() {
+        let map = PoolMap::new();
+        let buys = Arc::new(AtomicU64::new(0));
+        let mut pool = LiquidityPool::default();
+        pool.subscribe(Box::new(FlakyObserver(Arc::clone(&buys))));
+        map.insert("a", pool);
+        let buy = TradeOp::Buy {
+            token_amount: 1_000 * 10u64.pow(6),
+            max_native: None,
+        };
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| map.execute(&"a", &buy))).is_err());
+        assert_eq!(map.read(&"a", |pool| pool.trade_count()), Some(1));
+        map.execute(&"a", &buy).unwrap().unwrap();
+        assert_eq!(buys.load(Ordering::SeqCst), 2);
+        assert_eq!(map.read(&"a", |pool| pool.trade_count()), Some(2));
+    }
+}
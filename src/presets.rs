@@ -0,0 +1,56 @@
+//! Pools configured like well-known launchpads.
+
+use crate::{GraduationTarget, LiquidityPool, MAX_FEE_BPS};
+
+/// Virtual SOL a pump.fun curve starts with, in lamports.
+pub const PUMPFUN_VIRTUAL_SOL: u64 = 30 * 10u64.pow(9);
+/// Virtual tokens a pump.fun curve starts with, on top of the real ones, in base units.
+pub const PUMPFUN_VIRTUAL_TOKENS: u64 = 279_900_000 * 10u64.pow(6);
+/// Tokens sold along a pump.fun curve, in base units.
+pub const PUMPFUN_REAL_TOKENS: u64 = 793_100_000 * 10u64.pow(6);
+/// Total supply of a pump.fun token, in base units; what the curve does not sell is set aside
+/// for the liquidity migrated at graduation.
+pub const PUMPFUN_TOKEN_SUPPLY: u64 = 1_000_000_000 * 10u64.pow(6);
+/// Swap fee of a pump.fun curve.
+pub const PUMPFUN_FEE_BPS: u16 = 100;
+
+/// Returns a fresh pump.fun bonding curve: 30 virtual SOL against 1.073 billion tokens, 793.1
+/// million of them real, a 1% fee paid to the protocol rather than the curve, SOL with 9
+/// decimals and tokens with 6, graduating once every real token is sold, which takes about
+/// 85 SOL.
+pub fn pumpfun() -> LiquidityPool {
+    LiquidityPool::with_virtual_reserves(
+        0,
+        PUMPFUN_REAL_TOKENS,
+        PUMPFUN_VIRTUAL_SOL,
+        PUMPFUN_VIRTUAL_TOKENS,
+    )
+    .and_then(|pool| pool.with_fee(PUMPFUN_FEE_BPS))
+    .and_then(|pool| pool.with_protocol_fee_share(MAX_FEE_BPS))
+    .and_then(|pool| pool.with_decimals(9, 6))
+    .and_then(|pool| pool.with_graduation(GraduationTarget::TokensSoldBps(10_000)))
+    .expect("pump.fun constants make a valid pool")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PoolError;
+
+    #[test]
+    fn test_pumpfun() {
+        let mut pool = pumpfun();
+        assert_eq!(pool.effective_token_reserve(), 1_073_000_000 * 10u64.pow(6));
+        // About 0.000000028 SOL per token at launch.
+        assert!((pool.market_price_ui() - 30.0 / 1_073_000_000.0).abs() < 1e-15);
+
+        let quote = pool.quote_buy(PUMPFUN_REAL_TOKENS).unwrap();
+        let paid = pool.buy(PUMPFUN_REAL_TOKENS, None).unwrap();
+        assert_eq!(paid, quote.amount_in);
+        // 30 * 1_073 / 279.9 - 30 SOL reach the curve, plus the fee.
+        assert_eq!(pool.real_native_reserve(), 85_005_359_056);
+        assert_eq!(paid - pool.real_native_reserve(), 850_053_591);
+        assert!(pool.is_graduated());
+        assert_eq!(pool.sell(1, None), Err(PoolError::CurveCompleted));
+    }
+}
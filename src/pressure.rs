@@ -0,0 +1,95 @@
+use alloc::vec::Vec;
+
+use crate::{LiquidityPool, TradeOp, TradeRecord};
+
+/// Buy versus sell volume, in native currency, over a window of recorded trades.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pressure {
+    pub buy_volume: u128,
+    pub sell_volume: u128,
+}
+
+impl Pressure {
+    fn of<'a>(records: impl IntoIterator<Item = &'a TradeRecord>) -> Self {
+        records
+            .into_iter()
+            .fold(Self::default(), |mut pressure, record| {
+                match record.op {
+                    TradeOp::Buy { .. } => pressure.buy_volume += record.native_amount as u128,
+                    TradeOp::Sell { .. } => pressure.sell_volume += record.native_amount as u128,
+                }
+                pressure
+            })
+    }
+
+    /// Buy volume divided by sell volume; infinite if there were only buys, NaN if no volume.
+    pub fn ratio(&self) -> f64 {
+        self.buy_volume as f64 / self.sell_volume as f64
+    }
+
+    /// Normalized pressure in `[-1, 1]`: `1` for only buys, `-1` for only sells, `0` when
+    /// balanced or when there was no volume.
+    pub fn score(&self) -> f64 {
+        let total = self.buy_volume + self.sell_volume;
+        if total == 0 {
+            return 0.0;
+        }
+        (self.buy_volume as f64 - self.sell_volume as f64) / total as f64
+    }
+}
+
+impl LiquidityPool {
+    /// Buy/sell pressure over the last `window` recorded trades.
+    pub fn pressure(&self, window: usize) -> Pressure {
+        Pressure::of(self.history().iter().rev().take(window))
+    }
+
+    /// Pressure over a window of `window` trades ending at each recorded trade, oldest first.
+    /// The first entries cover fewer trades until the window fills up.
+    pub fn rolling_pressure(&self, window: usize) -> Vec<Pressure> {
+        let records = self.history();
+        (1..=records.len())
+            .map(|end| Pressure::of(&records[end.saturating_sub(window)..end]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let token_amount = 10u64.pow(12);
+        let spent = pool.buy(3 * token_amount, None).unwrap() as u128;
+        let received = pool.sell(token_amount, None).unwrap() as u128;
+
+        let pressure = pool.pressure(10);
+        assert_eq!(pressure.buy_volume, spent);
+        assert_eq!(pressure.sell_volume, received);
+        assert!(pressure.ratio() > 2.9);
+        assert!(pressure.score() > 0.0 && pressure.score() < 1.0);
+        assert_eq!(pool.pressure(1).score(), -1.0);
+        assert_eq!(pool.pressure(0).score(), 0.0);
+    }
+
+    #[test]
+    fn test_rolling_pressure() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let token_amount = 10u64.pow(12);
+        pool.buy(token_amount, None).unwrap();
+        pool.buy(token_amount, None).unwrap();
+        pool.sell(token_amount, None).unwrap();
+        let scores: Vec<f64> = pool
+            .rolling_pressure(1)
+            .iter()
+            .map(Pressure::score)
+            .collect();
+        assert_eq!(scores, [1.0, 1.0, -1.0]);
+        assert_eq!(pool.rolling_pressure(3)[2], pool.pressure(3));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{Direction, LiquidityPool, PoolError, Result, TradeOp};
+
+/// A non-negative price in Q64.64 fixed point: the high 64 bits hold the integer part and the
+/// low 64 bits the fraction, so prices replicate bit for bit wherever integer math does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct PriceQ64(pub u128);
+
+impl PriceQ64 {
+    pub const ONE: Self = Self(1 << 64);
+
+    /// Returns `num / den` rounded down, or `None` if `den` is zero or the quotient does not
+    /// fit in 64 integer bits.
+    pub fn from_ratio(num: u128, den: u128) -> Option<Self> {
+        let integer = num.checked_div(den)?;
+        if integer > u64::MAX as u128 {
+            return None;
+        }
+        // Long division of the remainder, one fractional bit at a time.
+        let mut remainder = num % den;
+        let mut fraction = 0u128;
+        for _ in 0..64 {
+            let carry = remainder >> 127;
+            remainder <<= 1;
+            fraction <<= 1;
+            if carry == 1 || remainder >= den {
+                remainder = remainder.wrapping_sub(den);
+                fraction |= 1;
+            }
+        }
+        Some(Self(integer << 64 | fraction))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / (1u128 << 64) as f64
+    }
+}
+
+impl LiquidityPool {
+    /// Returns [`market_price`](Self::market_price) as an exact `(numerator, denominator)`
+    /// pair.
+    pub fn market_price_ratio(&self) -> (u128, u128) {
+        (
+            self.native_reserve as u128,
+            self.initial_token_reserve as u128,
+        )
+    }
+
+    /// Returns [`market_price`](Self::market_price) in Q64.64 fixed point, rounded down.
+    pub fn market_price_q64(&self) -> PriceQ64 {
+        // The native reserve fits in 64 bits, so shifting it cannot overflow.
+        PriceQ64(((self.native_reserve as u128) << 64) / self.initial_token_reserve as u128)
+    }
+
+    /// Returns [`spot_price`](Self::spot_price) as an exact `(numerator, denominator)` pair.
+    pub fn spot_price_ratio(&self) -> (u128, u128) {
+        (self.native_reserve as u128, self.token_reserve as u128)
+    }
+
+    /// Returns [`spot_price`](Self::spot_price) in Q64.64 fixed point, rounded down.
+    pub fn spot_price_q64(&self) -> PriceQ64 {
+        PriceQ64(((self.native_reserve as u128) << 64) / self.token_reserve as u128)
+    }
+
+    /// Returns the average fill price of trading `token_amount` tokens in `direction`, as an
+    /// exact `(native, token_amount)` pair: native paid, fee included (buy) or received net of
+    /// the fee (sell). Fails like the trade itself.
+    pub fn execution_price(&self, direction: Direction, token_amount: u64) -> Result<(u128, u128)> {
+        let projection = self.simulate(&trade_op(direction, token_amount))?;
+        Ok((projection.native_amount as u128, token_amount as u128))
+    }
+
+    /// Returns the [`spot_price`](Self::spot_price) the pool would be left at by trading
+    /// `token_amount` tokens in `direction`, as an exact `(numerator, denominator)` pair. Fails
+    /// like the trade itself.
+    pub fn marginal_price_after(
+        &self,
+        direction: Direction,
+        token_amount: u64,
+    ) -> Result<(u128, u128)> {
+        let projection = self.simulate(&trade_op(direction, token_amount))?;
+        Ok((
+            projection.native_reserve as u128,
+            projection.token_reserve as u128,
+        ))
+    }
+
+    /// Integer counterpart of [`calculate_price_impact`](Self::calculate_price_impact), in
+    /// basis points rounded toward zero. Fails instead of panicking when the pool cannot fill
+    /// the buy.
+    pub fn calculate_price_impact_bps(&self, token_amount: u64) -> Result<i64> {
+        if token_amount == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let new_token_reserve = self
+            .token_reserve
+            .checked_sub(token_amount)
+            .filter(|&reserve| reserve > 0)
+            .ok_or(PoolError::InsufficientPoolFunds {
+                requested: token_amount,
+                available: self.token_reserve,
+            })?;
+        let new_native_reserve = self.constant_product / new_token_reserve as u128;
+        // new_native / new_token relative to native / initial_token.
+        let ratio = PriceQ64::from_ratio(
+            new_native_reserve * self.initial_token_reserve as u128,
+            self.native_reserve as u128 * new_token_reserve as u128,
+        )
+        .ok_or(PoolError::Overflow)?;
+        let (change, sign) = match ratio.0.checked_sub(PriceQ64::ONE.0) {
+            Some(change) => (change, 1),
+            None => (PriceQ64::ONE.0 - ratio.0, -1),
+        };
+        let bps = (change >> 64) * 10_000 + (((change & u64::MAX as u128) * 10_000) >> 64);
+        i64::try_from(bps)
+            .map(|bps| sign * bps)
+            .map_err(|_| PoolError::Overflow)
+    }
+}
+
+fn trade_op(direction: Direction, token_amount: u64) -> TradeOp {
+    match direction {
+        Direction::BuyToken => TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        },
+        Direction::SellToken => TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ratio() {
+        assert_eq!(PriceQ64::from_ratio(3, 2), Some(PriceQ64(3 << 63)));
+        assert_eq!(
+            PriceQ64::from_ratio(1, 3),
+            Some(PriceQ64(u64::MAX as u128 / 3))
+        );
+        assert_eq!(
+            PriceQ64::from_ratio(u128::MAX, u128::MAX),
+            Some(PriceQ64::ONE)
+        );
+        assert_eq!(PriceQ64::from_ratio(1 << 64, 1), None);
+        assert_eq!(PriceQ64::from_ratio(1, 0), None);
+    }
+
+    #[test]
+    fn test_fixed_point_prices_match_floats() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(100_000_000 * 10u64.pow(6), None).unwrap();
+        assert!((pool.market_price_q64().to_f64() - pool.market_price()).abs() < 1e-15);
+        assert!((pool.spot_price_q64().to_f64() - pool.spot_price()).abs() < 1e-15);
+
+        let token_amount = 50_000_000 * 10u64.pow(6);
+        let impact = pool.calculate_price_impact(token_amount);
+        let impact_bps = pool.calculate_price_impact_bps(token_amount).unwrap();
+        assert_eq!(impact_bps, (impact * 10_000.0) as i64);
+        assert_eq!(
+            pool.calculate_price_impact_bps(pool.get_token_reserve()),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: pool.get_token_reserve(),
+                available: pool.get_token_reserve()
+            })
+        );
+    }
+
+    #[test]
+    fn test_execution_and_marginal_prices() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let token_amount = 10u64.pow(12);
+        let quote = pool.quote_buy(token_amount).unwrap();
+        assert_eq!(
+            pool.execution_price(Direction::BuyToken, token_amount),
+            Ok((quote.amount_in as u128, token_amount as u128))
+        );
+        let (num, den) = pool
+            .marginal_price_after(Direction::BuyToken, token_amount)
+            .unwrap();
+        assert_eq!(num as f64 / den as f64, quote.spot_price_after);
+
+        let (num, den) = pool
+            .execution_price(Direction::SellToken, token_amount)
+            .unwrap();
+        let (after_num, after_den) = pool
+            .marginal_price_after(Direction::SellToken, token_amount)
+            .unwrap();
+        // Sellers fill below the spot price, which falls further.
+        let (spot_num, spot_den) = pool.spot_price_ratio();
+        assert!(num * spot_den < spot_num * den);
+        assert!(after_num * spot_den < spot_num * after_den);
+        assert!(pool.execution_price(Direction::BuyToken, 0).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+//! Kani proof harnesses for the core pool math, run with `cargo kani`.
+
+use crate::{LiquidityPool, TradeOp};
+
+fn any_pool() -> LiquidityPool {
+    let native_reserve: u64 = kani::any();
+    let token_reserve: u64 = kani::any();
+    kani::assume(native_reserve > 0 && token_reserve > 0);
+    LiquidityPool::new(native_reserve, token_reserve).unwrap()
+}
+
+fn any_op() -> TradeOp {
+    let token_amount: u64 = kani::any();
+    if kani::any() {
+        TradeOp::Buy {
+            token_amount,
+            max_native: kani::any(),
+        }
+    } else {
+        TradeOp::Sell {
+            token_amount,
+            min_native: kani::any(),
+        }
+    }
+}
+
+/// Quoting and executing any trade on any pool returns instead of panicking.
+#[kani::proof]
+fn trades_never_panic() {
+    let mut pool = any_pool();
+    let op = any_op();
+    let _ = pool.quote(&op);
+    let _ = pool.execute(&op);
+}
+
+/// The constant product is never lowered by a trade, whether it succeeds or not.
+#[kani::proof]
+fn constant_product_never_decreases() {
+    let mut pool = any_pool();
+    let k = pool.get_constant_product();
+    let _ = pool.execute(&any_op());
+    assert!(pool.get_constant_product() >= k);
+}
+
+/// A successful trade never pays out more than the pool holds.
+#[kani::proof]
+fn outputs_never_exceed_reserves() {
+    let mut pool = any_pool();
+    let native_reserve = pool.get_native_reserve();
+    let token_reserve = pool.get_token_reserve();
+    let op = any_op();
+    if let Ok(native_amount) = pool.execute(&op) {
+        match op {
+            TradeOp::Buy { token_amount, .. } => assert!(token_amount < token_reserve),
+            TradeOp::Sell { .. } => assert!(native_amount <= native_reserve),
+        }
+    }
+}
+
+/// A failed trade leaves the pool untouched.
+#[kani::proof]
+fn failed_trades_do_not_mutate() {
+    let mut pool = any_pool();
+    let before = (pool.get_native_reserve(), pool.get_token_reserve());
+    if pool.execute(&any_op()).is_err() {
+        assert_eq!(
+            before,
+            (pool.get_native_reserve(), pool.get_token_reserve())
+        );
+    }
+}
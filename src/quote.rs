@@ -0,0 +1,444 @@
+use alloc::vec::Vec;
+
+use crate::{
+    Curve, CurveState, Direction, LiquidityPool, PoolError, Projection, Result, TradeOp,
+    MAX_FEE_BPS,
+};
+
+/// Whether a native amount includes the swap fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeeMode {
+    /// What the trader pays (buy) or receives (sell), fee settled.
+    Included,
+    /// What moves through the reserves, before the fee is added or withheld.
+    Excluded,
+}
+
+/// Full breakdown of a prospective trade, as returned by [`LiquidityPool::quote_buy`] and
+/// [`LiquidityPool::quote_sell`].
+///
+/// Prices are in native currency per token unit, taken from the current reserves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quote {
+    pub direction: Direction,
+    /// Native paid, fee included (buy) or tokens sold, transfer tax included (sell).
+    pub amount_in: u64,
+    /// Tokens received after the transfer tax (buy) or native received net of the fee (sell).
+    pub amount_out: u64,
+    /// Swap fee, in native currency.
+    pub fee: u64,
+    /// Tokens taken by the token's transfer tax, see [`TokenTax`](crate::TokenTax).
+    pub token_tax: u64,
+    /// Native currency moving through the reserves, fee excluded.
+    pub curve_native_amount: u64,
+    /// Native amount of the trade per token, fee included.
+    pub execution_price: f64,
+    pub spot_price_before: f64,
+    pub spot_price_after: f64,
+    /// Relative move of the spot price caused by the trade: positive for buys, negative for
+    /// sells.
+    pub price_impact: f64,
+}
+
+/// Signed move of the spot price caused by a prospective trade, as returned by
+/// [`LiquidityPool::price_impact`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceImpact {
+    /// Relative move of the spot price: positive for buys, negative for sells.
+    pub impact: f64,
+    pub spot_price_before: f64,
+    pub spot_price_after: f64,
+}
+
+/// Liquidity between the spot price and a price offset, one level of
+/// [`LiquidityPool::depth`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthLevel {
+    /// Offset from the spot price, in basis points: positive above it, negative below.
+    pub offset_bps: i32,
+    /// Buys for offsets above the spot price, sells below.
+    pub direction: Direction,
+    /// Largest number of tokens tradable without moving the spot price past the offset.
+    pub token_amount: u64,
+    /// Native paid, fee included (buy) or received net of the fee (sell), for `token_amount`.
+    pub native_amount: u64,
+}
+
+impl Quote {
+    fn of<C: Curve>(pool: &LiquidityPool<C>, projection: &Projection) -> Self {
+        let token_amount = projection.op.token_amount();
+        let (direction, amount_in, amount_out, curve_native_amount) = match projection.op {
+            TradeOp::Buy { .. } => (
+                Direction::BuyToken,
+                projection.native_amount,
+                token_amount - projection.token_tax,
+                projection.native_amount - projection.fee,
+            ),
+            TradeOp::Sell { .. } => (
+                Direction::SellToken,
+                token_amount,
+                projection.native_amount,
+                projection.native_amount + projection.fee,
+            ),
+        };
+        let spot_price_before = pool.spot_price();
+        let spot_price_after = pool.curve().spot_price(&CurveState {
+            native_reserve: projection.native_reserve,
+            token_reserve: projection.token_reserve,
+            ..pool.curve_state()
+        });
+        Self {
+            direction,
+            amount_in,
+            amount_out,
+            fee: projection.fee,
+            token_tax: projection.token_tax,
+            curve_native_amount,
+            execution_price: projection.native_amount as f64 / token_amount as f64,
+            spot_price_before,
+            spot_price_after,
+            price_impact: spot_price_after / spot_price_before - 1.0,
+        }
+    }
+
+    /// Returns the native side of the trade, with or without the fee.
+    pub fn native_amount(&self, mode: FeeMode) -> u64 {
+        match (mode, self.direction) {
+            (FeeMode::Excluded, _) => self.curve_native_amount,
+            (FeeMode::Included, Direction::BuyToken) => self.amount_in,
+            (FeeMode::Included, Direction::SellToken) => self.amount_out,
+        }
+    }
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Quotes buying `token_amount` tokens.
+    pub fn quote_buy(&self, token_amount: u64) -> Result<Quote> {
+        let projection = self.simulate(&TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        })?;
+        Ok(Quote::of(self, &projection))
+    }
+
+    /// Quotes selling `token_amount` tokens.
+    pub fn quote_sell(&self, token_amount: u64) -> Result<Quote> {
+        let projection = self.simulate(&TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        })?;
+        Ok(Quote::of(self, &projection))
+    }
+
+    /// Returns the native currency, fee included, that buying each of `amounts` tokens would
+    /// spend, in order, for ladders of trade sizes. The pool state is read once for the whole
+    /// ladder.
+    pub fn quote_buy_many(&self, amounts: &[u64]) -> Vec<Result<u64>> {
+        self.quote_many(amounts, |token_amount| TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        })
+    }
+
+    /// Returns the native currency, net of the fee, that selling each of `amounts` tokens
+    /// would receive, in order, like [`quote_buy_many`](Self::quote_buy_many).
+    pub fn quote_sell_many(&self, amounts: &[u64]) -> Vec<Result<u64>> {
+        self.quote_many(amounts, |token_amount| TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        })
+    }
+
+    fn quote_many(&self, amounts: &[u64], op: impl Fn(u64) -> TradeOp) -> Vec<Result<u64>> {
+        if self.is_graduated() {
+            return amounts
+                .iter()
+                .map(|_| Err(PoolError::CurveCompleted))
+                .collect();
+        }
+        let state = self.curve_state();
+        amounts
+            .iter()
+            .map(|&token_amount| {
+                self.project(&state, &op(token_amount))
+                    .map(|projection| projection.native_amount)
+            })
+            .collect()
+    }
+
+    /// Returns the signed price impact of trading `token_amount` tokens in `direction`, with
+    /// the spot price it would leave. Fails like the trade itself rather than panicking when
+    /// the pool cannot fill it.
+    pub fn price_impact(&self, direction: Direction, token_amount: u64) -> Result<PriceImpact> {
+        let quote = match direction {
+            Direction::BuyToken => self.quote_buy(token_amount)?,
+            Direction::SellToken => self.quote_sell(token_amount)?,
+        };
+        Ok(PriceImpact {
+            impact: quote.price_impact,
+            spot_price_before: quote.spot_price_before,
+            spot_price_after: quote.spot_price_after,
+        })
+    }
+
+    /// Returns the [`price_impact`](Self::price_impact) of trading `token_amount` tokens in
+    /// `direction`, failing with [`PoolError::PriceImpactTooHigh`] if it exceeds
+    /// `max_impact_bps` basis points.
+    pub fn checked_price_impact(
+        &self,
+        direction: Direction,
+        token_amount: u64,
+        max_impact_bps: u32,
+    ) -> Result<PriceImpact> {
+        let impact = self.price_impact(direction, token_amount)?;
+        if impact.impact.abs() > max_impact_bps as f64 / MAX_FEE_BPS as f64 {
+            return Err(PoolError::PriceImpactTooHigh);
+        }
+        Ok(impact)
+    }
+
+    /// Returns the largest number of tokens tradable in `direction` whose
+    /// [`price_impact`](Self::price_impact) stays within `max_impact_bps` basis points, or 0 if
+    /// no trade does. The impact grows with the size of the trade, so the amount is found by
+    /// bisection over exact quotes, on any curve.
+    pub fn max_amount_for_impact(&self, direction: Direction, max_impact_bps: u32) -> u64 {
+        let max_impact = max_impact_bps as f64 / MAX_FEE_BPS as f64;
+        let within = |token_amount| {
+            self.price_impact(direction, token_amount)
+                .is_ok_and(|impact| impact.impact.abs() <= max_impact)
+        };
+        let (mut low, mut high) = match direction {
+            Direction::BuyToken => (0, self.real_token_reserve()),
+            Direction::SellToken => (0, u64::MAX - self.token_reserve),
+        };
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            if within(mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    }
+
+    /// Returns the liquidity within each of `offsets_bps` basis points of the spot price, as
+    /// an order book depth chart derived from the curve: buys up to positive offsets and sells
+    /// down to negative ones. Levels are cumulative and returned in the order of the offsets.
+    pub fn depth(&self, offsets_bps: &[i32]) -> Vec<DepthLevel> {
+        offsets_bps
+            .iter()
+            .map(|&offset_bps| {
+                let direction = if offset_bps < 0 {
+                    Direction::SellToken
+                } else {
+                    Direction::BuyToken
+                };
+                let token_amount = self.max_amount_for_impact(direction, offset_bps.unsigned_abs());
+                let native_amount = match (token_amount, direction) {
+                    (0, _) => Ok(0),
+                    (amount, Direction::BuyToken) => {
+                        self.quote_buy(amount).map(|quote| quote.amount_in)
+                    }
+                    (amount, Direction::SellToken) => {
+                        self.quote_sell(amount).map(|quote| quote.amount_out)
+                    }
+                };
+                DepthLevel {
+                    offset_bps,
+                    direction,
+                    token_amount,
+                    // Only amounts that can be quoted are within the offset.
+                    native_amount: native_amount.unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the smallest trade, as a direction and a token amount, that moves the spot price
+    /// to `target_price` or just past it, for arbitrage against an external reference price.
+    /// Returns a zero-sized buy if the spot price is already at the target. Fails with
+    /// [`PoolError::InvalidAmount`] for a target that is not a positive finite price, and like
+    /// the trade itself if the pool cannot fill it.
+    pub fn amount_to_reach_price(&self, target_price: f64) -> Result<(Direction, u64)> {
+        if !target_price.is_finite() || target_price <= 0.0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let spot_price = self.spot_price();
+        let (direction, max_amount) = if target_price > spot_price {
+            (Direction::BuyToken, self.real_token_reserve())
+        } else if target_price < spot_price {
+            (Direction::SellToken, u64::MAX - self.token_reserve)
+        } else {
+            return Ok((Direction::BuyToken, 0));
+        };
+        // Trades the pool cannot fill are at least as large as those reaching the target, so
+        // bisection finds the first amount that either reaches it or fails.
+        let reached_or_failed = |token_amount| match self.price_impact(direction, token_amount) {
+            Ok(impact) => match direction {
+                Direction::BuyToken => impact.spot_price_after >= target_price,
+                Direction::SellToken => impact.spot_price_after <= target_price,
+            },
+            Err(_) => true,
+        };
+        let (mut low, mut high) = (1, max_amount);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if reached_or_failed(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        let impact = self.price_impact(direction, low)?;
+        let reached = match direction {
+            Direction::BuyToken => impact.spot_price_after >= target_price,
+            Direction::SellToken => impact.spot_price_after <= target_price,
+        };
+        if !reached {
+            // Even the largest trade the pool can take falls short.
+            return Err(PoolError::InsufficientPoolFunds {
+                requested: max_amount.saturating_add(1),
+                available: max_amount,
+            });
+        }
+        Ok((direction, low))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_breakdown() {
+        let pool = LiquidityPool::new(1_000, 1_000)
+            .unwrap()
+            .with_fee(100)
+            .unwrap();
+
+        let buy = pool.quote_buy(500).unwrap();
+        assert_eq!(buy.direction, Direction::BuyToken);
+        assert_eq!((buy.amount_in, buy.amount_out, buy.fee), (1_010, 500, 10));
+        assert_eq!(buy.native_amount(FeeMode::Excluded), 1_000);
+        assert_eq!(buy.native_amount(FeeMode::Included), 1_010);
+        assert_eq!(buy.execution_price, 2.02);
+        // The fee stays in the native reserve.
+        assert_eq!((buy.spot_price_before, buy.spot_price_after), (1.0, 4.02));
+        assert!((buy.price_impact - 3.02).abs() < 1e-12);
+
+        let sell = pool.quote_sell(1_000).unwrap();
+        assert_eq!((sell.amount_in, sell.amount_out, sell.fee), (1_000, 495, 5));
+        assert_eq!(sell.native_amount(FeeMode::Excluded), 500);
+        assert_eq!(sell.native_amount(FeeMode::Included), 495);
+        assert!((sell.price_impact + 0.7475).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_quote_many() {
+        let pool = LiquidityPool::new(1_000, 1_000)
+            .unwrap()
+            .with_fee(100)
+            .unwrap();
+        assert_eq!(
+            pool.quote_buy_many(&[500, 0, 1_000]),
+            vec![
+                Ok(1_010),
+                Err(PoolError::InvalidAmount),
+                Err(PoolError::InsufficientPoolFunds {
+                    requested: 1_000,
+                    available: 1_000
+                })
+            ]
+        );
+        let amounts = [1, 10, 100, 1_000];
+        let sells = pool.quote_sell_many(&amounts);
+        for (amount, quote) in amounts.iter().zip(sells) {
+            assert_eq!(quote, pool.simulate_sell(*amount, None));
+        }
+        assert!(pool.quote_sell_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_max_amount_for_impact() {
+        let pool = LiquidityPool::default();
+        for direction in [Direction::BuyToken, Direction::SellToken] {
+            let amount = pool.max_amount_for_impact(direction, 100);
+            let impact = |amount| pool.price_impact(direction, amount).unwrap().impact.abs();
+            assert!(amount > 0 && impact(amount) <= 0.01 && impact(amount + 1) > 0.01);
+        }
+        assert_eq!(pool.max_amount_for_impact(Direction::BuyToken, 0), 0);
+        let amount = pool.max_amount_for_impact(Direction::BuyToken, 100);
+        assert!(pool
+            .checked_price_impact(Direction::BuyToken, amount, 100)
+            .is_ok());
+        assert_eq!(
+            pool.checked_price_impact(Direction::BuyToken, amount + 1, 100),
+            Err(PoolError::PriceImpactTooHigh)
+        );
+    }
+
+    #[test]
+    fn test_depth() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let levels = pool.depth(&[-200, -50, 0, 50, 200]);
+        assert_eq!(levels.len(), 5);
+        assert_eq!((levels[2].token_amount, levels[2].native_amount), (0, 0));
+        for level in &levels {
+            let direction = if level.offset_bps < 0 {
+                Direction::SellToken
+            } else {
+                Direction::BuyToken
+            };
+            assert_eq!(level.direction, direction);
+            assert_eq!(
+                level.token_amount,
+                pool.max_amount_for_impact(direction, level.offset_bps.unsigned_abs())
+            );
+        }
+        assert!(levels[0].token_amount > levels[1].token_amount);
+        assert!(levels[4].token_amount > levels[3].token_amount);
+        let buy = pool.quote_buy(levels[4].token_amount).unwrap();
+        assert_eq!(levels[4].native_amount, buy.amount_in);
+        assert!(buy.price_impact <= 0.02);
+    }
+
+    #[test]
+    fn test_amount_to_reach_price() {
+        let pool = LiquidityPool::default();
+        let spot_price = pool.spot_price();
+        for target in [spot_price * 1.5, spot_price / 2.0] {
+            let (direction, amount) = pool.amount_to_reach_price(target).unwrap();
+            let after = |amount| {
+                pool.price_impact(direction, amount)
+                    .unwrap()
+                    .spot_price_after
+            };
+            let distance = |price: f64| (price - target) * (price - spot_price);
+            // The amount reaches the target and one token fewer does not.
+            assert!(distance(after(amount)) >= 0.0 && distance(after(amount - 1)) < 0.0);
+        }
+        assert_eq!(
+            pool.amount_to_reach_price(spot_price),
+            Ok((Direction::BuyToken, 0))
+        );
+        assert_eq!(
+            pool.amount_to_reach_price(f64::NAN),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn test_signed_price_impact() {
+        let pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        let sell = pool.price_impact(Direction::SellToken, 1_000).unwrap();
+        assert_eq!((sell.impact, sell.spot_price_after), (-0.75, 0.25));
+        assert!(pool.price_impact(Direction::BuyToken, 500).unwrap().impact > 0.0);
+        // Draining the pool fails where `calculate_price_impact` would panic.
+        assert!(pool.price_impact(Direction::BuyToken, 1_000).is_err());
+    }
+}
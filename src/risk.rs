@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+
+use crate::{LiquidityKind, LiquidityPool};
+
+/// Spot price drawdowns, in percent, at which [`RiskReport::support`] is measured.
+pub const SUPPORT_DRAWDOWNS: [f64; 4] = [10.0, 25.0, 50.0, 90.0];
+
+/// Selling needed to push the spot price a given percentage below its current level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SupportLevel {
+    pub drawdown_percent: f64,
+    /// Tokens that must be sold into the pool to reach the drawdown.
+    pub tokens_to_sell: f64,
+    /// Native currency those sales would withdraw from the pool.
+    pub native_withdrawn: f64,
+    /// `tokens_to_sell` as a percentage of the current token reserve.
+    pub percent_of_token_reserve: f64,
+}
+
+/// Rug-pull risk signals derived from the pool reserves and recorded trades.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RiskReport {
+    pub native_reserve: u64,
+    pub support: Vec<SupportLevel>,
+    /// Trades covered by the drain measurements, at most the requested window.
+    pub window: usize,
+    /// Net native outflow over the window as a percentage of the native reserve at its start;
+    /// zero when more native flowed in than out.
+    pub native_drained_percent: f64,
+    /// Average net native outflow per trade over the window, negative for net inflow.
+    pub drain_per_trade: f64,
+    /// Share of the liquidity that providers can withdraw, i.e. not locked at creation. Also
+    /// an upper bound on what the largest provider can pull.
+    pub removable_liquidity_percent: f64,
+    /// LP shares burned during the window as a percentage of the supply at its start.
+    pub shares_removed_percent: f64,
+}
+
+impl LiquidityPool {
+    /// Computes price support at [`SUPPORT_DRAWDOWNS`], how much liquidity providers can pull,
+    /// and how fast native liquidity left the pool over the last `window` recorded trades.
+    pub fn risk_report(&self, window: usize) -> RiskReport {
+        let native_reserve = self.native_reserve as f64;
+        let token_reserve = self.token_reserve as f64;
+        // Along x * y = k, scaling the price by `r` scales the token reserve by `1 / sqrt(r)`
+        // and the native reserve by `sqrt(r)`.
+        let support = SUPPORT_DRAWDOWNS
+            .iter()
+            .map(|&drawdown_percent| {
+                let root = (1.0 - drawdown_percent / 100.0).sqrt();
+                let tokens_to_sell = token_reserve * (1.0 / root - 1.0);
+                SupportLevel {
+                    drawdown_percent,
+                    tokens_to_sell,
+                    native_withdrawn: native_reserve * (1.0 - root),
+                    percent_of_token_reserve: tokens_to_sell / token_reserve * 100.0,
+                }
+            })
+            .collect();
+
+        let window = window.min(self.history().len());
+        let net_flow = self.net_flow(window) as f64;
+        let reserve_at_start = native_reserve - net_flow;
+
+        // Liquidity changes applied after the first trade of the window.
+        let window_start = window
+            .checked_sub(1)
+            .and_then(|offset| self.history().iter().rev().nth(offset))
+            .map_or(self.trade_count + 1, |record| record.seq);
+        let (mut burned, mut minted) = (0u128, 0u128);
+        for change in self.liquidity_changes() {
+            if change.seq >= window_start {
+                match change.kind {
+                    LiquidityKind::Add => minted += change.shares as u128,
+                    LiquidityKind::Remove => burned += change.shares as u128,
+                }
+            }
+        }
+        let supply_at_start = self.lp_supply as u128 + burned - minted;
+        let removable = self.lp_supply - self.locked_liquidity;
+        RiskReport {
+            native_reserve: self.native_reserve,
+            support,
+            window,
+            native_drained_percent: (-net_flow).max(0.0) / reserve_at_start * 100.0,
+            drain_per_trade: if window == 0 {
+                0.0
+            } else {
+                -net_flow / window as f64
+            },
+            removable_liquidity_percent: removable as f64 / self.lp_supply as f64 * 100.0,
+            shares_removed_percent: burned as f64 / supply_at_start as f64 * 100.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_support_levels() {
+        let pool = LiquidityPool::default();
+        let report = pool.risk_report(10);
+        let half = report
+            .support
+            .iter()
+            .find(|level| level.drawdown_percent == 50.0)
+            .unwrap();
+        let mut sold = pool.clone();
+        let native_out = sold.sell(half.tokens_to_sell as u64, None).unwrap();
+        let spot = |pool: &LiquidityPool| {
+            pool.get_native_reserve() as f64 / pool.get_token_reserve() as f64
+        };
+        let price_ratio = spot(&sold) / spot(&pool);
+        assert!((price_ratio - 0.5).abs() < 1e-6);
+        assert!((native_out as f64 - half.native_withdrawn).abs() < 2.0);
+        assert_eq!(report.window, 0);
+        assert_eq!(report.native_drained_percent, 0.0);
+    }
+
+    #[test]
+    fn test_native_drain() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let token_amount = 100_000_000 * 10u64.pow(6);
+        pool.buy(token_amount, None).unwrap();
+        let native_before = pool.get_native_reserve();
+        let received = pool.sell(2 * token_amount, None).unwrap();
+        let report = pool.risk_report(1);
+        assert_eq!(report.window, 1);
+        let expected = received as f64 / native_before as f64 * 100.0;
+        assert!((report.native_drained_percent - expected).abs() < 1e-9);
+        assert_eq!(report.drain_per_trade, received as f64);
+        assert!(pool.risk_report(2).native_drained_percent < expected);
+    }
+
+    #[test]
+    fn test_liquidity_signals() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        pool.buy(10u64.pow(12), None).unwrap();
+        pool.remove_liquidity(pool.lp_supply() / 2).unwrap();
+        pool.buy(10u64.pow(12), None).unwrap();
+        let report = pool.risk_report(1);
+        assert_eq!(report.shares_removed_percent, 0.0);
+        let report = pool.risk_report(2);
+        assert!((report.shares_removed_percent - 50.0).abs() < 1e-9);
+        assert!(report.removable_liquidity_percent > 99.99);
+    }
+}
@@ -0,0 +1,108 @@
+/// A small, fast pseudo-random generator (SplitMix64).
+///
+/// Implemented in-crate rather than pulled from `rand` so that a given seed produces the same
+/// stream on every platform and across dependency upgrades.
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a float uniformly distributed in `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns an integer in `min..=max`.
+    pub(crate) fn range_u64(&mut self, min: u64, max: u64) -> u64 {
+        let span = (max - min) as u128 + 1;
+        min + ((self.next_u64() as u128 * span) >> 64) as u64
+    }
+
+    /// Returns an integer in `min..=max` (with `min` non-zero) whose logarithm is uniformly
+    /// distributed, computed in fixed point so the stream matches on every platform.
+    pub(crate) fn log_range_u64(&mut self, min: u64, max: u64) -> u64 {
+        let low = log2_fixed(min);
+        // Sizes round down, so every size up to `max` owns the logarithms below the next one.
+        let high = log2_fixed(max.saturating_add(1)).saturating_sub(1).max(low);
+        exp2_fixed(self.range_u64(low, high)).clamp(min, max)
+    }
+}
+
+/// `2^(2^-k)` for `k` in `1..=32`, in fixed point with 63 fractional bits.
+const EXP2_FRACTIONS: [u64; 32] = [
+    0xb504_f333_f9de_6484,
+    0x9837_f051_8db8_a96f,
+    0x8b95_c1e3_ea8b_d6e7,
+    0x85aa_c367_cc48_7b15,
+    0x82cd_8698_ac2b_a1d7,
+    0x8164_d1f3_bc03_0773,
+    0x80b1_ed4f_d999_ab6c,
+    0x8058_d7d2_d5e5_f6b1,
+    0x802c_6436_d0e0_4f51,
+    0x8016_302f_1746_7628,
+    0x800b_179c_8202_8fd1,
+    0x8005_8baf_7fee_3b5d,
+    0x8002_c5d0_0fdc_fcb7,
+    0x8001_62e6_1bed_4a49,
+    0x8000_b172_92f7_02a4,
+    0x8000_58b9_2abb_ae02,
+    0x8000_2c5c_8dad_e4d7,
+    0x8000_162e_44ea_f636,
+    0x8000_0b17_21fa_7c19,
+    0x8000_058b_90de_7e4d,
+    0x8000_02c5_c867_8f37,
+    0x8000_0162_e431_dba0,
+    0x8000_00b1_7218_72d1,
+    0x8000_0058_b90c_1aa9,
+    0x8000_002c_5c86_05a4,
+    0x8000_0016_2e43_00e6,
+    0x8000_000b_1721_7ff8,
+    0x8000_0005_8b90_bfdd,
+    0x8000_0002_c5c8_5fe7,
+    0x8000_0001_62e4_2ff2,
+    0x8000_0000_b172_17f8,
+    0x8000_0000_58b9_0bfc,
+];
+
+/// Base-2 logarithm of a non-zero `x` in fixed point with 32 fractional bits, rounded down.
+fn log2_fixed(x: u64) -> u64 {
+    let integer = 63 - x.leading_zeros() as u64;
+    // The mantissa in [1, 2), with 63 fractional bits. Squaring it doubles its logarithm,
+    // whose next bit is set when the square reaches 2.
+    let mut mantissa = (x as u128) << x.leading_zeros();
+    let mut fraction = 0;
+    for _ in 0..32 {
+        mantissa = (mantissa * mantissa) >> 63;
+        fraction <<= 1;
+        if mantissa >= 1 << 64 {
+            mantissa >>= 1;
+            fraction |= 1;
+        }
+    }
+    integer << 32 | fraction
+}
+
+/// `2^x` for `x` in fixed point with 32 fractional bits, rounded down and saturating at
+/// `u64::MAX`.
+fn exp2_fixed(x: u64) -> u64 {
+    let mut mantissa = 1u128 << 63;
+    for (bit, factor) in EXP2_FRACTIONS.iter().enumerate() {
+        if (x >> (31 - bit)) & 1 == 1 {
+            mantissa = (mantissa * *factor as u128) >> 63;
+        }
+    }
+    u64::try_from((mantissa << (x >> 32)) >> 63).unwrap_or(u64::MAX)
+}
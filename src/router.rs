@@ -0,0 +1,266 @@
+use alloc::vec::Vec;
+
+use crate::{AssetId, AssetPool, AssetSwap, PoolError, Result};
+
+/// Most pools a route may cross when the router is built with [`Router::default`].
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+/// Swaps through consecutive pools, each offered what the previous one paid out, as returned
+/// by [`Router::quote_route`] and [`Router::execute_route`]. A pool paid in its first asset may
+/// leave a remainder unspent, see [`AssetPool::swap`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Route<A> {
+    pub hops: Vec<AssetSwap<A>>,
+}
+
+impl<A> Route<A> {
+    /// Amount paid into the first pool.
+    pub fn amount_in(&self) -> u64 {
+        self.hops.first().map_or(0, |hop| hop.amount_in)
+    }
+
+    /// Amount paid out by the last pool.
+    pub fn amount_out(&self) -> u64 {
+        self.hops.last().map_or(0, |hop| hop.amount_out)
+    }
+}
+
+/// Pools keyed by asset pair, routing swaps between assets no single pool pairs.
+///
+/// Routes never visit an asset twice, so each pool is crossed at most once and quoting a route
+/// matches executing it.
+#[derive(Debug, Clone)]
+pub struct Router<A: AssetId> {
+    pools: Vec<AssetPool<A>>,
+    max_hops: usize,
+}
+
+impl<A: AssetId> Default for Router<A> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HOPS)
+    }
+}
+
+impl<A: AssetId> Router<A> {
+    /// Creates an empty router whose routes cross at most `max_hops` pools (at least one).
+    pub fn new(max_hops: usize) -> Self {
+        Self {
+            pools: Vec::new(),
+            max_hops: max_hops.max(1),
+        }
+    }
+
+    /// Adds `pool`, replacing and returning any pool of the same pair.
+    pub fn insert(&mut self, pool: AssetPool<A>) -> Option<AssetPool<A>> {
+        let (a, b) = pool.assets();
+        match self.pools.iter().position(|existing| pairs(existing, a, b)) {
+            Some(index) => Some(core::mem::replace(&mut self.pools[index], pool)),
+            None => {
+                self.pools.push(pool);
+                None
+            }
+        }
+    }
+
+    /// Returns the pool pairing `a` and `b`, in either order.
+    pub fn pool(&self, a: &A, b: &A) -> Option<&AssetPool<A>> {
+        self.pools.iter().find(|pool| pairs(pool, a, b))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// Quotes the route paying exactly `amount_in` of `asset_in` that returns the most of
+    /// `asset_out`. Fails with [`PoolError::PoolNotFound`] if no route within the hop limit
+    /// can fill the swap.
+    pub fn quote_route(&self, asset_in: &A, asset_out: &A, amount_in: u64) -> Result<Route<A>> {
+        self.best_route(asset_in, asset_out, amount_in)
+            .map(|(_, route)| route)
+    }
+
+    /// Executes the best route for `amount_in` of `asset_in`, receiving at least `min_out` of
+    /// `asset_out` if set. Either every swap of the route applies or none does.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), err(level = "debug"))
+    )]
+    pub fn execute_route(
+        &mut self,
+        asset_in: &A,
+        asset_out: &A,
+        amount_in: u64,
+        min_out: Option<u64>,
+    ) -> Result<Route<A>> {
+        let (path, route) = self.best_route(asset_in, asset_out, amount_in)?;
+        if let Some(min_out) = min_out.filter(|&min_out| route.amount_out() < min_out) {
+            return Err(PoolError::SlippageExceeded {
+                limit: min_out,
+                actual: route.amount_out(),
+            });
+        }
+        let saved: Vec<AssetPool<A>> = path
+            .iter()
+            .map(|&index| self.pools[index].clone())
+            .collect();
+        let mut hops: Vec<AssetSwap<A>> = Vec::with_capacity(path.len());
+        for (&index, quoted) in path.iter().zip(&route.hops) {
+            // Each pool is offered all of the previous output, as when quoting.
+            let offered = hops.last().map_or(amount_in, |hop| hop.amount_out);
+            match self.pools[index].swap(&quoted.asset_in, offered, None) {
+                Ok(hop) => hops.push(hop),
+                Err(err) => {
+                    for (&index, pool) in path.iter().zip(saved) {
+                        self.pools[index] = pool;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(Route { hops })
+    }
+
+    /// Returns the indices of the pools along the best route, with its quote.
+    fn best_route(
+        &self,
+        asset_in: &A,
+        asset_out: &A,
+        amount_in: u64,
+    ) -> Result<(Vec<usize>, Route<A>)> {
+        if asset_in == asset_out || amount_in == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let mut best = None;
+        let mut path = Vec::new();
+        let mut visited = alloc::vec![asset_in.clone()];
+        self.search(asset_out, amount_in, &mut visited, &mut path, &mut best);
+        best.ok_or(PoolError::PoolNotFound)
+    }
+
+    /// Extends `path`, whose last swap paid out `amount` of the last `visited` asset, depth
+    /// first, keeping the route paying out the most of `asset_out` in `best`.
+    fn search(
+        &self,
+        asset_out: &A,
+        amount: u64,
+        visited: &mut Vec<A>,
+        path: &mut Vec<(usize, AssetSwap<A>)>,
+        best: &mut Option<(Vec<usize>, Route<A>)>,
+    ) {
+        let asset = visited
+            .last()
+            .expect("the input asset is always visited")
+            .clone();
+        if asset == *asset_out {
+            if best
+                .as_ref()
+                .is_none_or(|(_, route)| amount > route.amount_out())
+            {
+                *best = Some((
+                    path.iter().map(|(index, _)| *index).collect(),
+                    Route {
+                        hops: path.iter().map(|(_, hop)| hop.clone()).collect(),
+                    },
+                ));
+            }
+            return;
+        }
+        if path.len() == self.max_hops {
+            return;
+        }
+        for (index, pool) in self.pools.iter().enumerate() {
+            let Ok(hop) = pool.quote_swap(&asset, amount) else {
+                continue;
+            };
+            if hop.amount_out == 0 || visited.contains(&hop.asset_out) {
+                continue;
+            }
+            visited.push(hop.asset_out.clone());
+            let amount_out = hop.amount_out;
+            path.push((index, hop));
+            self.search(asset_out, amount_out, visited, path, best);
+            path.pop();
+            visited.pop();
+        }
+    }
+}
+
+fn pairs<A: AssetId>(pool: &AssetPool<A>, a: &A, b: &A) -> bool {
+    let (first, second) = pool.assets();
+    (first == a && second == b) || (first == b && second == a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(
+        a: &'static str,
+        reserve_a: u64,
+        b: &'static str,
+        reserve_b: u64,
+    ) -> AssetPool<&'static str> {
+        AssetPool::new(a, reserve_a, b, reserve_b)
+            .unwrap()
+            .with_fee(30)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_best_route() {
+        let mut router = Router::default();
+        router.insert(pool("USDC", 2_000 * 10u64.pow(9), "ETH", 10u64.pow(9)));
+        router.insert(pool("ETH", 10u64.pow(9), "BTC", 5 * 10u64.pow(7)));
+        // A shallow direct pool loses to the deeper two-hop route for large trades.
+        router.insert(pool("USDC", 40 * 10u64.pow(9), "BTC", 10u64.pow(6)));
+        assert_eq!(router.len(), 3);
+
+        let amount_in = 10 * 10u64.pow(9);
+        let route = router.quote_route(&"USDC", &"BTC", amount_in).unwrap();
+        assert_eq!(route.hops.len(), 2);
+        // Paying in the native side of a pool may leave dust unspent.
+        assert!(route.amount_in() <= amount_in && route.amount_in() > amount_in - 10u64.pow(6));
+        let direct = router
+            .pool(&"BTC", &"USDC")
+            .unwrap()
+            .quote_swap(&"USDC", amount_in)
+            .unwrap();
+        assert!(route.amount_out() > direct.amount_out);
+
+        let executed = router
+            .execute_route(&"USDC", &"BTC", amount_in, Some(route.amount_out()))
+            .unwrap();
+        assert_eq!(executed, route);
+        assert!(router.quote_route(&"USDC", &"BTC", amount_in).unwrap() != route);
+    }
+
+    #[test]
+    fn test_route_errors() {
+        let mut router = Router::new(1);
+        router.insert(pool("USDC", 2_000 * 10u64.pow(9), "ETH", 10u64.pow(9)));
+        router.insert(pool("ETH", 10u64.pow(9), "BTC", 5 * 10u64.pow(7)));
+        assert_eq!(
+            router.quote_route(&"USDC", &"BTC", 10u64.pow(9)),
+            Err(PoolError::PoolNotFound)
+        );
+        let before = router.pool(&"USDC", &"ETH").unwrap().reserve(&"ETH");
+        assert!(matches!(
+            router.execute_route(&"USDC", &"ETH", 10u64.pow(9), Some(u64::MAX)),
+            Err(PoolError::SlippageExceeded {
+                limit: u64::MAX,
+                ..
+            })
+        ));
+        assert_eq!(
+            router.pool(&"USDC", &"ETH").unwrap().reserve(&"ETH"),
+            before
+        );
+        assert!(router.insert(pool("ETH", 1_000, "USDC", 1_000)).is_some());
+        assert_eq!(router.len(), 2);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{LiquidityPool, PoolSnapshot, Result, TradeOp};
+
+/// The most profitable sandwich of a pending trade, as returned by
+/// [`LiquidityPool::analyze_sandwich`].
+///
+/// The attacker trades tokens in the victim's direction right before it, `front_run`, and
+/// trades the same amount back right after, `back_run`, so it ends with the tokens it started
+/// with, transfer taxes aside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sandwich {
+    pub front_run: TradeOp,
+    pub back_run: TradeOp,
+    /// Native paid for the tokens on the buying leg, fee included.
+    pub attacker_cost: u64,
+    /// Native received for the tokens on the selling leg, net of the fee.
+    pub attacker_proceeds: u64,
+    /// `attacker_proceeds - attacker_cost`.
+    pub profit: u64,
+    /// Native the victim spends (buy) or receives (sell) once sandwiched.
+    pub victim_native: u64,
+    /// Native the victim would spend or receive without the attack.
+    pub victim_baseline: u64,
+    /// Native the victim loses to the attack: paid beyond the baseline on a buy, received
+    /// short of it on a sell.
+    pub victim_loss: u64,
+}
+
+impl LiquidityPool {
+    /// Returns the most profitable sandwich of `victim`, or `None` if none is profitable after
+    /// fees. The victim's slippage limit bounds the attack, since a trade pushed past it
+    /// reverts, and so does `attacker_budget`, the most native the attacker can pay on the
+    /// buying leg. The profit is searched over exact quotes up to the largest front-run both
+    /// allow. Fails like `victim` itself if it cannot execute on the current pool.
+    pub fn analyze_sandwich(
+        &self,
+        victim: &TradeOp,
+        attacker_budget: u64,
+    ) -> Result<Option<Sandwich>> {
+        // A bare copy, cheap to clone for every candidate attack.
+        let pool = PoolSnapshot::of(self).to_pool();
+        let victim_baseline = pool.simulate(victim)?.native_amount;
+        let attack = |token_amount| sandwich(&pool, victim, victim_baseline, token_amount);
+
+        // Larger front-runs cost more and leave the victim a worse price, so the feasible
+        // sizes form a range, found by bisection.
+        let (mut low, mut high) = (0, u64::MAX);
+        while low < high {
+            let mid = low + (high - low).div_ceil(2);
+            match attack(mid) {
+                Some(attack) if attack.attacker_cost <= attacker_budget => low = mid,
+                _ => high = mid - 1,
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        let profit = |token_amount| {
+            attack(token_amount).map_or(i128::MIN, |attack| {
+                attack.attacker_proceeds as i128 - attack.attacker_cost as i128
+            })
+        };
+        // The profit rises with the size of the front-run until fees and the curvature catch
+        // up with it; narrow down on the peak by ternary search.
+        let (mut left, mut right) = (1, low);
+        while right - left > 2 {
+            let third = (right - left) / 3;
+            if profit(left + third) < profit(right - third) {
+                left += third + 1;
+            } else {
+                right -= third + 1;
+            }
+        }
+        Ok((left..=right.max(left))
+            .chain([low])
+            .filter_map(&attack)
+            .filter(|attack| attack.attacker_proceeds > attack.attacker_cost)
+            .max_by_key(|attack| attack.attacker_proceeds - attack.attacker_cost)
+            .map(|attack| Sandwich {
+                profit: attack.attacker_proceeds - attack.attacker_cost,
+                ..attack
+            }))
+    }
+}
+
+/// Applies a sandwich of `token_amount` tokens around `victim` to a copy of `pool`, or returns
+/// `None` if any of the three trades fails. `profit` is left at zero.
+fn sandwich(
+    pool: &LiquidityPool,
+    victim: &TradeOp,
+    victim_baseline: u64,
+    token_amount: u64,
+) -> Option<Sandwich> {
+    if token_amount == 0 {
+        return None;
+    }
+    let buy = TradeOp::Buy {
+        token_amount,
+        max_native: None,
+    };
+    let sell = TradeOp::Sell {
+        token_amount,
+        min_native: None,
+    };
+    let (front_run, back_run) = match victim {
+        TradeOp::Buy { .. } => (buy, sell),
+        TradeOp::Sell { .. } => (sell, buy),
+    };
+    let mut pool = pool.clone();
+    let mut execute = |op: &TradeOp| {
+        let projection = pool.simulate(op).ok()?;
+        pool.apply_trade(&projection).ok()?;
+        Some(projection.native_amount)
+    };
+    let front_native = execute(&front_run)?;
+    let victim_native = execute(victim)?;
+    let back_native = execute(&back_run)?;
+    let (attacker_cost, attacker_proceeds, victim_loss) = match victim {
+        TradeOp::Buy { .. } => (
+            front_native,
+            back_native,
+            victim_native.saturating_sub(victim_baseline),
+        ),
+        TradeOp::Sell { .. } => (
+            back_native,
+            front_native,
+            victim_baseline.saturating_sub(victim_native),
+        ),
+    };
+    Some(Sandwich {
+        front_run,
+        back_run,
+        attacker_cost,
+        attacker_proceeds,
+        profit: 0,
+        victim_native,
+        victim_baseline,
+        victim_loss,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandwich_buy() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let token_amount = 10u64.pow(13);
+        let quoted = pool.simulate_buy(token_amount, None).unwrap();
+        // The victim accepts paying 2% more than quoted.
+        let victim = TradeOp::Buy {
+            token_amount,
+            max_native: Some(quoted + quoted / 50),
+        };
+        let sandwich = pool.analyze_sandwich(&victim, u64::MAX).unwrap().unwrap();
+        assert_eq!(sandwich.victim_baseline, quoted);
+        assert!(sandwich.victim_native <= quoted + quoted / 50);
+        assert!(sandwich.victim_loss > 0);
+        assert!(sandwich.profit > 0);
+        assert!(sandwich.profit < sandwich.victim_loss);
+        assert_eq!(
+            sandwich.profit,
+            sandwich.attacker_proceeds - sandwich.attacker_cost
+        );
+        assert_eq!(
+            sandwich.front_run.token_amount(),
+            sandwich.back_run.token_amount()
+        );
+
+        // A smaller budget caps the front-run, and the profit with it.
+        let capped = pool
+            .analyze_sandwich(&victim, sandwich.attacker_cost / 4)
+            .unwrap()
+            .unwrap();
+        assert!(capped.attacker_cost <= sandwich.attacker_cost / 4);
+        assert!(capped.profit < sandwich.profit);
+
+        // Without slippage tolerance there is nothing to extract.
+        let strict = TradeOp::Buy {
+            token_amount,
+            max_native: Some(quoted),
+        };
+        assert_eq!(pool.analyze_sandwich(&strict, u64::MAX), Ok(None));
+    }
+
+    #[test]
+    fn test_sandwich_sell() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let token_amount = 10u64.pow(13);
+        let quoted = pool.simulate_sell(token_amount, None).unwrap();
+        let victim = TradeOp::Sell {
+            token_amount,
+            min_native: Some(quoted - quoted / 50),
+        };
+        let sandwich = pool.analyze_sandwich(&victim, u64::MAX).unwrap().unwrap();
+        assert!(matches!(sandwich.front_run, TradeOp::Sell { .. }));
+        assert!(sandwich.victim_native >= quoted - quoted / 50);
+        assert_eq!(sandwich.victim_loss, quoted - sandwich.victim_native);
+        assert!(sandwich.profit > 0);
+    }
+}
@@ -0,0 +1,399 @@
+//! Reproducible scenario fixtures.
+//!
+//! A scenario describes one or more initial pools, a sequence of trades and the expected
+//! final reserves, in a line-based text format suited to attaching to bug reports:
+//!
+//! ```text
+//! # pool <name> <native_reserve> <token_reserve>
+//! pool main 1000000000 1000000000000000
+//! # buy <pool> <token_amount> [max_native] / sell <pool> <token_amount> [min_native]
+//! buy main 1000000000000
+//! sell main 500000000000 400000
+//! # expect <pool> <native_reserve> <token_reserve>
+//! expect main 1000500250 999500000000000
+//! ```
+//!
+//! A pool starts as [`LiquidityPool::new`] with the declared reserves. Any field of its
+//! [`PoolSnapshot`] can then be overridden before the trades with
+//! `set <pool> <field> <value>`, using the field names and values of the
+//! [`WalPool`](crate::wal::WalPool) snapshot file:
+//!
+//! ```text
+//! # A launchpad pool: 30 bps fee, a quarter of it to the protocol, 1% sell tax, 200000000
+//! # virtual tokens, rounding buys up, sells down and fees up.
+//! pool launch 30000000000 1273000000000000
+//! set launch fee_bps 30
+//! set launch protocol_fee_share_bps 2500
+//! set launch token_tax_sell_bps 100
+//! set launch virtual_token 200000000000000
+//! set launch rounding up:down:up
+//! ```
+//!
+//! The fields are `initial_token_reserve`, `native_reserve`, `token_reserve`,
+//! `constant_product`, `trade_count`, `native_volume`, `fee_bps`, `fees_collected`,
+//! `lp_supply`, `locked_liquidity`, `virtual_native`, `virtual_token`, `graduation` (`-`,
+//! `native:<reserve>:<supply>` or `sold:<bps>:<supply>`), `native_decimals`,
+//! `token_decimals`, `rounding` (`<buy>:<sell>:<fee>`, each `down` or `up`),
+//! `protocol_fee_share_bps`, `protocol_fees_owed`, `token_tax_buy_bps` and
+//! `token_tax_sell_bps`. Overrides are taken as is, so a state no pool can be in makes
+//! [`Scenario::run`] fail.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{LiquidityPool, PoolError, PoolSnapshot, Result, TradeOp};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScenarioError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Initial state of a named pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSpec {
+    pub name: String,
+    /// A new pool with the declared reserves, with every `set` override applied.
+    pub state: PoolSnapshot,
+}
+
+/// A trade applied to a named pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioOp {
+    pub pool: String,
+    pub op: TradeOp,
+}
+
+/// Reserves a named pool is expected to hold once every trade has been applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedState {
+    pub pool: String,
+    pub native_reserve: u64,
+    pub token_reserve: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scenario {
+    pub pools: Vec<PoolSpec>,
+    pub ops: Vec<ScenarioOp>,
+    pub expected: Vec<ExpectedState>,
+}
+
+/// A final reserve check that did not hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub expected: ExpectedState,
+    /// Actual `(native_reserve, token_reserve)`, or `None` if the pool does not exist.
+    pub actual: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioOutcome {
+    /// Result of each trade, in order.
+    pub results: Vec<Result<u64>>,
+    /// Final state of each pool, in declaration order.
+    pub pools: Vec<(String, LiquidityPool)>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl ScenarioOutcome {
+    /// Returns `true` if every expectation held.
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn pool(&self, name: &str) -> Option<&LiquidityPool> {
+        self.pools
+            .iter()
+            .find(|(pool_name, _)| pool_name == name)
+            .map(|(_, pool)| pool)
+    }
+}
+
+impl Scenario {
+    /// Loads a scenario from a fixture file.
+    pub fn from_file(path: impl AsRef<Path>) -> std::result::Result<Self, ScenarioError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Creates the pools, applies every trade and checks the expected final state.
+    ///
+    /// Failed trades are recorded in the outcome and do not stop the run. Trades on an
+    /// undeclared pool fail with [`PoolError::PoolNotFound`]. Fails like
+    /// [`LiquidityPool::try_from`] if a pool's overridden state is one no pool can be in.
+    pub fn run(&self) -> Result<ScenarioOutcome> {
+        let mut pools = self
+            .pools
+            .iter()
+            .map(|spec| LiquidityPool::try_from(spec.state).map(|pool| (spec.name.clone(), pool)))
+            .collect::<Result<Vec<_>>>()?;
+        let results = self
+            .ops
+            .iter()
+            .map(|scenario_op| {
+                pools
+                    .iter_mut()
+                    .find(|(name, _)| *name == scenario_op.pool)
+                    .ok_or(PoolError::PoolNotFound)
+                    .and_then(|(_, pool)| pool.execute(&scenario_op.op))
+            })
+            .collect();
+        let mut outcome = ScenarioOutcome {
+            results,
+            pools,
+            mismatches: Vec::new(),
+        };
+        outcome.mismatches = self
+            .expected
+            .iter()
+            .filter_map(|expected| {
+                let actual = outcome
+                    .pool(&expected.pool)
+                    .map(|pool| (pool.get_native_reserve(), pool.get_token_reserve()));
+                (actual != Some((expected.native_reserve, expected.token_reserve))).then(|| {
+                    Mismatch {
+                        expected: expected.clone(),
+                        actual,
+                    }
+                })
+            })
+            .collect();
+        Ok(outcome)
+    }
+}
+
+fn parse_u64(line: usize, field: &str, value: &str) -> std::result::Result<u64, ScenarioError> {
+    value.parse().map_err(|_| ScenarioError::Parse {
+        line,
+        message: format!("invalid {field} `{value}`"),
+    })
+}
+
+impl FromStr for Scenario {
+    type Err = ScenarioError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, ScenarioError> {
+        let mut scenario = Scenario::default();
+        for (index, raw) in s.lines().enumerate() {
+            let line = index + 1;
+            let content = raw.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = content.split_whitespace().collect();
+            let error = |message: &str| ScenarioError::Parse {
+                line,
+                message: message.to_string(),
+            };
+            match fields.as_slice() {
+                [] => {}
+                ["pool", name, native, token] => {
+                    let native_reserve = parse_u64(line, "native reserve", native)?;
+                    let token_reserve = parse_u64(line, "token reserve", token)?;
+                    if native_reserve == 0 || token_reserve == 0 {
+                        return Err(error("pool reserves must be non-zero"));
+                    }
+                    if scenario.pools.iter().any(|spec| spec.name == *name) {
+                        return Err(error("duplicate pool name"));
+                    }
+                    let pool = LiquidityPool::new(native_reserve, token_reserve)
+                        .map_err(|err| error(&err.to_string()))?;
+                    scenario.pools.push(PoolSpec {
+                        name: name.to_string(),
+                        state: pool.snapshot(),
+                    });
+                }
+                ["set", pool, field, value] => {
+                    let spec = scenario
+                        .pools
+                        .iter_mut()
+                        .find(|spec| spec.name == *pool)
+                        .ok_or_else(|| error(&format!("undeclared pool `{pool}`")))?;
+                    spec.state
+                        .set_field(field, value)
+                        .ok_or_else(|| error(&format!("invalid {field} `{value}`")))?;
+                }
+                [kind @ ("buy" | "sell"), pool, amount, limit @ ..] if limit.len() <= 1 => {
+                    let token_amount = parse_u64(line, "token amount", amount)?;
+                    let limit = limit
+                        .first()
+                        .map(|limit| parse_u64(line, "slippage limit", limit))
+                        .transpose()?;
+                    let op = if *kind == "buy" {
+                        TradeOp::Buy {
+                            token_amount,
+                            max_native: limit,
+                        }
+                    } else {
+                        TradeOp::Sell {
+                            token_amount,
+                            min_native: limit,
+                        }
+                    };
+                    scenario.ops.push(ScenarioOp {
+                        pool: pool.to_string(),
+                        op,
+                    });
+                }
+                ["expect", pool, native, token] => scenario.expected.push(ExpectedState {
+                    pool: pool.to_string(),
+                    native_reserve: parse_u64(line, "native reserve", native)?,
+                    token_reserve: parse_u64(line, "token reserve", token)?,
+                }),
+                _ => return Err(error(&format!("unrecognized directive `{}`", raw.trim()))),
+            }
+        }
+        Ok(scenario)
+    }
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for spec in &self.pools {
+            let state = &spec.state;
+            writeln!(
+                f,
+                "pool {} {} {}",
+                spec.name, state.native_reserve, state.token_reserve
+            )?;
+            // Only the fields a new pool with these reserves does not already have.
+            let base = LiquidityPool::new(state.native_reserve, state.token_reserve)
+                .map(|pool| pool.snapshot().fields())
+                .ok();
+            for (index, (field, value)) in state.fields().into_iter().enumerate() {
+                if base.as_ref().is_none_or(|base| base[index].1 != value) {
+                    writeln!(f, "set {} {field} {value}", spec.name)?;
+                }
+            }
+        }
+        for scenario_op in &self.ops {
+            let (kind, token_amount, limit) = match scenario_op.op {
+                TradeOp::Buy {
+                    token_amount,
+                    max_native,
+                } => ("buy", token_amount, max_native),
+                TradeOp::Sell {
+                    token_amount,
+                    min_native,
+                } => ("sell", token_amount, min_native),
+            };
+            write!(f, "{kind} {} {token_amount}", scenario_op.pool)?;
+            if let Some(limit) = limit {
+                write!(f, " {limit}")?;
+            }
+            writeln!(f)?;
+        }
+        for expected in &self.expected {
+            writeln!(
+                f,
+                "expect {} {} {}",
+                expected.pool, expected.native_reserve, expected.token_reserve
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "
+        # Buy then partially sell back.
+        pool main 1000000000 1000000000000000
+        buy main 1000000000000
+        sell main 500000000000 400000
+        expect main 1000500250 999500000000000
+    ";
+
+    #[test]
+    fn test_run_fixture() {
+        let scenario: Scenario = FIXTURE.parse().unwrap();
+        let outcome = scenario.run().unwrap();
+        assert!(outcome.results.iter().all(|result| result.is_ok()));
+        assert!(outcome.passed());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let scenario: Scenario = FIXTURE.parse().unwrap();
+        let reparsed: Scenario = scenario.to_string().parse().unwrap();
+        assert_eq!(scenario, reparsed);
+    }
+
+    #[test]
+    fn test_mismatch_and_parse_error() {
+        let scenario: Scenario = "pool a 100 100\nbuy a 50\nexpect a 100 100"
+            .parse()
+            .unwrap();
+        let outcome = scenario.run().unwrap();
+        assert_eq!(outcome.mismatches[0].actual, Some((200, 50)));
+
+        let err = "pool a 100 100\nswap a 5".parse::<Scenario>().unwrap_err();
+        assert!(matches!(err, ScenarioError::Parse { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_pool_settings() {
+        let fixture = "
+            pool launch 30000000000 1273000000000000
+            set launch fee_bps 30
+            set launch protocol_fee_share_bps 2500
+            set launch token_tax_sell_bps 100
+            set launch virtual_token 200000000000000
+            set launch rounding up:down:up
+            buy launch 100000000000000
+            sell launch 40000000000000
+        ";
+        let scenario: Scenario = fixture.parse().unwrap();
+        let mut pool = LiquidityPool::with_virtual_reserves(
+            30_000_000_000,
+            1_073_000_000_000_000,
+            0,
+            200_000_000_000_000,
+        )
+        .unwrap()
+        .with_fee(30)
+        .unwrap()
+        .with_protocol_fee_share(2_500)
+        .unwrap()
+        .with_token_tax(crate::TokenTax {
+            buy_bps: 0,
+            sell_bps: 100,
+        })
+        .unwrap()
+        .with_rounding(crate::RoundingPolicy {
+            buy: crate::Rounding::Up,
+            sell: crate::Rounding::Down,
+            fee: crate::Rounding::Up,
+        });
+        assert_eq!(scenario.pools[0].state, pool.snapshot());
+
+        let outcome = scenario.run().unwrap();
+        for (scenario_op, result) in scenario.ops.iter().zip(&outcome.results) {
+            assert_eq!(*result, pool.execute(&scenario_op.op));
+        }
+        assert_eq!(outcome.pool("launch").unwrap().snapshot(), pool.snapshot());
+
+        // Every field, graduation included, survives writing the fixture back.
+        let mut scenario = scenario;
+        scenario.pools[0].state = pool
+            .with_graduation(crate::GraduationTarget::TokensSoldBps(8_000))
+            .unwrap()
+            .snapshot();
+        let written = scenario.to_string();
+        assert!(written.contains("set launch graduation sold:8000:"));
+        assert_eq!(written.parse::<Scenario>().unwrap(), scenario);
+
+        let err = "pool a 100 100\nset b fee_bps 30"
+            .parse::<Scenario>()
+            .unwrap_err();
+        assert!(matches!(err, ScenarioError::Parse { line: 2, .. }));
+        let err = "pool a 100 100\nset a fee 30"
+            .parse::<Scenario>()
+            .unwrap_err();
+        assert!(matches!(err, ScenarioError::Parse { line: 2, .. }));
+        let invalid: Scenario = "pool a 100 100\nset a fee_bps 10000".parse().unwrap();
+        assert_eq!(invalid.run().unwrap_err(), PoolError::InvalidAmount);
+    }
+}
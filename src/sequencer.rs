@@ -0,0 +1,264 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use crate::{LiquidityPool, Result, TradeOp};
+
+/// Order in which the sequencer applies queued trades.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequencingPolicy {
+    /// Trades are applied in the order they were received.
+    Fifo,
+    /// Queued trades are applied highest priority first, ties broken by arrival order.
+    Priority,
+}
+
+/// The outcome of a trade applied by the sequencer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Receipt {
+    /// Position of the trade among those the sequencer applied, from 0. Rejected trades are
+    /// not applied and take no position, so positions follow the pool's trade count.
+    pub seq: u64,
+    pub op: TradeOp,
+    /// Native currency spent (buy) or received (sell).
+    pub native_amount: u64,
+    pub native_reserve: u64,
+    pub token_reserve: u64,
+}
+
+struct Request {
+    op: TradeOp,
+    priority: u32,
+    arrival: u64,
+    reply: SyncSender<Result<Receipt>>,
+}
+
+impl Request {
+    fn key(&self) -> (u32, Reverse<u64>) {
+        (self.priority, Reverse(self.arrival))
+    }
+}
+
+impl PartialEq for Request {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Request {}
+
+impl PartialOrd for Request {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Request {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+enum Message {
+    Trade {
+        op: TradeOp,
+        priority: u32,
+        reply: SyncSender<Result<Receipt>>,
+    },
+    Shutdown,
+}
+
+/// Applies trades submitted from many threads to a single pool in a deterministic order.
+///
+/// The pool is owned by a worker thread; each submission returns a one-shot receiver that
+/// yields the trade's [`Receipt`] once it has been applied.
+#[derive(Debug)]
+pub struct Sequencer {
+    sender: Sender<Message>,
+    worker: JoinHandle<LiquidityPool>,
+}
+
+/// A cloneable handle used to submit trades to a [`Sequencer`].
+#[derive(Debug, Clone)]
+pub struct SequencerHandle {
+    sender: Sender<Message>,
+}
+
+impl Sequencer {
+    /// Moves `pool` onto a worker thread and starts accepting trades.
+    pub fn spawn(pool: LiquidityPool, policy: SequencingPolicy) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::spawn(move || run(pool, receiver, policy));
+        Self { sender, worker }
+    }
+
+    pub fn handle(&self) -> SequencerHandle {
+        SequencerHandle {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Applies every trade submitted so far, stops the worker and returns the final pool.
+    /// Trades submitted afterwards are dropped and their receivers disconnect.
+    pub fn shutdown(self) -> LiquidityPool {
+        // The worker only exits after receiving this message, so the send cannot fail.
+        let _ = self.sender.send(Message::Shutdown);
+        self.worker.join().expect("sequencer worker panicked")
+    }
+}
+
+impl SequencerHandle {
+    /// Queues `op` with the lowest priority.
+    pub fn submit(&self, op: TradeOp) -> Receiver<Result<Receipt>> {
+        self.submit_with_priority(op, 0)
+    }
+
+    /// Queues `op` with `priority`, which is only taken into account by [`SequencingPolicy::Priority`].
+    pub fn submit_with_priority(&self, op: TradeOp, priority: u32) -> Receiver<Result<Receipt>> {
+        let (reply, receipt) = mpsc::sync_channel(1);
+        // If the sequencer has shut down the request is dropped and `receipt` disconnects.
+        let _ = self.sender.send(Message::Trade {
+            op,
+            priority,
+            reply,
+        });
+        receipt
+    }
+}
+
+fn run(
+    mut pool: LiquidityPool,
+    receiver: Receiver<Message>,
+    policy: SequencingPolicy,
+) -> LiquidityPool {
+    let mut queue = BinaryHeap::new();
+    let mut arrival = 0u64;
+    let mut seq = 0u64;
+    let mut enqueue = |queue: &mut BinaryHeap<Request>, message| match message {
+        Message::Trade {
+            op,
+            priority,
+            reply,
+        } => {
+            let priority = match policy {
+                SequencingPolicy::Fifo => 0,
+                SequencingPolicy::Priority => priority,
+            };
+            queue.push(Request {
+                op,
+                priority,
+                arrival,
+                reply,
+            });
+            arrival += 1;
+            true
+        }
+        Message::Shutdown => false,
+    };
+
+    let mut running = true;
+    while running {
+        let Ok(message) = receiver.recv() else {
+            break;
+        };
+        running = enqueue(&mut queue, message);
+        // Collect everything already waiting so priorities are compared across the whole backlog.
+        while running {
+            match receiver.try_recv() {
+                Ok(message) => running = enqueue(&mut queue, message),
+                Err(_) => break,
+            }
+        }
+        while let Some(request) = queue.pop() {
+            let result = pool.execute(&request.op).map(|native_amount| {
+                seq += 1;
+                Receipt {
+                    seq: seq - 1,
+                    op: request.op,
+                    native_amount,
+                    native_reserve: pool.get_native_reserve(),
+                    token_reserve: pool.get_token_reserve(),
+                }
+            });
+            // The submitter may have dropped its receiver; the trade is applied regardless.
+            let _ = request.reply.send(result);
+        }
+    }
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(token_amount: u64) -> TradeOp {
+        TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        }
+    }
+
+    #[test]
+    fn test_concurrent_submissions() {
+        let sequencer = Sequencer::spawn(LiquidityPool::default(), SequencingPolicy::Fifo);
+        let token_amount = 1_000 * 10u64.pow(6);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = sequencer.handle();
+                thread::spawn(move || {
+                    (0..25)
+                        .map(|_| handle.submit(buy(token_amount)).recv().unwrap().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut seqs: Vec<u64> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .map(|receipt| receipt.seq)
+            .collect();
+        seqs.sort_unstable();
+        assert_eq!(seqs, (0..100).collect::<Vec<_>>());
+
+        let pool = sequencer.shutdown();
+        assert_eq!(
+            pool.get_token_reserve(),
+            LiquidityPool::default().get_token_reserve() - 100 * token_amount
+        );
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let (sender, receiver) = mpsc::channel();
+        let handle = SequencerHandle { sender };
+        let token_amount = 1_000 * 10u64.pow(6);
+        let low = handle.submit_with_priority(buy(token_amount), 1);
+        let high = handle.submit_with_priority(buy(2 * token_amount), 5);
+        let also_low = handle.submit_with_priority(buy(3 * token_amount), 1);
+        handle.sender.send(Message::Shutdown).unwrap();
+
+        run(
+            LiquidityPool::default(),
+            receiver,
+            SequencingPolicy::Priority,
+        );
+        assert_eq!(high.recv().unwrap().unwrap().seq, 0);
+        assert_eq!(low.recv().unwrap().unwrap().seq, 1);
+        assert_eq!(also_low.recv().unwrap().unwrap().seq, 2);
+    }
+
+    #[test]
+    fn test_failed_trade_receipt() {
+        let sequencer = Sequencer::spawn(LiquidityPool::default(), SequencingPolicy::Fifo);
+        let receipt = sequencer.handle().submit(buy(0)).recv().unwrap();
+        assert_eq!(receipt, Err(crate::PoolError::InvalidAmount));
+        let receipt = sequencer
+            .handle()
+            .submit(buy(10u64.pow(12)))
+            .recv()
+            .unwrap();
+        assert_eq!(receipt.unwrap().seq, 0);
+        assert_eq!(sequencer.shutdown().trade_count(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+//! Monte Carlo simulation of random trade flow, for stress-testing launch parameters.
+//!
+//! Each run applies a [`TradeGenerator`] stream to its own copy of the pool, skipping the
+//! trades the pool rejects, and the outcomes of all runs are summarized as distributions. Runs
+//! are seeded from a single seed, so a simulation is reproducible on every machine.
+
+use crate::rng::SplitMix64;
+use crate::{LiquidityPool, PoolError, Result, SizeDistribution, TradeGenerator};
+
+/// Parameters of a simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimConfig {
+    pub sizes: SizeDistribution,
+    /// Probability of each trade being a buy, see [`TradeGenerator::new`].
+    pub buy_ratio: f64,
+    /// Trades attempted per run.
+    pub steps: usize,
+    pub runs: usize,
+    pub seed: u64,
+}
+
+/// Outcome of a single run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunOutcome {
+    /// Seed of the run's trade stream, to replay it with [`TradeGenerator::new`].
+    pub seed: u64,
+    /// Spot price once every trade was attempted.
+    pub final_price: f64,
+    /// Native currency spent and received by the trades applied.
+    pub native_volume: u128,
+    /// Swap fees charged by the trades applied.
+    pub fees: u128,
+    /// Trades the pool rejected.
+    pub rejected: usize,
+}
+
+/// Summary of a sample, with nearest-rank percentiles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Distribution {
+    pub min: f64,
+    pub p5: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl Distribution {
+    /// Summarizes `values`, or returns `None` if there are none.
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let percentile = |percentile: f64| {
+            let rank = (percentile / 100.0 * values.len() as f64).ceil() as usize;
+            values[rank.clamp(1, values.len()) - 1]
+        };
+        Some(Self {
+            min: values[0],
+            p5: percentile(5.0),
+            median: percentile(50.0),
+            p95: percentile(95.0),
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+        })
+    }
+}
+
+/// Outcomes of every run of a simulation and their distributions.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimReport {
+    pub runs: Vec<RunOutcome>,
+    pub final_price: Distribution,
+    pub native_volume: Distribution,
+    pub fees: Distribution,
+}
+
+/// Runs `config.runs` randomized trade sequences against copies of `pool`. Fails with
+/// [`PoolError::InvalidAmount`] if no run is requested.
+pub fn run(pool: &LiquidityPool, config: &SimConfig) -> Result<SimReport> {
+    if config.runs == 0 {
+        return Err(PoolError::InvalidAmount);
+    }
+    let mut seeds = SplitMix64::new(config.seed);
+    let runs: Vec<RunOutcome> = (0..config.runs)
+        .map(|_| run_once(pool, config, seeds.next_u64()))
+        .collect();
+    let distribution = |value: fn(&RunOutcome) -> f64| {
+        Distribution::of(runs.iter().map(value)).ok_or(PoolError::InvalidAmount)
+    };
+    Ok(SimReport {
+        final_price: distribution(|run| run.final_price)?,
+        native_volume: distribution(|run| run.native_volume as f64)?,
+        fees: distribution(|run| run.fees as f64)?,
+        runs,
+    })
+}
+
+fn run_once(pool: &LiquidityPool, config: &SimConfig, seed: u64) -> RunOutcome {
+    let mut pool = pool.clone();
+    pool.reset_stats();
+    let mut rejected = 0;
+    for op in TradeGenerator::new(seed, config.sizes, config.buy_ratio).take(config.steps) {
+        if pool.execute(&op).is_err() {
+            rejected += 1;
+        }
+    }
+    RunOutcome {
+        seed,
+        final_price: pool.spot_price(),
+        native_volume: pool.stats().native_volume(),
+        fees: pool.stats().fees,
+        rejected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(seed: u64) -> SimConfig {
+        SimConfig {
+            sizes: SizeDistribution::LogUniform {
+                min: 10u64.pow(6),
+                max: 10u64.pow(13),
+            },
+            buy_ratio: 0.6,
+            steps: 200,
+            runs: 50,
+            seed,
+        }
+    }
+
+    #[test]
+    fn test_simulation_is_reproducible() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let report = run(&pool, &config(7)).unwrap();
+        assert_eq!(report.runs.len(), 50);
+        assert_eq!(report, run(&pool, &config(7)).unwrap());
+        assert_ne!(report, run(&pool, &config(8)).unwrap());
+
+        let price = report.final_price;
+        assert!(price.min <= price.p5 && price.p5 <= price.median);
+        assert!(price.median <= price.p95 && price.p95 <= price.max);
+        // Buy-heavy flow pushes the price up.
+        assert!(price.median > pool.spot_price());
+        assert!(report.fees.min > 0.0);
+        assert_eq!(pool.trade_count(), 0);
+
+        let first = report.runs[0];
+        let mut replayed = pool.clone();
+        for op in TradeGenerator::new(first.seed, config(7).sizes, 0.6).take(200) {
+            let _ = replayed.execute(&op);
+        }
+        assert_eq!(replayed.spot_price(), first.final_price);
+    }
+
+    #[test]
+    fn test_distribution() {
+        let summary = Distribution::of((1..=100).map(f64::from)).unwrap();
+        assert_eq!((summary.min, summary.max), (1.0, 100.0));
+        assert_eq!((summary.p5, summary.median, summary.p95), (5.0, 50.0, 95.0));
+        assert_eq!(summary.mean, 50.5);
+        assert_eq!(Distribution::of([]), None);
+        assert_eq!(
+            run(
+                &LiquidityPool::default(),
+                &SimConfig {
+                    runs: 0,
+                    ..config(1)
+                }
+            ),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,657 @@
+use crate::{
+    ConstantProduct, Curve, Graduation, LiquidityPool, PoolError, RoundingPolicy, TokenTax,
+    MAX_DECIMALS, MAX_FEE_BPS,
+};
+#[cfg(feature = "std")]
+use crate::{GraduationTarget, Rounding};
+
+/// A copy of the core state of a pool at a given point in its trade sequence.
+///
+/// With the `borsh` feature, snapshots and pools are encoded as a layout version byte followed
+/// by the fields of that layout, little-endian. Layout `2`, written by this version, is
+/// `initial_token_reserve: u64`, `native_reserve: u64`, `token_reserve: u64`,
+/// `constant_product: u128`, `trade_count: u64`, `native_volume: u128`, `fee_bps: u16`,
+/// `fees_collected: u128`, `lp_supply: u64`, `locked_liquidity: u64`, `virtual_native: u64`,
+/// `virtual_token: u64`, then `graduation` as a `0` byte, or a `1` byte followed by the
+/// [`Graduation`], then `native_decimals: u8`, `token_decimals: u8`, the [`RoundingPolicy`] as
+/// three bytes, `0` for down and `1` for up, `protocol_fee_share_bps: u16`,
+/// `protocol_fees_owed: u128`, and the [`TokenTax`] as `buy_bps: u16` and `sell_bps: u16`:
+/// 143 bytes for pools without graduation. Layout `1` is its first ten fields, 98 bytes, with
+/// the fee kept out of the reserves. Published layouts never change: new fields get a new
+/// version, and every version keeps decoding. Layout `1` data written before the version byte
+/// existed decodes with [`deserialize_unversioned`](Self::deserialize_unversioned).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolSnapshot {
+    pub(crate) initial_token_reserve: u64,
+    pub(crate) native_reserve: u64,
+    pub(crate) token_reserve: u64,
+    pub(crate) constant_product: u128,
+    pub(crate) trade_count: u64,
+    pub(crate) native_volume: u128,
+    pub(crate) fee_bps: u16,
+    pub(crate) fees_collected: u128,
+    pub(crate) lp_supply: u64,
+    pub(crate) locked_liquidity: u64,
+    pub(crate) virtual_native: u64,
+    pub(crate) virtual_token: u64,
+    pub(crate) graduation: Option<Graduation>,
+    pub(crate) native_decimals: u8,
+    pub(crate) token_decimals: u8,
+    pub(crate) rounding: RoundingPolicy,
+    pub(crate) protocol_fee_share_bps: u16,
+    pub(crate) protocol_fees_owed: u128,
+    pub(crate) token_tax: TokenTax,
+}
+
+impl PoolSnapshot {
+    pub(crate) fn of<C: Curve>(pool: &LiquidityPool<C>) -> Self {
+        Self {
+            initial_token_reserve: pool.initial_token_reserve,
+            native_reserve: pool.native_reserve,
+            token_reserve: pool.token_reserve,
+            constant_product: pool.constant_product,
+            trade_count: pool.trade_count,
+            native_volume: pool.native_volume,
+            fee_bps: pool.fee_bps,
+            fees_collected: pool.fees_collected,
+            lp_supply: pool.lp_supply,
+            locked_liquidity: pool.locked_liquidity,
+            virtual_native: pool.virtual_native,
+            virtual_token: pool.virtual_token,
+            graduation: pool.graduation,
+            native_decimals: pool.native_decimals,
+            token_decimals: pool.token_decimals,
+            rounding: pool.rounding,
+            protocol_fee_share_bps: pool.protocol_fee_share_bps,
+            protocol_fees_owed: pool.protocol_fees_owed,
+            token_tax: pool.token_tax,
+        }
+    }
+
+    pub fn native_reserve(&self) -> u64 {
+        self.native_reserve
+    }
+
+    pub fn token_reserve(&self) -> u64 {
+        self.token_reserve
+    }
+
+    pub fn constant_product(&self) -> u128 {
+        self.constant_product
+    }
+
+    /// Number of trades the pool had applied when the snapshot was taken.
+    pub fn trade_count(&self) -> u64 {
+        self.trade_count
+    }
+
+    pub fn native_volume(&self) -> u128 {
+        self.native_volume
+    }
+
+    pub fn fee_bps(&self) -> u16 {
+        self.fee_bps
+    }
+
+    pub fn fees_collected(&self) -> u128 {
+        self.fees_collected
+    }
+
+    pub fn lp_supply(&self) -> u64 {
+        self.lp_supply
+    }
+
+    /// Spot price of one token in native currency, `native_reserve / token_reserve`.
+    pub fn spot_price(&self) -> f64 {
+        self.native_reserve as f64 / self.token_reserve as f64
+    }
+
+    /// Returns `steps` states strictly between `self` and `to`, evenly spaced in spot price.
+    ///
+    /// Each state lies on the constant-product curve, with `k` itself interpolated so that
+    /// snapshots drifted apart by rounding still join smoothly. Interior states are estimates,
+    /// not pool history, and keep the trade count, volume, fees and LP shares of `self`.
+    #[cfg(feature = "std")]
+    pub fn interpolate(&self, to: &PoolSnapshot, steps: usize) -> Vec<PoolSnapshot> {
+        let (price_from, price_to) = (self.spot_price(), to.spot_price());
+        let (k_from, k_to) = (self.constant_product as f64, to.constant_product as f64);
+        (1..=steps)
+            .map(|step| {
+                let fraction = step as f64 / (steps + 1) as f64;
+                let price = price_from + (price_to - price_from) * fraction;
+                let k = k_from + (k_to - k_from) * fraction;
+                let native_reserve = ((k * price).sqrt().round() as u64).max(1);
+                let token_reserve = ((k / price).sqrt().round() as u64).max(1);
+                PoolSnapshot {
+                    initial_token_reserve: self.initial_token_reserve,
+                    native_reserve,
+                    token_reserve,
+                    constant_product: native_reserve as u128 * token_reserve as u128,
+                    trade_count: self.trade_count,
+                    native_volume: self.native_volume,
+                    fee_bps: self.fee_bps,
+                    fees_collected: self.fees_collected,
+                    lp_supply: self.lp_supply,
+                    locked_liquidity: self.locked_liquidity,
+                    virtual_native: self.virtual_native,
+                    virtual_token: self.virtual_token,
+                    graduation: self.graduation,
+                    native_decimals: self.native_decimals,
+                    token_decimals: self.token_decimals,
+                    rounding: self.rounding,
+                    protocol_fee_share_bps: self.protocol_fee_share_bps,
+                    protocol_fees_owed: self.protocol_fees_owed,
+                    token_tax: self.token_tax,
+                }
+            })
+            .collect()
+    }
+
+    /// Builds a standalone pool in the captured state, without any recording enabled.
+    pub fn to_pool(&self) -> LiquidityPool {
+        LiquidityPool {
+            initial_token_reserve: self.initial_token_reserve,
+            native_reserve: self.native_reserve,
+            token_reserve: self.token_reserve,
+            constant_product: self.constant_product,
+            trade_count: self.trade_count,
+            native_volume: self.native_volume,
+            fee_bps: self.fee_bps,
+            fees_collected: self.fees_collected,
+            protocol_fee_share_bps: self.protocol_fee_share_bps,
+            protocol_fees_owed: self.protocol_fees_owed,
+            lp_supply: self.lp_supply,
+            locked_liquidity: self.locked_liquidity,
+            virtual_native: self.virtual_native,
+            virtual_token: self.virtual_token,
+            graduation: self.graduation,
+            native_decimals: self.native_decimals,
+            token_decimals: self.token_decimals,
+            rounding: self.rounding,
+            token_tax: self.token_tax,
+            curve: ConstantProduct,
+            #[cfg(feature = "std")]
+            checkpoints: None,
+            history: None,
+            oracle: None,
+            stats: Default::default(),
+            #[cfg(feature = "std")]
+            alerts: Default::default(),
+            observers: Default::default(),
+        }
+    }
+}
+
+impl LiquidityPool {
+    /// Returns a copy of the core state of the pool, to [`restore`](Self::restore) later.
+    pub fn snapshot(&self) -> PoolSnapshot {
+        PoolSnapshot::of(self)
+    }
+
+    /// Resets the core state of the pool to `snapshot`, rejecting states no pool can be in.
+    /// Recorded history and checkpoints restart from the restored state, since the trades they
+    /// cover may have been undone; alert subscriptions are kept.
+    pub fn restore(&mut self, snapshot: &PoolSnapshot) -> Result<(), PoolError> {
+        LiquidityPool::try_from(*snapshot)?;
+        self.initial_token_reserve = snapshot.initial_token_reserve;
+        self.native_reserve = snapshot.native_reserve;
+        self.token_reserve = snapshot.token_reserve;
+        self.constant_product = snapshot.constant_product;
+        self.trade_count = snapshot.trade_count;
+        self.native_volume = snapshot.native_volume;
+        self.fee_bps = snapshot.fee_bps;
+        self.fees_collected = snapshot.fees_collected;
+        self.lp_supply = snapshot.lp_supply;
+        self.locked_liquidity = snapshot.locked_liquidity;
+        self.virtual_native = snapshot.virtual_native;
+        self.virtual_token = snapshot.virtual_token;
+        self.graduation = snapshot.graduation;
+        self.native_decimals = snapshot.native_decimals;
+        self.token_decimals = snapshot.token_decimals;
+        self.rounding = snapshot.rounding;
+        self.protocol_fee_share_bps = snapshot.protocol_fee_share_bps;
+        self.protocol_fees_owed = snapshot.protocol_fees_owed;
+        self.token_tax = snapshot.token_tax;
+        if self.history.is_some() {
+            self.enable_history();
+        }
+        #[cfg(feature = "std")]
+        if let Some(mut checkpoints) = self.checkpoints.take() {
+            checkpoints.reset(self);
+            self.checkpoints = Some(checkpoints);
+        }
+        Ok(())
+    }
+}
+
+/// Version of the borsh layout written by [`PoolSnapshot`]'s `BorshSerialize`.
+#[cfg(feature = "borsh")]
+const BORSH_LAYOUT_VERSION: u8 = 2;
+
+#[cfg(feature = "borsh")]
+impl PoolSnapshot {
+    /// Decodes layout `1` without its version byte, as encoded before layouts were versioned.
+    ///
+    /// Fields added since take their defaults: no virtual reserves, graduation or decimals,
+    /// [`RoundingPolicy::DEFAULT`], no token tax, and the whole fee owed to the protocol, since
+    /// it never reached the reserves.
+    pub fn deserialize_unversioned<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        use borsh::BorshDeserialize;
+
+        Ok(Self {
+            initial_token_reserve: u64::deserialize_reader(reader)?,
+            native_reserve: u64::deserialize_reader(reader)?,
+            token_reserve: u64::deserialize_reader(reader)?,
+            constant_product: u128::deserialize_reader(reader)?,
+            trade_count: u64::deserialize_reader(reader)?,
+            native_volume: u128::deserialize_reader(reader)?,
+            fee_bps: u16::deserialize_reader(reader)?,
+            fees_collected: u128::deserialize_reader(reader)?,
+            lp_supply: u64::deserialize_reader(reader)?,
+            locked_liquidity: u64::deserialize_reader(reader)?,
+            virtual_native: 0,
+            virtual_token: 0,
+            graduation: None,
+            native_decimals: 0,
+            token_decimals: 0,
+            rounding: RoundingPolicy::DEFAULT,
+            protocol_fee_share_bps: MAX_FEE_BPS,
+            protocol_fees_owed: 0,
+            token_tax: TokenTax::default(),
+        })
+    }
+
+    fn deserialize_v2<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        use borsh::BorshDeserialize;
+
+        Ok(Self {
+            initial_token_reserve: u64::deserialize_reader(reader)?,
+            native_reserve: u64::deserialize_reader(reader)?,
+            token_reserve: u64::deserialize_reader(reader)?,
+            constant_product: u128::deserialize_reader(reader)?,
+            trade_count: u64::deserialize_reader(reader)?,
+            native_volume: u128::deserialize_reader(reader)?,
+            fee_bps: u16::deserialize_reader(reader)?,
+            fees_collected: u128::deserialize_reader(reader)?,
+            lp_supply: u64::deserialize_reader(reader)?,
+            locked_liquidity: u64::deserialize_reader(reader)?,
+            virtual_native: u64::deserialize_reader(reader)?,
+            virtual_token: u64::deserialize_reader(reader)?,
+            graduation: Option::deserialize_reader(reader)?,
+            native_decimals: u8::deserialize_reader(reader)?,
+            token_decimals: u8::deserialize_reader(reader)?,
+            rounding: RoundingPolicy::deserialize_reader(reader)?,
+            protocol_fee_share_bps: u16::deserialize_reader(reader)?,
+            protocol_fees_owed: u128::deserialize_reader(reader)?,
+            token_tax: TokenTax::deserialize_reader(reader)?,
+        })
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for PoolSnapshot {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        BORSH_LAYOUT_VERSION.serialize(writer)?;
+        self.initial_token_reserve.serialize(writer)?;
+        self.native_reserve.serialize(writer)?;
+        self.token_reserve.serialize(writer)?;
+        self.constant_product.serialize(writer)?;
+        self.trade_count.serialize(writer)?;
+        self.native_volume.serialize(writer)?;
+        self.fee_bps.serialize(writer)?;
+        self.fees_collected.serialize(writer)?;
+        self.lp_supply.serialize(writer)?;
+        self.locked_liquidity.serialize(writer)?;
+        self.virtual_native.serialize(writer)?;
+        self.virtual_token.serialize(writer)?;
+        self.graduation.serialize(writer)?;
+        self.native_decimals.serialize(writer)?;
+        self.token_decimals.serialize(writer)?;
+        self.rounding.serialize(writer)?;
+        self.protocol_fee_share_bps.serialize(writer)?;
+        self.protocol_fees_owed.serialize(writer)?;
+        self.token_tax.serialize(writer)
+    }
+}
+
+/// Decodes any published layout, failing on versions this crate does not know.
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for PoolSnapshot {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        match u8::deserialize_reader(reader)? {
+            1 => Self::deserialize_unversioned(reader),
+            2 => Self::deserialize_v2(reader),
+            _ => Err(borsh::io::Error::new(
+                borsh::io::ErrorKind::InvalidData,
+                "unknown PoolSnapshot layout version",
+            )),
+        }
+    }
+}
+
+/// Text form of the fields, shared by the [`WalPool`](crate::wal::WalPool) snapshot file and
+/// scenario fixtures.
+#[cfg(feature = "std")]
+impl PoolSnapshot {
+    /// Returns every field as a `(name, value)` pair in declaration order, with the token tax
+    /// split into `token_tax_buy_bps` and `token_tax_sell_bps`.
+    pub(crate) fn fields(&self) -> [(&'static str, String); 20] {
+        [
+            (
+                "initial_token_reserve",
+                self.initial_token_reserve.to_string(),
+            ),
+            ("native_reserve", self.native_reserve.to_string()),
+            ("token_reserve", self.token_reserve.to_string()),
+            ("constant_product", self.constant_product.to_string()),
+            ("trade_count", self.trade_count.to_string()),
+            ("native_volume", self.native_volume.to_string()),
+            ("fee_bps", self.fee_bps.to_string()),
+            ("fees_collected", self.fees_collected.to_string()),
+            ("lp_supply", self.lp_supply.to_string()),
+            ("locked_liquidity", self.locked_liquidity.to_string()),
+            ("virtual_native", self.virtual_native.to_string()),
+            ("virtual_token", self.virtual_token.to_string()),
+            ("graduation", encode_graduation(self.graduation)),
+            ("native_decimals", self.native_decimals.to_string()),
+            ("token_decimals", self.token_decimals.to_string()),
+            ("rounding", encode_rounding(self.rounding)),
+            (
+                "protocol_fee_share_bps",
+                self.protocol_fee_share_bps.to_string(),
+            ),
+            ("protocol_fees_owed", self.protocol_fees_owed.to_string()),
+            ("token_tax_buy_bps", self.token_tax.buy_bps.to_string()),
+            ("token_tax_sell_bps", self.token_tax.sell_bps.to_string()),
+        ]
+    }
+
+    /// Sets the field `name` from `value` in the form [`fields`](Self::fields) writes. Returns
+    /// `None` for an unknown field or a malformed value.
+    pub(crate) fn set_field(&mut self, name: &str, value: &str) -> Option<()> {
+        fn parse<T: core::str::FromStr>(value: &str) -> Option<T> {
+            value.parse().ok()
+        }
+
+        match name {
+            "initial_token_reserve" => self.initial_token_reserve = parse(value)?,
+            "native_reserve" => self.native_reserve = parse(value)?,
+            "token_reserve" => self.token_reserve = parse(value)?,
+            "constant_product" => self.constant_product = parse(value)?,
+            "trade_count" => self.trade_count = parse(value)?,
+            "native_volume" => self.native_volume = parse(value)?,
+            "fee_bps" => self.fee_bps = parse(value)?,
+            "fees_collected" => self.fees_collected = parse(value)?,
+            "lp_supply" => self.lp_supply = parse(value)?,
+            "locked_liquidity" => self.locked_liquidity = parse(value)?,
+            "virtual_native" => self.virtual_native = parse(value)?,
+            "virtual_token" => self.virtual_token = parse(value)?,
+            "graduation" => self.graduation = decode_graduation(value)?,
+            "native_decimals" => self.native_decimals = parse(value)?,
+            "token_decimals" => self.token_decimals = parse(value)?,
+            "rounding" => self.rounding = decode_rounding(value)?,
+            "protocol_fee_share_bps" => self.protocol_fee_share_bps = parse(value)?,
+            "protocol_fees_owed" => self.protocol_fees_owed = parse(value)?,
+            "token_tax_buy_bps" => self.token_tax.buy_bps = parse(value)?,
+            "token_tax_sell_bps" => self.token_tax.sell_bps = parse(value)?,
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Encodes a graduation as a single field: `-`, `native:<reserve>:<supply>` or
+/// `sold:<bps>:<supply>`.
+#[cfg(feature = "std")]
+fn encode_graduation(graduation: Option<Graduation>) -> String {
+    match graduation {
+        None => "-".to_string(),
+        Some(Graduation {
+            target: GraduationTarget::NativeReserve(native_reserve),
+            token_supply,
+        }) => format!("native:{native_reserve}:{token_supply}"),
+        Some(Graduation {
+            target: GraduationTarget::TokensSoldBps(bps),
+            token_supply,
+        }) => format!("sold:{bps}:{token_supply}"),
+    }
+}
+
+#[cfg(feature = "std")]
+fn decode_graduation(field: &str) -> Option<Option<Graduation>> {
+    if field == "-" {
+        return Some(None);
+    }
+    let mut parts = field.split(':');
+    let (kind, target, token_supply) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let target = match kind {
+        "native" => GraduationTarget::NativeReserve(target.parse().ok()?),
+        "sold" => GraduationTarget::TokensSoldBps(target.parse().ok()?),
+        _ => return None,
+    };
+    Some(Some(Graduation {
+        target,
+        token_supply: token_supply.parse().ok()?,
+    }))
+}
+
+/// Encodes a rounding policy as a single field, `<buy>:<sell>:<fee>` with each `down` or `up`.
+#[cfg(feature = "std")]
+fn encode_rounding(policy: RoundingPolicy) -> String {
+    let name = |rounding| match rounding {
+        Rounding::Down => "down",
+        Rounding::Up => "up",
+    };
+    format!(
+        "{}:{}:{}",
+        name(policy.buy),
+        name(policy.sell),
+        name(policy.fee)
+    )
+}
+
+#[cfg(feature = "std")]
+fn decode_rounding(field: &str) -> Option<RoundingPolicy> {
+    let parse = |name: &str| match name {
+        "down" => Some(Rounding::Down),
+        "up" => Some(Rounding::Up),
+        _ => None,
+    };
+    let mut parts = field.split(':');
+    let policy = RoundingPolicy {
+        buy: parse(parts.next()?)?,
+        sell: parse(parts.next()?)?,
+        fee: parse(parts.next()?)?,
+    };
+    parts.next().is_none().then_some(policy)
+}
+
+impl From<LiquidityPool> for PoolSnapshot {
+    fn from(pool: LiquidityPool) -> Self {
+        Self::of(&pool)
+    }
+}
+
+/// Rebuilds a pool from a snapshot of unknown origin, such as a deserialized one, rejecting
+/// states no pool can be in.
+impl TryFrom<PoolSnapshot> for LiquidityPool {
+    type Error = PoolError;
+
+    fn try_from(snapshot: PoolSnapshot) -> Result<Self, PoolError> {
+        if snapshot.initial_token_reserve == 0
+            || snapshot.fee_bps >= MAX_FEE_BPS
+            || snapshot.locked_liquidity > snapshot.lp_supply
+            || snapshot.virtual_native > snapshot.native_reserve
+            || snapshot.virtual_token > snapshot.token_reserve
+            || snapshot
+                .graduation
+                .is_some_and(|graduation| !graduation.target.is_valid())
+            || snapshot.native_decimals > MAX_DECIMALS
+            || snapshot.token_decimals > MAX_DECIMALS
+            || snapshot.protocol_fee_share_bps > MAX_FEE_BPS
+            || !snapshot.token_tax.is_valid()
+        {
+            return Err(PoolError::InvalidAmount);
+        }
+        let pool = snapshot.to_pool();
+        pool.check_invariants()?;
+        Ok(pool)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for LiquidityPool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PoolSnapshot::of(self).serialize(serializer)
+    }
+}
+
+/// Decodes a [`PoolSnapshot`], rejecting states no pool can be in.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LiquidityPool {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = PoolSnapshot::deserialize(deserializer)?;
+        LiquidityPool::try_from(snapshot).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for LiquidityPool {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        PoolSnapshot::of(self).serialize(writer)
+    }
+}
+
+/// Decodes the [`PoolSnapshot`] layout, rejecting states no pool can be in.
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for LiquidityPool {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        use alloc::string::ToString;
+
+        let snapshot = PoolSnapshot::deserialize_reader(reader)?;
+        LiquidityPool::try_from(snapshot).map_err(|err| {
+            borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, err.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate() {
+        let mut pool = LiquidityPool::default();
+        let from = PoolSnapshot::of(&pool);
+        pool.buy(300_000_000 * 10u64.pow(6), None).unwrap();
+        let to = PoolSnapshot::of(&pool);
+
+        let states = from.interpolate(&to, 3);
+        assert_eq!(states.len(), 3);
+        let step = (to.spot_price() - from.spot_price()) / 4.0;
+        for (index, state) in states.iter().enumerate() {
+            let expected = from.spot_price() + step * (index + 1) as f64;
+            assert!((state.spot_price() - expected).abs() / expected < 1e-9);
+            let drift = state.constant_product().abs_diff(from.constant_product());
+            assert!((drift as f64) / (from.constant_product() as f64) < 1e-9);
+        }
+        assert!(from.interpolate(&to, 0).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        pool.buy(1_000_000 * 10u64.pow(6), None).unwrap();
+        let saved = pool.snapshot();
+        pool.sell(500_000 * 10u64.pow(6), None).unwrap();
+        pool.add_liquidity(10u64.pow(9), u64::MAX).unwrap();
+
+        pool.restore(&saved).unwrap();
+        assert_eq!(pool.snapshot(), saved);
+        assert!(pool.history().is_empty());
+        assert_eq!(pool.state_at(saved.trade_count()), Ok(saved));
+
+        let mut corrupt = saved;
+        corrupt.native_reserve /= 2;
+        assert_eq!(pool.restore(&corrupt), Err(PoolError::InvariantViolated));
+        assert_eq!(pool.snapshot(), saved);
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_layout() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        pool.buy(300_000_000 * 10u64.pow(6), None).unwrap();
+        pool.add_liquidity(10u64.pow(9), u64::MAX).unwrap();
+
+        let bytes = borsh::to_vec(&pool).unwrap();
+        assert_eq!(bytes.len(), 143);
+        assert_eq!(bytes[0], 2);
+        assert_eq!(bytes[9..17], pool.get_native_reserve().to_le_bytes());
+        assert_eq!(bytes[25..41], pool.get_constant_product().to_le_bytes());
+        assert_eq!(bytes[65..67], 30u16.to_le_bytes());
+        let restored: LiquidityPool = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(PoolSnapshot::of(&restored), PoolSnapshot::of(&pool));
+
+        let mut drained = bytes.clone();
+        drained[9..17].copy_from_slice(&1u64.to_le_bytes());
+        assert!(borsh::from_slice::<LiquidityPool>(&drained).is_err());
+        let mut unknown = bytes;
+        unknown[0] = 3;
+        assert!(borsh::from_slice::<LiquidityPool>(&unknown).is_err());
+
+        // A 1_000_000 / 1_000_000 pool with a 30 bps fee after buying 100_000 tokens, as the
+        // first release encoded it, before layouts carried a version byte.
+        let legacy = "40420f000000000047f4100000000000a0bb0d00000000000010a5d4e800000000000000000000\
+                      00010000000000000055b301000000000000000000000000001e004e0100000000000000000000\
+                      0000000040420f0000000000e803000000000000";
+        let legacy: Vec<u8> = (0..legacy.len())
+            .step_by(2)
+            .map(|at| u8::from_str_radix(&legacy[at..at + 2], 16).unwrap())
+            .collect();
+        assert_eq!(legacy.len(), 98);
+        let snapshot = PoolSnapshot::deserialize_unversioned(&mut legacy.as_slice()).unwrap();
+        assert_eq!(
+            (snapshot.native_reserve(), snapshot.token_reserve()),
+            (1_111_111, 900_000)
+        );
+        assert_eq!((snapshot.fee_bps(), snapshot.fees_collected()), (30, 334));
+        assert_eq!(snapshot.protocol_fee_share_bps, MAX_FEE_BPS);
+        let tagged = [&[1][..], &legacy].concat();
+        let pool: LiquidityPool = borsh::from_slice(&tagged).unwrap();
+        assert_eq!(pool.snapshot(), snapshot);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        pool.buy(300_000_000 * 10u64.pow(6), None).unwrap();
+        pool.add_liquidity(10u64.pow(9), u64::MAX).unwrap();
+        pool.sell(10u64.pow(12), None).unwrap();
+        pool.reanchor();
+
+        let json = serde_json::to_string(&pool).unwrap();
+        let restored: LiquidityPool = serde_json::from_str(&json).unwrap();
+        assert_eq!(PoolSnapshot::of(&restored), PoolSnapshot::of(&pool));
+        assert!(pool.get_constant_product() > u64::MAX as u128);
+
+        let drained = json.replace(
+            &format!("\"native_reserve\":{}", pool.get_native_reserve()),
+            "\"native_reserve\":1",
+        );
+        assert!(serde_json::from_str::<LiquidityPool>(&drained).is_err());
+        // Every field is required, with no defaults for earlier layouts.
+        let mut fields: serde_json::Value = serde_json::from_str(&json).unwrap();
+        fields
+            .as_object_mut()
+            .unwrap()
+            .remove("protocol_fee_share_bps");
+        assert!(serde_json::from_value::<LiquidityPool>(fields).is_err());
+    }
+}
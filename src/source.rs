@@ -0,0 +1,35 @@
+use crate::{LiquidityPool, Result};
+
+/// A provider of up-to-date pool state, such as an RPC client or an indexer.
+pub trait PoolSource {
+    /// Fetches the current state of the pool.
+    fn fetch_pool(&self) -> Result<LiquidityPool>;
+}
+
+impl<F> PoolSource for F
+where
+    F: Fn() -> Result<LiquidityPool>,
+{
+    fn fetch_pool(&self) -> Result<LiquidityPool> {
+        self()
+    }
+}
+
+/// An asynchronous [`PoolSource`], such as an async RPC client, used by
+/// [`AsyncPool::refresh`](crate::AsyncPool::refresh).
+#[cfg(feature = "async")]
+pub trait AsyncPoolSource {
+    /// Fetches the current state of the pool.
+    fn fetch_pool(&self) -> impl core::future::Future<Output = Result<LiquidityPool>> + Send;
+}
+
+#[cfg(feature = "async")]
+impl<F, Fut> AsyncPoolSource for F
+where
+    F: Fn() -> Fut,
+    Fut: core::future::Future<Output = Result<LiquidityPool>> + Send,
+{
+    fn fetch_pool(&self) -> impl core::future::Future<Output = Result<LiquidityPool>> + Send {
+        self()
+    }
+}
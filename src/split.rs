@@ -0,0 +1,231 @@
+//! Splitting an order across parallel constant product pools of the same pair.
+//!
+//! The marginal cost of buying `t` tokens from reserves `(x, y)` is `(1 + f) * x * y / (y - t)^2`
+//! and the marginal proceeds of selling `s` tokens `(1 - f) * x * y / (y + s)^2`. An order costs
+//! least, or returns most, when every pool it trades with ends at the same marginal price, so
+//! each pool's share is `y - sqrt(c) * m` for buys and `sqrt(c) * m - y` for sells, with
+//! `c = (1 ± f) * x * y` and `m` set by the order size. Pools whose share would be negative are
+//! left out, and the units lost to rounding go to the pools quoting them best.
+
+use crate::{Direction, LiquidityPool, PoolError, Quote, Result, MAX_FEE_BPS};
+
+/// Order split across pools, as returned by [`quote_split`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitQuote {
+    pub direction: Direction,
+    /// Tokens traded with each pool, in the order of the pools.
+    pub allocation: Vec<u64>,
+    /// Quote of each pool receiving a share, `None` for the others.
+    pub quotes: Vec<Option<Quote>>,
+    /// Native paid, fee included (buy) or received net of the fee (sell), across all pools.
+    pub native_amount: u64,
+    /// Swap fees across all pools.
+    pub fee: u64,
+    /// Native amount of the order per token, fee included.
+    pub execution_price: f64,
+}
+
+/// Returns how many of `token_amount` tokens to trade in `direction` with each of `pools` so
+/// that the order costs least (buy) or returns most (sell). Fails with
+/// [`PoolError::InvalidAmount`] for an empty order or no pools, and like the trades
+/// themselves if the pools cannot fill it.
+pub fn split_order(
+    pools: &[LiquidityPool],
+    direction: Direction,
+    token_amount: u64,
+) -> Result<Vec<u64>> {
+    if pools.is_empty() || token_amount == 0 {
+        return Err(PoolError::InvalidAmount);
+    }
+    let curve = |pool: &LiquidityPool| {
+        let fee = pool.fee_bps() as f64 / MAX_FEE_BPS as f64;
+        let scale = match direction {
+            Direction::BuyToken => 1.0 + fee,
+            Direction::SellToken => 1.0 - fee,
+        };
+        let (native, token) = (
+            pool.get_native_reserve() as f64,
+            pool.get_token_reserve() as f64,
+        );
+        ((scale * native * token).sqrt(), token)
+    };
+    let curves: Vec<(f64, f64)> = pools.iter().map(curve).collect();
+    let share = |(root, token): (f64, f64), level: f64| match direction {
+        Direction::BuyToken => token - root * level,
+        Direction::SellToken => root * level - token,
+    };
+
+    // Drop pools whose share is negative until every remaining one trades.
+    let mut active = vec![true; pools.len()];
+    let level = loop {
+        let (roots, tokens) = curves
+            .iter()
+            .zip(&active)
+            .filter(|(_, &active)| active)
+            .fold((0.0, 0.0), |(roots, tokens), ((root, token), _)| {
+                (roots + root, tokens + token)
+            });
+        let level = match direction {
+            Direction::BuyToken => (tokens - token_amount as f64) / roots,
+            Direction::SellToken => (tokens + token_amount as f64) / roots,
+        };
+        let mut changed = false;
+        for (curve, active) in curves.iter().zip(active.iter_mut()) {
+            if *active && share(*curve, level) < 0.0 {
+                *active = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            break level;
+        }
+    };
+
+    let mut allocation: Vec<u64> = curves
+        .iter()
+        .zip(&active)
+        .map(|(&curve, &active)| {
+            if active {
+                (share(curve, level).max(0.0) as u64).min(token_amount)
+            } else {
+                0
+            }
+        })
+        .collect();
+    // Floating-point error may overshoot the order by a few units; take them back from the
+    // largest shares, then hand out what rounding left to the pools quoting each unit best.
+    while allocation.iter().sum::<u64>() > token_amount {
+        let largest = (0..allocation.len())
+            .max_by_key(|&index| allocation[index])
+            .expect("pools are not empty");
+        allocation[largest] -= 1;
+    }
+    for _ in allocation.iter().sum::<u64>()..token_amount {
+        let (best, _) = pools
+            .iter()
+            .zip(&allocation)
+            .enumerate()
+            .filter_map(|(index, (pool, &amount))| {
+                let native = |amount| native_amount(pool, direction, amount);
+                Some((index, native(amount + 1)? - native(amount)?))
+            })
+            .min_by_key(|&(_, step)| match direction {
+                Direction::BuyToken => step,
+                Direction::SellToken => -step,
+            })
+            .ok_or(PoolError::InsufficientPoolFunds {
+                requested: token_amount,
+                available: allocation.iter().sum(),
+            })?;
+        allocation[best] += 1;
+    }
+    Ok(allocation)
+}
+
+/// Splits `token_amount` tokens across `pools` like [`split_order`] and quotes the whole order.
+pub fn quote_split(
+    pools: &[LiquidityPool],
+    direction: Direction,
+    token_amount: u64,
+) -> Result<SplitQuote> {
+    let allocation = split_order(pools, direction, token_amount)?;
+    let quotes = pools
+        .iter()
+        .zip(&allocation)
+        .map(|(pool, &amount)| match (amount, direction) {
+            (0, _) => Ok(None),
+            (amount, Direction::BuyToken) => pool.quote_buy(amount).map(Some),
+            (amount, Direction::SellToken) => pool.quote_sell(amount).map(Some),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (native_amount, fee) =
+        quotes
+            .iter()
+            .flatten()
+            .fold((0u64, 0u64), |(native, fee), quote| {
+                let quoted = match direction {
+                    Direction::BuyToken => quote.amount_in,
+                    Direction::SellToken => quote.amount_out,
+                };
+                (native.saturating_add(quoted), fee.saturating_add(quote.fee))
+            });
+    Ok(SplitQuote {
+        direction,
+        allocation,
+        quotes,
+        native_amount,
+        fee,
+        execution_price: native_amount as f64 / token_amount as f64,
+    })
+}
+
+/// Native paid (buy) or received (sell) for `token_amount` tokens, fee included, as a signed
+/// amount so marginal steps can be compared.
+fn native_amount(pool: &LiquidityPool, direction: Direction, token_amount: u64) -> Option<i128> {
+    let native = match (token_amount, direction) {
+        (0, _) => 0,
+        (amount, Direction::BuyToken) => pool.quote_buy(amount).ok()?.amount_in,
+        (amount, Direction::SellToken) => pool.quote_sell(amount).ok()?.amount_out,
+    };
+    Some(native as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pools() -> Vec<LiquidityPool> {
+        vec![
+            LiquidityPool::new(10u64.pow(12), 10u64.pow(12)).unwrap(),
+            LiquidityPool::new(4 * 10u64.pow(11), 4 * 10u64.pow(11))
+                .unwrap()
+                .with_fee(30)
+                .unwrap(),
+            // Too expensive to receive any share of a small buy.
+            LiquidityPool::new(2 * 10u64.pow(12), 10u64.pow(12)).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_split_buy() {
+        let pools = pools();
+        let token_amount = 10u64.pow(11);
+        let split = quote_split(&pools, Direction::BuyToken, token_amount).unwrap();
+        assert_eq!(split.allocation.iter().sum::<u64>(), token_amount);
+        assert_eq!(split.allocation[2], 0);
+        assert!(split.quotes[2].is_none());
+
+        // Moving tokens between the two used pools only raises the cost.
+        let cost = |first: u64| {
+            pools[0].quote_buy(first).unwrap().amount_in
+                + pools[1].quote_buy(token_amount - first).unwrap().amount_in
+        };
+        assert_eq!(cost(split.allocation[0]), split.native_amount);
+        for offset in [1, 10u64.pow(6)] {
+            assert!(cost(split.allocation[0] - offset) >= split.native_amount);
+            assert!(cost(split.allocation[0] + offset) >= split.native_amount);
+        }
+        assert!(split.native_amount < pools[0].quote_buy(token_amount).unwrap().amount_in);
+    }
+
+    #[test]
+    fn test_split_sell() {
+        let pools = pools();
+        let token_amount = 10u64.pow(12);
+        let split = quote_split(&pools, Direction::SellToken, token_amount).unwrap();
+        assert_eq!(split.allocation.iter().sum::<u64>(), token_amount);
+        assert!(split.allocation.iter().all(|&amount| amount > 0));
+        let best_single = pools
+            .iter()
+            .filter_map(|pool| pool.quote_sell(token_amount).ok())
+            .map(|quote| quote.amount_out)
+            .max()
+            .unwrap();
+        assert!(split.native_amount > best_single);
+        assert_eq!(
+            split_order(&[], Direction::SellToken, 1),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::curve::mul_div;
+use crate::{Curve, CurveState, Direction, PoolError};
+
+/// Newton iterations allowed before a solver gives up, as in Curve's contracts.
+const MAX_ITERATIONS: usize = 255;
+
+/// The two-coin StableSwap invariant of Curve pools,
+/// `A·n^n·(x + y) + D = A·n^n·D + D^(n+1) / (n^n·x·y)` with `n = 2`, for assets meant to trade
+/// near parity.
+///
+/// `amp` is the amplification coefficient `A` as set on Curve pools: the higher it is, the
+/// closer the curve stays to constant sum around the balance point before bending toward
+/// constant product. `D` and the reserve left by a trade are solved by Newton iteration, and
+/// every trade rounds one unit in favour of the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StableSwap {
+    pub amp: u64,
+}
+
+impl StableSwap {
+    /// `A·n`, the factor Curve's solvers are written in.
+    fn ann(&self) -> Result<u128, PoolError> {
+        match self.amp {
+            0 => Err(PoolError::InvalidAmount),
+            amp => Ok(amp as u128 * 2),
+        }
+    }
+
+    /// Solves `D` for the reserves `x` and `y`.
+    fn solve_d(&self, x: u128, y: u128) -> Result<u128, PoolError> {
+        let ann = self.ann()?;
+        let sum = x + y;
+        let mut d = sum;
+        for _ in 0..MAX_ITERATIONS {
+            // D^3 / (4·x·y), one reserve at a time.
+            let d_p = mul_div(d, d, x * 2)
+                .and_then(|d_p| mul_div(d_p, d, y * 2))
+                .ok_or(PoolError::Overflow)?;
+            let previous = d;
+            let numerator = (ann * sum)
+                .checked_add(d_p * 2)
+                .ok_or(PoolError::Overflow)?;
+            let denominator = ((ann - 1) * d)
+                .checked_add(d_p * 3)
+                .ok_or(PoolError::Overflow)?;
+            d = mul_div(numerator, d, denominator).ok_or(PoolError::Overflow)?;
+            if d.abs_diff(previous) <= 1 {
+                return Ok(d);
+            }
+        }
+        Err(PoolError::Overflow)
+    }
+
+    /// Solves the reserve paired with `x` on the curve of `d`.
+    fn solve_y(&self, x: u128, d: u128) -> Result<u128, PoolError> {
+        let ann = self.ann()?;
+        let c = mul_div(d, d, x * 2)
+            .and_then(|c| mul_div(c, d, ann * 2))
+            .ok_or(PoolError::Overflow)?;
+        let b = x + d / ann;
+        let mut y = d;
+        for _ in 0..MAX_ITERATIONS {
+            let previous = y;
+            let numerator = y
+                .checked_mul(y)
+                .and_then(|square| square.checked_add(c))
+                .ok_or(PoolError::Overflow)?;
+            let denominator = (2 * y + b).checked_sub(d).ok_or(PoolError::Overflow)?;
+            y = numerator / denominator;
+            if y.abs_diff(previous) <= 1 {
+                return Ok(y);
+            }
+        }
+        Err(PoolError::Overflow)
+    }
+}
+
+impl Curve for StableSwap {
+    fn invariant(&self, native_reserve: u64, token_reserve: u64) -> Result<u128, PoolError> {
+        if native_reserve == 0 || token_reserve == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.solve_d(native_reserve as u128, token_reserve as u128)
+    }
+
+    fn amount_out(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_in: u64,
+    ) -> Result<u64, PoolError> {
+        let (reserve_in, reserve_out) = match direction {
+            Direction::BuyToken => (state.native_reserve, state.token_reserve),
+            Direction::SellToken => (state.token_reserve, state.native_reserve),
+        };
+        let new_reserve_in = reserve_in as u128 + amount_in as u128;
+        let new_reserve_out = self.solve_y(new_reserve_in, state.invariant)?;
+        Ok((reserve_out as u128).saturating_sub(new_reserve_out + 1) as u64)
+    }
+
+    fn amount_in(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_out: u64,
+    ) -> Result<u64, PoolError> {
+        let (reserve_in, reserve_out) = match direction {
+            Direction::BuyToken => (state.native_reserve, state.token_reserve),
+            Direction::SellToken => (state.token_reserve, state.native_reserve),
+        };
+        let new_reserve_out = reserve_out
+            .checked_sub(amount_out)
+            .filter(|&reserve| reserve > 0)
+            .ok_or(PoolError::InsufficientPoolFunds {
+                requested: amount_out,
+                available: reserve_out,
+            })?;
+        let new_reserve_in = self.solve_y(new_reserve_out as u128, state.invariant)?;
+        u64::try_from((new_reserve_in + 1).saturating_sub(reserve_in as u128))
+            .map_err(|_| PoolError::Overflow)
+    }
+
+    /// Returns `-dx/dy` along the curve through the reserves, which reduces to `x / y` as `A`
+    /// approaches zero and to 1 at the balance point.
+    fn spot_price(&self, state: &CurveState) -> f64 {
+        let (x, y) = (state.native_reserve as f64, state.token_reserve as f64);
+        let ann = self.amp as f64 * 2.0;
+        let d = state.invariant as f64;
+        let d_cubed = d * d * d / 4.0;
+        (ann + d_cubed / (x * y * y)) / (ann + d_cubed / (x * x * y))
+    }
+
+    /// Newton iteration solves `D` to within one unit, so the reserves lie on the curve when
+    /// their `D` is within that error plus the unit each trade rounds by, scaled by how
+    /// steep the curve is at the reserves.
+    fn on_curve(&self, state: &CurveState) -> bool {
+        let (low, high) = if state.native_reserve < state.token_reserve {
+            (state.native_reserve, state.token_reserve)
+        } else {
+            (state.token_reserve, state.native_reserve)
+        };
+        self.invariant(state.native_reserve, state.token_reserve)
+            .is_ok_and(|d| d.abs_diff(state.invariant) <= 2 * ((high / low) as u128 + 2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityPool;
+
+    #[test]
+    fn test_stable_swap_trades_near_parity() {
+        let reserve = 1_000_000 * 10u64.pow(6);
+        let mut pool =
+            LiquidityPool::with_curve(StableSwap { amp: 100 }, reserve, reserve).unwrap();
+        let mut product = LiquidityPool::new(reserve, reserve).unwrap();
+        assert_eq!(pool.get_constant_product(), 2 * reserve as u128);
+        assert!((pool.spot_price() - 1.0).abs() < 1e-12);
+
+        let token_amount = 100_000 * 10u64.pow(6);
+        let cost = pool.buy(token_amount, None).unwrap();
+        assert!(cost > token_amount && cost < token_amount + token_amount / 500);
+        assert!(cost < product.buy(token_amount, None).unwrap());
+        assert!(pool.spot_price() > 1.0);
+        assert_eq!(pool.check_invariants(), Ok(()));
+
+        let received = pool.sell(token_amount, None).unwrap();
+        assert!(received < cost && cost - received <= 2);
+        assert_eq!(pool.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_stable_swap_bends_toward_constant_product() {
+        let reserve = 1_000 * 10u64.pow(6);
+        let pool = LiquidityPool::with_curve(StableSwap { amp: 10 }, reserve, reserve).unwrap();
+        // Draining most of the tokens costs far more than parity.
+        let quote = pool.quote_buy(reserve - reserve / 100).unwrap();
+        assert!(quote.amount_in > 3 * reserve);
+        assert_eq!(
+            pool.quote_buy(reserve).map(|_| ()),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: reserve,
+                available: reserve
+            })
+        );
+        assert_eq!(
+            LiquidityPool::with_curve(StableSwap { amp: 0 }, reserve, reserve).map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
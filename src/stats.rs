@@ -0,0 +1,99 @@
+use crate::{Curve, LiquidityPool, Projection, TradeOp};
+
+/// Trade statistics accumulated since the pool was created or since the last
+/// [`reset_stats`](LiquidityPool::reset_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolStats {
+    pub buys: u64,
+    pub sells: u64,
+    /// Tokens bought.
+    pub token_bought: u128,
+    /// Native paid by buyers, fee included.
+    pub native_spent: u128,
+    /// Tokens sold.
+    pub token_sold: u128,
+    /// Native received by sellers, net of the fee.
+    pub native_received: u128,
+    /// Largest native amount of a single trade, fee included.
+    pub largest_trade: u64,
+    /// Swap fees charged.
+    pub fees: u128,
+}
+
+impl PoolStats {
+    pub fn trade_count(&self) -> u64 {
+        self.buys + self.sells
+    }
+
+    /// Native spent and received across all trades.
+    pub fn native_volume(&self) -> u128 {
+        self.native_spent + self.native_received
+    }
+
+    /// Tokens bought and sold across all trades.
+    pub fn token_volume(&self) -> u128 {
+        self.token_bought + self.token_sold
+    }
+
+    pub(crate) fn record(&mut self, projection: &Projection) {
+        let token_amount = projection.op.token_amount() as u128;
+        let native_amount = projection.native_amount as u128;
+        match projection.op {
+            TradeOp::Buy { .. } => {
+                self.buys += 1;
+                self.token_bought += token_amount;
+                self.native_spent += native_amount;
+            }
+            TradeOp::Sell { .. } => {
+                self.sells += 1;
+                self.token_sold += token_amount;
+                self.native_received += native_amount;
+            }
+        }
+        self.largest_trade = self.largest_trade.max(projection.native_amount);
+        self.fees += projection.fee as u128;
+    }
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Returns the trade statistics, which unlike [`trade_count`](Self::trade_count) and
+    /// [`native_volume`](Self::native_volume) can be reset and are not persisted.
+    pub fn stats(&self) -> &PoolStats {
+        &self.stats
+    }
+
+    /// Clears the trade statistics, returning them.
+    pub fn reset_stats(&mut self) -> PoolStats {
+        core::mem::take(&mut self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        let token_amount = 10u64.pow(12);
+        let paid = pool.buy(token_amount, None).unwrap();
+        let received = pool.sell(token_amount / 2, None).unwrap();
+
+        let stats = *pool.stats();
+        assert_eq!((stats.buys, stats.sells), (1, 1));
+        assert_eq!(
+            stats.token_volume(),
+            (token_amount + token_amount / 2) as u128
+        );
+        assert_eq!(stats.native_spent, paid as u128);
+        assert_eq!(stats.native_received, received as u128);
+        assert_eq!(stats.largest_trade, paid);
+        assert_eq!(stats.fees, pool.fees_collected());
+        assert_eq!(stats.native_volume(), pool.native_volume());
+
+        assert_eq!(pool.reset_stats(), stats);
+        assert_eq!(*pool.stats(), PoolStats::default());
+        assert_eq!(pool.trade_count(), 2);
+    }
+}
@@ -0,0 +1,68 @@
+//! [`proptest`] strategies generating pools and trade sequences that stay within safe numeric
+//! ranges, for writing property tests against pool behavior.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::{LiquidityPool, TradeOp};
+
+/// Smallest reserve generated on either side of a pool.
+pub const MIN_RESERVE: u64 = 1_000;
+/// Largest native reserve generated (one million units with 9 decimals).
+pub const MAX_NATIVE_RESERVE: u64 = 10u64.pow(15);
+/// Largest token reserve generated (one trillion units with 6 decimals).
+pub const MAX_TOKEN_RESERVE: u64 = 10u64.pow(18);
+
+/// Generates a pool with both reserves in `MIN_RESERVE..=MAX_*_RESERVE`.
+pub fn pool() -> impl Strategy<Value = LiquidityPool> {
+    (
+        MIN_RESERVE..=MAX_NATIVE_RESERVE,
+        MIN_RESERVE..=MAX_TOKEN_RESERVE,
+    )
+        .prop_map(|(native_reserve, token_reserve)| {
+            LiquidityPool::new(native_reserve, token_reserve).expect("reserves are non-zero")
+        })
+}
+
+/// Generates a buy or sell of between 1 and `max_token_amount` tokens, without slippage limits.
+pub fn trade_op(max_token_amount: u64) -> impl Strategy<Value = TradeOp> {
+    (any::<bool>(), 1..=max_token_amount.max(1)).prop_map(|(is_buy, token_amount)| {
+        if is_buy {
+            TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            }
+        } else {
+            TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            }
+        }
+    })
+}
+
+/// Generates a pool together with up to `max_len` trades that can all be executed in order.
+///
+/// Each trade moves at most `1 / (2 * max_len)` of the initial token reserve, so the token
+/// reserve never drops below half its initial value and the native reserve at most doubles.
+pub fn pool_with_trades(max_len: usize) -> impl Strategy<Value = (LiquidityPool, Vec<TradeOp>)> {
+    pool().prop_flat_map(move |pool| {
+        let max_token_amount = pool.get_token_reserve() / (2 * max_len.max(1) as u64);
+        let ops = vec(trade_op(max_token_amount), 0..=max_len);
+        (Just(pool), ops)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_generated_trades_execute((mut pool, ops) in pool_with_trades(32)) {
+            for op in &ops {
+                prop_assert!(pool.execute(op).is_ok());
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Predefined adversarial trade sequences, run as a robustness gate.
+//!
+//! Each [`StressScenario`] derives its trades from the pool it is run against, applies them to
+//! a copy, and reports every rejected trade along with any [`StressFinding`]: a panic, a broken
+//! invariant, or a rejected trade that still changed the pool.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{LiquidityPool, PoolError, PoolSnapshot, TradeOp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StressScenario {
+    /// Buys and sells sized to the whole token reserve and to the limits of `u64`.
+    MaxSizeTrades,
+    /// Repeatedly buys half the token reserve and sells it straight back.
+    PumpDump { rounds: usize },
+    /// Drains the token reserve down to a single unit and trades against what is left.
+    NearEmptyReserves,
+    /// Zero, one and `u64::MAX` amounts and slippage limits.
+    U64Boundaries,
+}
+
+/// What went wrong at a step of a stress scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StressFailure {
+    Panicked,
+    /// The trade left the pool in a state failing [`LiquidityPool::check_invariants`], or was
+    /// rejected with [`PoolError::InvariantViolated`].
+    InvariantViolated,
+    /// The trade was rejected but the pool state changed anyway.
+    MutatedOnError(PoolError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StressFinding {
+    pub step: usize,
+    pub op: TradeOp,
+    pub failure: StressFailure,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StressReport {
+    pub scenario: StressScenario,
+    pub trades: usize,
+    /// Trades the pool rejected, with their step index.
+    pub rejected: Vec<(usize, PoolError)>,
+    pub findings: Vec<StressFinding>,
+}
+
+impl StressReport {
+    /// Returns `true` if no panic, invariant violation or partial mutation was found.
+    pub fn passed(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl StressScenario {
+    /// Every scenario, with pump/dump cycles run 50 times.
+    pub const ALL: [StressScenario; 4] = [
+        StressScenario::MaxSizeTrades,
+        StressScenario::PumpDump { rounds: 50 },
+        StressScenario::NearEmptyReserves,
+        StressScenario::U64Boundaries,
+    ];
+
+    /// Returns the trades this scenario applies to `pool`.
+    pub fn ops(&self, pool: &LiquidityPool) -> Vec<TradeOp> {
+        let token_reserve = pool.get_token_reserve();
+        let buy = |token_amount| TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        };
+        let sell = |token_amount| TradeOp::Sell {
+            token_amount,
+            min_native: None,
+        };
+        match *self {
+            StressScenario::MaxSizeTrades => vec![
+                buy(token_reserve - 1),
+                sell(token_reserve - 1),
+                buy(token_reserve),
+                sell(u64::MAX - token_reserve),
+                sell(u64::MAX),
+                buy(u64::MAX),
+            ],
+            StressScenario::PumpDump { rounds } => {
+                let half = (token_reserve / 2).max(1);
+                (0..rounds).flat_map(|_| [buy(half), sell(half)]).collect()
+            }
+            StressScenario::NearEmptyReserves => vec![
+                buy(token_reserve - 1),
+                buy(1),
+                sell(1),
+                buy(1),
+                sell(1),
+                sell(token_reserve - 1),
+            ],
+            StressScenario::U64Boundaries => [0, 1, u64::MAX - 1, u64::MAX]
+                .into_iter()
+                .flat_map(|token_amount| {
+                    [None, Some(0), Some(1), Some(u64::MAX)]
+                        .into_iter()
+                        .flat_map(move |limit| {
+                            [
+                                TradeOp::Buy {
+                                    token_amount,
+                                    max_native: limit,
+                                },
+                                TradeOp::Sell {
+                                    token_amount,
+                                    min_native: limit,
+                                },
+                            ]
+                        })
+                })
+                .collect(),
+        }
+    }
+
+    /// Runs the scenario against a copy of `pool`, continuing past failed trades.
+    pub fn run(&self, pool: &LiquidityPool) -> StressReport {
+        let mut pool = pool.clone();
+        let ops = self.ops(&pool);
+        let mut report = StressReport {
+            scenario: *self,
+            trades: ops.len(),
+            rejected: Vec::new(),
+            findings: Vec::new(),
+        };
+        for (step, op) in ops.into_iter().enumerate() {
+            let before = PoolSnapshot::of(&pool);
+            let failure = match panic::catch_unwind(AssertUnwindSafe(|| pool.execute(&op))) {
+                Err(_) => {
+                    // The pool may be half-updated; carry on from the last known good state.
+                    pool = before.to_pool();
+                    Some(StressFailure::Panicked)
+                }
+                Ok(Ok(_)) => pool
+                    .check_invariants()
+                    .err()
+                    .map(|_| StressFailure::InvariantViolated),
+                Ok(Err(err)) => {
+                    report.rejected.push((step, err));
+                    if err == PoolError::InvariantViolated {
+                        Some(StressFailure::InvariantViolated)
+                    } else if PoolSnapshot::of(&pool) != before {
+                        Some(StressFailure::MutatedOnError(err))
+                    } else {
+                        None
+                    }
+                }
+            };
+            if let Some(failure) = failure {
+                report.findings.push(StressFinding { step, op, failure });
+            }
+        }
+        report
+    }
+}
+
+/// Runs every scenario in [`StressScenario::ALL`] against `pool`.
+pub fn run_all(pool: &LiquidityPool) -> Vec<StressReport> {
+    StressScenario::ALL
+        .iter()
+        .map(|scenario| scenario.run(pool))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_pool_passes() {
+        let pool = LiquidityPool::new(1_000, 1_000).unwrap();
+        for report in run_all(&pool) {
+            assert!(report.passed(), "{report:?}");
+        }
+        let report = StressScenario::U64Boundaries.run(&pool);
+        assert!(report.rejected.contains(&(0, PoolError::InvalidAmount)));
+    }
+
+    #[test]
+    fn test_rejects_overflowing_drain() {
+        // Draining a deep pool would push the native reserve past `u64::MAX`.
+        let report = StressScenario::NearEmptyReserves.run(&LiquidityPool::default());
+        assert!(report.passed(), "{report:?}");
+        assert_eq!(report.rejected[0], (0, PoolError::Overflow));
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{
+    tax, Curve, Direction, LiquidityPool, PoolError, Projection, Result, TradeOp, MAX_FEE_BPS,
+};
+
+/// Which side of a swap is fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum Amount {
+    /// Exactly this much goes in: native for buys, tokens for sells. Buys spend at most this
+    /// much, as the remainder may not pay for a whole token unit.
+    ExactIn(u64),
+    /// Exactly this much comes out: tokens for buys, native for sells. Sells return at least
+    /// this much, as a whole token unit may pay slightly more.
+    ExactOut(u64),
+}
+
+/// Bound on the side of a swap not fixed by its [`Amount`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum SlippageLimit {
+    #[default]
+    None,
+    /// Minimum output of an [`Amount::ExactIn`] swap.
+    MinOut(u64),
+    /// Maximum input of an [`Amount::ExactOut`] swap.
+    MaxIn(u64),
+}
+
+/// Tolerance around a quoted amount, turned into the bound of a trade by
+/// [`max_in`](Self::max_in) or [`min_out`](Self::min_out).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Slippage {
+    /// No bound.
+    #[default]
+    None,
+    /// Basis points of the quoted amount.
+    Bps(u32),
+    /// Percent of the quoted amount, so `Percent(0.5)` is 0.5%.
+    Percent(f64),
+    /// Units of the quoted amount's asset.
+    Absolute(u64),
+}
+
+impl Slippage {
+    /// Returns the most a trade quoted to take `quoted` may take in, rounded down, or `None`
+    /// without a bound. Fails with [`PoolError::InvalidAmount`] for a negative or non-finite
+    /// percentage.
+    pub fn max_in(self, quoted: u64) -> Result<Option<u64>> {
+        let max_in = match self {
+            Slippage::None => return Ok(None),
+            Slippage::Bps(bps) => {
+                let max_in =
+                    quoted as u128 * (MAX_FEE_BPS as u128 + bps as u128) / MAX_FEE_BPS as u128;
+                u64::try_from(max_in).unwrap_or(u64::MAX)
+            }
+            Slippage::Percent(percent) => quoted.saturating_add(tolerance(quoted, percent)?),
+            Slippage::Absolute(tolerance) => quoted.saturating_add(tolerance),
+        };
+        Ok(Some(max_in))
+    }
+
+    /// Returns the least a trade quoted to pay out `quoted` may pay out, rounded up, or `None`
+    /// without a bound. Fails with [`PoolError::InvalidAmount`] for a negative or non-finite
+    /// percentage.
+    pub fn min_out(self, quoted: u64) -> Result<Option<u64>> {
+        let min_out = match self {
+            Slippage::None => return Ok(None),
+            Slippage::Bps(bps) => {
+                let kept = (MAX_FEE_BPS as u128).saturating_sub(bps as u128);
+                (quoted as u128 * kept).div_ceil(MAX_FEE_BPS as u128) as u64
+            }
+            Slippage::Percent(percent) => quoted.saturating_sub(tolerance(quoted, percent)?),
+            Slippage::Absolute(tolerance) => quoted.saturating_sub(tolerance),
+        };
+        Ok(Some(min_out))
+    }
+}
+
+/// `percent` of `quoted`, rounded down so the bound stays within the tolerance.
+fn tolerance(quoted: u64, percent: f64) -> Result<u64> {
+    if percent.is_finite() && percent >= 0.0 {
+        // Casting saturates.
+        Ok((quoted as f64 * percent / 100.0) as u64)
+    } else {
+        Err(PoolError::InvalidAmount)
+    }
+}
+
+/// A swap request, as accepted by [`LiquidityPool::swap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct SwapParams {
+    pub direction: Direction,
+    pub amount: Amount,
+    pub limit: SlippageLimit,
+}
+
+/// Outcome of a swap, from the trader's point of view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapResult {
+    pub direction: Direction,
+    /// Native paid (buy) or tokens sold, transfer tax included (sell).
+    pub amount_in: u64,
+    /// Tokens received after the transfer tax (buy) or native received (sell).
+    pub amount_out: u64,
+    /// Swap fee included in the native amount.
+    pub fee: u64,
+    /// Tokens taken by the token's transfer tax, see [`TokenTax`](crate::TokenTax).
+    pub token_tax: u64,
+    pub price_before: f64,
+    pub price_after: f64,
+}
+
+impl SwapParams {
+    /// Builds a swap bounded by `slippage` around `quote`, as returned by
+    /// [`LiquidityPool::quote_swap`]: its output for [`Amount::ExactIn`] swaps and its input
+    /// for [`Amount::ExactOut`] ones.
+    pub fn with_slippage(
+        direction: Direction,
+        amount: Amount,
+        quote: &SwapResult,
+        slippage: Slippage,
+    ) -> Result<Self> {
+        let limit = match amount {
+            Amount::ExactIn(_) => slippage
+                .min_out(quote.amount_out)?
+                .map(SlippageLimit::MinOut),
+            Amount::ExactOut(_) => slippage.max_in(quote.amount_in)?.map(SlippageLimit::MaxIn),
+        };
+        Ok(Self {
+            direction,
+            amount,
+            limit: limit.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<&Projection> for SwapResult {
+    fn from(projection: &Projection) -> Self {
+        let token_amount = projection.op.token_amount();
+        let (direction, amount_in, amount_out) = match projection.op {
+            TradeOp::Buy { .. } => (
+                Direction::BuyToken,
+                projection.native_amount,
+                token_amount - projection.token_tax,
+            ),
+            TradeOp::Sell { .. } => (Direction::SellToken, token_amount, projection.native_amount),
+        };
+        Self {
+            direction,
+            amount_in,
+            amount_out,
+            fee: projection.fee,
+            token_tax: projection.token_tax,
+            price_before: projection.price_before,
+            price_after: projection.price_after,
+        }
+    }
+}
+
+impl LiquidityPool {
+    /// Quotes `params` without mutating the pool.
+    pub fn quote_swap(&self, params: &SwapParams) -> Result<SwapResult> {
+        let op = self.swap_op(params)?;
+        self.simulate(&op)
+            .map(|projection| SwapResult::from(&projection))
+    }
+
+    /// Executes `params`, enforcing its slippage limit.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip(self),
+            fields(native_reserve = self.native_reserve, token_reserve = self.token_reserve),
+            err(level = "debug")
+        )
+    )]
+    pub fn swap(&mut self, params: &SwapParams) -> Result<SwapResult> {
+        let op = self.swap_op(params)?;
+        let projection = self.simulate(&op)?;
+        self.commit_trade(&projection)?;
+        Ok(SwapResult::from(&projection))
+    }
+
+    /// Resolves `params` into the equivalent trade, with the limit expressed on its native
+    /// amount where possible.
+    fn swap_op(&self, params: &SwapParams) -> Result<TradeOp> {
+        let (min_out, max_in) = match (params.amount, params.limit) {
+            (_, SlippageLimit::None) => (None, None),
+            (Amount::ExactIn(_), SlippageLimit::MinOut(min_out)) => (Some(min_out), None),
+            (Amount::ExactOut(_), SlippageLimit::MaxIn(max_in)) => (None, Some(max_in)),
+            _ => return Err(PoolError::InvalidAmount),
+        };
+        let op = match (params.direction, params.amount) {
+            (Direction::BuyToken, Amount::ExactIn(native_amount)) => {
+                let spend = self.simulate_spend_native(native_amount)?;
+                let received =
+                    spend.token_amount - tax::tax_on(self.token_tax.buy_bps, spend.token_amount);
+                if let Some(min_out) = min_out.filter(|&min_out| received < min_out) {
+                    return Err(PoolError::SlippageExceeded {
+                        limit: min_out,
+                        actual: received,
+                    });
+                }
+                TradeOp::Buy {
+                    token_amount: spend.token_amount,
+                    max_native: Some(native_amount),
+                }
+            }
+            (Direction::BuyToken, Amount::ExactOut(token_amount)) => TradeOp::Buy {
+                // Enough to receive `token_amount` once the transfer tax is taken.
+                token_amount: tax::gross_for_net(self.token_tax.buy_bps, token_amount)
+                    .ok_or(PoolError::Overflow)?,
+                max_native: max_in,
+            },
+            (Direction::SellToken, Amount::ExactIn(token_amount)) => TradeOp::Sell {
+                token_amount,
+                min_native: min_out,
+            },
+            (Direction::SellToken, Amount::ExactOut(native_amount)) => {
+                let token_amount = tax::gross_for_net(
+                    self.token_tax.sell_bps,
+                    self.calculate_tokens_needed_for_native(native_amount)?,
+                )
+                .ok_or(PoolError::Overflow)?;
+                if let Some(max_in) = max_in.filter(|&max_in| token_amount > max_in) {
+                    return Err(PoolError::SlippageExceeded {
+                        limit: max_in,
+                        actual: token_amount,
+                    });
+                }
+                TradeOp::Sell {
+                    token_amount,
+                    min_native: Some(native_amount),
+                }
+            }
+        };
+        Ok(op)
+    }
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// [`buy`](Self::buy)s `token_amount` tokens quoted at `quoted_native`, spending at most
+    /// what `slippage` allows above the quote.
+    pub fn buy_with_slippage(
+        &mut self,
+        token_amount: u64,
+        quoted_native: u64,
+        slippage: Slippage,
+    ) -> Result<u64> {
+        self.buy(token_amount, slippage.max_in(quoted_native)?)
+    }
+
+    /// [`sell`](Self::sell)s `token_amount` tokens quoted at `quoted_native`, receiving at least
+    /// what `slippage` allows below the quote.
+    pub fn sell_with_slippage(
+        &mut self,
+        token_amount: u64,
+        quoted_native: u64,
+        slippage: Slippage,
+    ) -> Result<u64> {
+        self.sell(token_amount, slippage.min_out(quoted_native)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_modes() {
+        let pool = LiquidityPool::default().with_fee(30).unwrap();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let native_amount = 10u64.pow(6);
+        let cases = [
+            (Direction::BuyToken, Amount::ExactIn(native_amount)),
+            (Direction::BuyToken, Amount::ExactOut(token_amount)),
+            (Direction::SellToken, Amount::ExactIn(token_amount)),
+            (Direction::SellToken, Amount::ExactOut(native_amount)),
+        ];
+        for (direction, amount) in cases {
+            let params = SwapParams {
+                direction,
+                amount,
+                limit: SlippageLimit::None,
+            };
+            let quoted = pool.quote_swap(&params).unwrap();
+            let mut swapped = pool.clone();
+            assert_eq!(swapped.swap(&params).unwrap(), quoted);
+            assert_eq!(swapped.fees_collected(), quoted.fee as u128);
+            match amount {
+                Amount::ExactIn(amount_in) => assert!(quoted.amount_in <= amount_in),
+                Amount::ExactOut(amount_out) => assert!(quoted.amount_out >= amount_out),
+            }
+        }
+    }
+
+    #[test]
+    fn test_swap_limits() {
+        let mut pool = LiquidityPool::default();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let buy = |limit| SwapParams {
+            direction: Direction::BuyToken,
+            amount: Amount::ExactOut(token_amount),
+            limit,
+        };
+        let cost = pool
+            .quote_swap(&buy(SlippageLimit::None))
+            .unwrap()
+            .amount_in;
+        assert_eq!(
+            pool.swap(&buy(SlippageLimit::MaxIn(cost - 1))),
+            Err(PoolError::SlippageExceeded {
+                limit: cost - 1,
+                actual: cost
+            })
+        );
+        assert_eq!(
+            pool.swap(&buy(SlippageLimit::MinOut(token_amount))),
+            Err(PoolError::InvalidAmount)
+        );
+        assert_eq!(pool.trade_count(), 0);
+        assert_eq!(
+            pool.swap(&buy(SlippageLimit::MaxIn(cost)))
+                .unwrap()
+                .amount_in,
+            cost
+        );
+    }
+
+    #[test]
+    fn test_slippage_bounds() {
+        assert_eq!(Slippage::Bps(50).max_in(10_000), Ok(Some(10_050)));
+        assert_eq!(Slippage::Bps(50).min_out(10_001), Ok(Some(9_951)));
+        assert_eq!(Slippage::Bps(20_000).min_out(10_000), Ok(Some(0)));
+        assert_eq!(Slippage::Percent(0.5).max_in(10_000), Ok(Some(10_050)));
+        assert_eq!(Slippage::Percent(0.5).min_out(10_000), Ok(Some(9_950)));
+        assert_eq!(Slippage::Absolute(7).min_out(5), Ok(Some(0)));
+        assert_eq!(Slippage::Absolute(7).max_in(u64::MAX), Ok(Some(u64::MAX)));
+        assert_eq!(Slippage::None.max_in(10_000), Ok(None));
+        assert_eq!(
+            Slippage::Percent(-1.0).max_in(10_000),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn test_trades_with_slippage() {
+        let mut pool = LiquidityPool::default();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        let quoted = pool.simulate_buy(token_amount, None).unwrap();
+        // Someone else buys first, raising the cost about 2%.
+        let mut front_run = pool.clone();
+        front_run.buy(10 * token_amount, None).unwrap();
+        assert!(matches!(
+            front_run.buy_with_slippage(token_amount, quoted, Slippage::Bps(100)),
+            Err(PoolError::SlippageExceeded { .. })
+        ));
+        assert!(front_run
+            .buy_with_slippage(token_amount, quoted, Slippage::Percent(5.0))
+            .is_ok());
+
+        let amount = Amount::ExactIn(token_amount);
+        let quote = pool
+            .quote_swap(&SwapParams {
+                direction: Direction::SellToken,
+                amount,
+                limit: SlippageLimit::None,
+            })
+            .unwrap();
+        let params =
+            SwapParams::with_slippage(Direction::SellToken, amount, &quote, Slippage::Bps(50))
+                .unwrap();
+        assert_eq!(
+            params.limit,
+            SlippageLimit::MinOut(quote.amount_out - quote.amount_out * 50 / 10_000)
+        );
+        assert_eq!(pool.swap(&params).unwrap(), quote);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{Curve, LiquidityPool, PoolError, Result, MAX_FEE_BPS};
+
+/// Transfer tax of a fee-on-transfer token, in basis points of the tokens moved, rounded up.
+///
+/// Trade sizes, as in [`TradeOp`](crate::TradeOp), count tokens as they leave the pool (buy)
+/// or the seller (sell); the tax is taken from that transfer, so buyers receive less than
+/// they bought and sellers' tokens reach the reserves diminished.
+/// [`Projection::token_tax`](crate::Projection::token_tax), [`Quote`](crate::Quote) and
+/// [`SwapResult`](crate::SwapResult) report the tax and the amounts actually received.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct TokenTax {
+    pub buy_bps: u16,
+    pub sell_bps: u16,
+}
+
+impl TokenTax {
+    pub(crate) fn is_valid(&self) -> bool {
+        self.buy_bps < MAX_FEE_BPS && self.sell_bps < MAX_FEE_BPS
+    }
+}
+
+/// Tax taken from a transfer of `token_amount` at `bps`.
+pub(crate) fn tax_on(bps: u16, token_amount: u64) -> u64 {
+    (token_amount as u128 * bps as u128).div_ceil(MAX_FEE_BPS as u128) as u64
+}
+
+/// Smallest transfer at `bps` delivering at least `net` tokens, if it fits in a `u64`.
+pub(crate) fn gross_for_net(bps: u16, net: u64) -> Option<u64> {
+    let net_bps = (MAX_FEE_BPS - bps) as u128;
+    let mut gross = u64::try_from((net as u128 * MAX_FEE_BPS as u128).div_ceil(net_bps)).ok()?;
+    // The tax rounds up, so the estimate may fall one unit short.
+    while gross - tax_on(bps, gross) < net {
+        gross = gross.checked_add(1)?;
+    }
+    Some(gross)
+}
+
+impl<C: Curve> LiquidityPool<C> {
+    /// Simulates a fee-on-transfer token taxing its transfers out of the pool at
+    /// `tax.buy_bps` and into it at `tax.sell_bps`. Fails with [`PoolError::InvalidAmount`]
+    /// unless both are below 100%.
+    pub fn with_token_tax(mut self, tax: TokenTax) -> Result<Self> {
+        if !tax.is_valid() {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.token_tax = tax;
+        Ok(self)
+    }
+
+    pub fn token_tax(&self) -> TokenTax {
+        self.token_tax
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, Direction, SlippageLimit, SwapParams};
+
+    #[test]
+    fn test_token_tax() {
+        let tax = TokenTax {
+            buy_bps: 300,
+            sell_bps: 500,
+        };
+        let pool = LiquidityPool::default().with_token_tax(tax).unwrap();
+        let untaxed = LiquidityPool::default();
+        let token_amount = 10u64.pow(12);
+
+        let quote = pool.quote_buy(token_amount).unwrap();
+        assert_eq!(quote.token_tax, token_amount * 3 / 100);
+        assert_eq!(quote.amount_out, token_amount - quote.token_tax);
+        assert_eq!(
+            quote.amount_in,
+            untaxed.quote_buy(token_amount).unwrap().amount_in
+        );
+
+        // Only 95% of the tokens sold reach the pool.
+        let quote = pool.quote_sell(token_amount).unwrap();
+        assert_eq!(quote.token_tax, token_amount / 20);
+        assert_eq!(
+            quote.amount_out,
+            untaxed
+                .quote_sell(token_amount - quote.token_tax)
+                .unwrap()
+                .amount_out
+        );
+
+        let mut swapped = pool.clone();
+        let result = swapped
+            .swap(&SwapParams {
+                direction: Direction::BuyToken,
+                amount: Amount::ExactOut(token_amount),
+                limit: SlippageLimit::None,
+            })
+            .unwrap();
+        assert!(result.amount_out >= token_amount && result.amount_out - token_amount < 2);
+        assert_eq!(
+            swapped.get_token_reserve(),
+            pool.get_token_reserve() - result.amount_out - result.token_tax
+        );
+        let native_amount = 10u64.pow(6);
+        let sold = pool
+            .quote_swap(&SwapParams {
+                direction: Direction::SellToken,
+                amount: Amount::ExactOut(native_amount),
+                limit: SlippageLimit::None,
+            })
+            .unwrap();
+        assert!(sold.amount_out >= native_amount && sold.token_tax > 0);
+        assert_eq!(
+            LiquidityPool::default()
+                .with_token_tax(TokenTax {
+                    buy_bps: 0,
+                    sell_bps: MAX_FEE_BPS
+                })
+                .map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Helpers for testing code built on top of pools, enabled by the `test-utils` feature.
+
+use crate::LiquidityPool;
+
+/// Maximum relative drift of the constant product tolerated by [`assert_invariants`].
+pub const MAX_INTEGRITY_DRIFT: f64 = 0.000000001;
+
+/// Panics if `pool` has an empty reserve or its constant product has drifted by more than
+/// [`MAX_INTEGRITY_DRIFT`] from the value recorded at creation.
+#[track_caller]
+pub fn assert_invariants(pool: &LiquidityPool) {
+    let drift = pool
+        .check_pool_integrity()
+        .unwrap_or_else(|_| panic!("pool has an empty reserve: {pool:?}"));
+    assert!(
+        drift.abs() < MAX_INTEGRITY_DRIFT,
+        "constant product drifted by {drift} in {pool:?}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assert_invariants_after_trades() {
+        let mut pool = LiquidityPool::default();
+        pool.buy(1_000_000 * 10u64.pow(6), None).unwrap();
+        pool.sell(500_000 * 10u64.pow(6), None).unwrap();
+        assert_invariants(&pool);
+    }
+
+    #[test]
+    #[should_panic(expected = "constant product drifted")]
+    fn test_assert_invariants_detects_drift() {
+        let mut pool = LiquidityPool::default();
+        pool.native_reserve *= 2;
+        assert_invariants(&pool);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{Curve, LiquidityPool, PoolError, Result, TradeOp};
+
+/// A trader's native and token balances, traded against pools with the cost of the tokens held
+/// tracked at average cost.
+///
+/// Trades fail with [`PoolError::InsufficientBalance`] instead of spending more than the
+/// trader holds. Fees and transfer taxes are borne by the trader: they are part of the native
+/// spent on buys and of the tokens given up on sells, so they show in the PnL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trader {
+    native_balance: u64,
+    token_balance: u64,
+    cost_basis: u128,
+    realized_pnl: i128,
+}
+
+impl Trader {
+    /// Creates a trader holding `native_balance` and no tokens.
+    pub fn new(native_balance: u64) -> Self {
+        Self {
+            native_balance,
+            ..Self::default()
+        }
+    }
+
+    /// Adds `token_amount` tokens acquired elsewhere for `cost_basis` native currency.
+    pub fn with_tokens(mut self, token_amount: u64, cost_basis: u128) -> Self {
+        self.token_balance += token_amount;
+        self.cost_basis += cost_basis;
+        self
+    }
+
+    pub fn native_balance(&self) -> u64 {
+        self.native_balance
+    }
+
+    pub fn token_balance(&self) -> u64 {
+        self.token_balance
+    }
+
+    /// Native currency paid for the tokens held, fees included.
+    pub fn cost_basis(&self) -> u128 {
+        self.cost_basis
+    }
+
+    /// Native currency paid per token held, or `None` without tokens.
+    pub fn average_entry_price(&self) -> Option<f64> {
+        (self.token_balance > 0).then(|| self.cost_basis as f64 / self.token_balance as f64)
+    }
+
+    /// Native currency received by sells beyond the cost basis of the tokens sold.
+    pub fn realized_pnl(&self) -> i128 {
+        self.realized_pnl
+    }
+
+    /// Value of the tokens held at the spot price of `pool` beyond their cost basis.
+    pub fn unrealized_pnl<C: Curve>(&self, pool: &LiquidityPool<C>) -> f64 {
+        self.token_balance as f64 * pool.spot_price() - self.cost_basis as f64
+    }
+
+    /// Buys `token_amount` tokens from `pool` as [`LiquidityPool::buy`] and returns the native
+    /// currency spent. The tokens credited are net of the buy tax.
+    pub fn buy<C: Curve>(
+        &mut self,
+        pool: &mut LiquidityPool<C>,
+        token_amount: u64,
+        max_native: Option<u64>,
+    ) -> Result<u64> {
+        let projection = pool.simulate(&TradeOp::Buy {
+            token_amount,
+            max_native,
+        })?;
+        if projection.native_amount > self.native_balance {
+            return Err(PoolError::InsufficientBalance {
+                requested: projection.native_amount,
+                available: self.native_balance,
+            });
+        }
+        let received = token_amount - projection.token_tax;
+        let token_balance = self
+            .token_balance
+            .checked_add(received)
+            .ok_or(PoolError::Overflow)?;
+        pool.commit_trade(&projection)?;
+        self.native_balance -= projection.native_amount;
+        self.token_balance = token_balance;
+        self.cost_basis += projection.native_amount as u128;
+        Ok(projection.native_amount)
+    }
+
+    /// Sells `token_amount` tokens to `pool` as [`LiquidityPool::sell`] and returns the native
+    /// currency received, realizing the difference with their share of the cost basis.
+    pub fn sell<C: Curve>(
+        &mut self,
+        pool: &mut LiquidityPool<C>,
+        token_amount: u64,
+        min_native: Option<u64>,
+    ) -> Result<u64> {
+        if token_amount > self.token_balance {
+            return Err(PoolError::InsufficientBalance {
+                requested: token_amount,
+                available: self.token_balance,
+            });
+        }
+        let projection = pool.simulate(&TradeOp::Sell {
+            token_amount,
+            min_native,
+        })?;
+        let native_balance = self
+            .native_balance
+            .checked_add(projection.native_amount)
+            .ok_or(PoolError::Overflow)?;
+        pool.commit_trade(&projection)?;
+        let cost = self.cost_basis * token_amount as u128 / self.token_balance as u128;
+        self.native_balance = native_balance;
+        self.token_balance -= token_amount;
+        self.cost_basis -= cost;
+        self.realized_pnl += projection.native_amount as i128 - cost as i128;
+        Ok(projection.native_amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TokenTax;
+
+    #[test]
+    fn test_trader_pnl() {
+        let mut pool = LiquidityPool::default().with_fee(30).unwrap();
+        let mut trader = Trader::new(10u64.pow(9));
+        let token_amount = 10u64.pow(14);
+        let paid = trader.buy(&mut pool, token_amount, None).unwrap();
+        assert_eq!(trader.native_balance(), 10u64.pow(9) - paid);
+        assert_eq!(trader.token_balance(), token_amount);
+        assert_eq!(
+            trader.average_entry_price(),
+            Some(paid as f64 / token_amount as f64)
+        );
+        // Buying moved the spot price above the average entry price.
+        assert!(trader.unrealized_pnl(&pool) > 0.0);
+
+        let received = trader.sell(&mut pool, token_amount / 2, None).unwrap();
+        assert_eq!(trader.cost_basis(), (paid - paid / 2) as u128);
+        assert_eq!(trader.realized_pnl(), received as i128 - (paid / 2) as i128);
+        trader.sell(&mut pool, token_amount / 2, None).unwrap();
+        // A round trip loses the fees and the price impact.
+        assert!(trader.realized_pnl() < 0);
+        assert_eq!(trader.average_entry_price(), None);
+        assert_eq!(
+            trader.native_balance() as i128,
+            10i128.pow(9) + trader.realized_pnl()
+        );
+    }
+
+    #[test]
+    fn test_trader_balances() {
+        let mut pool = LiquidityPool::default();
+        let mut trader = Trader::new(1_000);
+        let cost = pool.quote(&TradeOp::Buy {
+            token_amount: 2 * 10u64.pow(9),
+            max_native: None,
+        });
+        assert_eq!(
+            trader.buy(&mut pool, 2 * 10u64.pow(9), None),
+            Err(PoolError::InsufficientBalance {
+                requested: cost.unwrap(),
+                available: 1_000
+            })
+        );
+        assert_eq!(
+            trader.sell(&mut pool, 1, None),
+            Err(PoolError::InsufficientBalance {
+                requested: 1,
+                available: 0
+            })
+        );
+        assert_eq!(pool.trade_count(), 0);
+
+        let mut taxed = LiquidityPool::default()
+            .with_token_tax(TokenTax {
+                buy_bps: 500,
+                sell_bps: 0,
+            })
+            .unwrap();
+        let mut trader = Trader::new(10u64.pow(9)).with_tokens(100, 0);
+        trader.buy(&mut taxed, 10u64.pow(6), None).unwrap();
+        assert_eq!(trader.token_balance(), 100 + 950_000);
+    }
+}
@@ -0,0 +1,110 @@
+use alloc::vec::Vec;
+
+use crate::{LiquidityPool, PoolSnapshot, Projection, Result, TradeOp};
+
+/// Trades staged against a pool, applied together by [`commit`](Self::commit) or discarded
+/// when dropped. Created by [`LiquidityPool::begin`].
+///
+/// Staging works on a bare copy of the pool state, so history, checkpoints and alerts are
+/// neither copied nor touched until the trades are committed.
+#[derive(Debug)]
+pub struct PoolTransaction<'a> {
+    pool: &'a mut LiquidityPool,
+    staged: LiquidityPool,
+    trades: Vec<Projection>,
+}
+
+impl<'a> PoolTransaction<'a> {
+    /// Stages `op` on top of the trades already staged and returns the native currency it
+    /// spends (buy) or receives (sell).
+    pub fn execute(&mut self, op: &TradeOp) -> Result<u64> {
+        let projection = self.staged.simulate(op)?;
+        self.staged.apply_trade(&projection)?;
+        self.trades.push(projection);
+        Ok(projection.native_amount)
+    }
+
+    pub fn buy(&mut self, token_amount: u64, max_native: Option<u64>) -> Result<u64> {
+        self.execute(&TradeOp::Buy {
+            token_amount,
+            max_native,
+        })
+    }
+
+    pub fn sell(&mut self, token_amount: u64, min_native: Option<u64>) -> Result<u64> {
+        self.execute(&TradeOp::Sell {
+            token_amount,
+            min_native,
+        })
+    }
+
+    /// Returns the pool as it will be once the staged trades are committed.
+    pub fn staged(&self) -> &LiquidityPool {
+        &self.staged
+    }
+
+    /// Returns the staged trades, oldest first.
+    pub fn trades(&self) -> &[Projection] {
+        &self.trades
+    }
+
+    /// Applies the staged trades to the pool in order, recording and notifying each one as if
+    /// it had been executed directly.
+    pub fn commit(self) -> Result<()> {
+        for projection in &self.trades {
+            self.pool.commit_trade(projection)?;
+        }
+        Ok(())
+    }
+}
+
+impl LiquidityPool {
+    /// Starts staging trades against the pool. Dropping the transaction without committing it
+    /// leaves the pool untouched.
+    pub fn begin(&mut self) -> PoolTransaction<'_> {
+        PoolTransaction {
+            staged: PoolSnapshot::of(self).to_pool(),
+            pool: self,
+            trades: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_matches_direct_execution() {
+        let mut pool = LiquidityPool::default();
+        pool.enable_history();
+        let mut direct = pool.clone();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+
+        let mut transaction = pool.begin();
+        let spent = transaction.buy(token_amount, None).unwrap();
+        let received = transaction.sell(token_amount / 2, None).unwrap();
+        assert_eq!(transaction.staged().trade_count(), 2);
+        assert_eq!(transaction.trades().len(), 2);
+        transaction.commit().unwrap();
+
+        assert_eq!(direct.buy(token_amount, None).unwrap(), spent);
+        assert_eq!(direct.sell(token_amount / 2, None).unwrap(), received);
+        assert_eq!(PoolSnapshot::of(&pool), PoolSnapshot::of(&direct));
+        assert_eq!(pool.history(), direct.history());
+    }
+
+    #[test]
+    fn test_drop_rolls_back() {
+        let mut pool = LiquidityPool::default();
+        let mut transaction = pool.begin();
+        transaction.buy(1_000_000 * 10u64.pow(6), None).unwrap();
+        assert!(transaction.sell(u64::MAX, None).is_err());
+        assert_eq!(transaction.trades().len(), 1);
+        drop(transaction);
+        assert_eq!(
+            PoolSnapshot::of(&pool),
+            PoolSnapshot::of(&LiquidityPool::default())
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+
+use crate::{LiquidityPool, PoolError, Result, TradeOp};
+
+/// How holders are assumed to behave when their tokens unlock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnlockBehavior {
+    /// Fraction of each unlock sold into the pool, from 0 to 1.
+    pub sell_fraction: f64,
+    /// Number of equal sells the unlock is split into.
+    pub chunks: usize,
+    /// Fraction of the native raised by each unlock that buyers spend on tokens before the
+    /// next unlock, from 0 (no recovery) to 1.
+    pub recovery: f64,
+}
+
+/// Price impact of one unlock event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnlockImpact {
+    pub unlocked: u64,
+    pub tokens_sold: u64,
+    pub native_received: u64,
+    /// Market price when the unlock happened.
+    pub price_before: f64,
+    /// Market price once the unlocked tokens were sold, before any recovery.
+    pub price_after: f64,
+    /// Price drop caused by the unlock, in percent of `price_before`.
+    pub drawdown_percent: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnlockReport {
+    pub impacts: Vec<UnlockImpact>,
+    /// Lowest price reached as a percentage drop from the starting price.
+    pub max_drawdown_percent: f64,
+    /// The pool after every unlock and recovery.
+    pub pool: LiquidityPool,
+}
+
+impl LiquidityPool {
+    /// Simulates selling a vesting schedule into a copy of the pool, one entry per unlock
+    /// event in token units, and reports the drawdown each unlock causes.
+    pub fn simulate_unlocks(
+        &self,
+        schedule: &[u64],
+        behavior: UnlockBehavior,
+    ) -> Result<UnlockReport> {
+        if !(0.0..=1.0).contains(&behavior.sell_fraction)
+            || !(0.0..=1.0).contains(&behavior.recovery)
+            || behavior.chunks == 0
+        {
+            return Err(PoolError::InvalidAmount);
+        }
+        let mut pool = self.clone();
+        let start_price = pool.market_price();
+        let mut lowest_price = start_price;
+        let mut impacts = Vec::with_capacity(schedule.len());
+        for &unlocked in schedule {
+            let price_before = pool.market_price();
+            let tokens_sold = (unlocked as f64 * behavior.sell_fraction) as u64;
+            let chunks = behavior.chunks as u64;
+            let mut native_received = 0;
+            for chunk in 0..chunks {
+                // Spread the remainder over the first chunks.
+                let token_amount = tokens_sold / chunks + u64::from(chunk < tokens_sold % chunks);
+                if token_amount > 0 {
+                    native_received += pool.execute(&TradeOp::Sell {
+                        token_amount,
+                        min_native: None,
+                    })?;
+                }
+            }
+            let price_after = pool.market_price();
+            lowest_price = lowest_price.min(price_after);
+            impacts.push(UnlockImpact {
+                unlocked,
+                tokens_sold,
+                native_received,
+                price_before,
+                price_after,
+                drawdown_percent: (1.0 - price_after / price_before) * 100.0,
+            });
+            let recovery = (native_received as f64 * behavior.recovery) as u64;
+            if recovery > 0 {
+                // A budget too small to buy a single token unit is simply not spent.
+                let _ = pool.spend_native(recovery);
+            }
+        }
+        Ok(UnlockReport {
+            impacts,
+            max_drawdown_percent: (1.0 - lowest_price / start_price) * 100.0,
+            pool,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlock_drawdowns() {
+        let pool = LiquidityPool::default();
+        let unlock = 50_000_000 * 10u64.pow(6);
+        let behavior = UnlockBehavior {
+            sell_fraction: 0.5,
+            chunks: 4,
+            recovery: 0.0,
+        };
+        let report = pool.simulate_unlocks(&[unlock, unlock], behavior).unwrap();
+        assert_eq!(report.impacts.len(), 2);
+        assert_eq!(report.impacts[0].tokens_sold, unlock / 2);
+        assert!(report.impacts[0].drawdown_percent > 0.0);
+        assert_eq!(
+            report.impacts[1].price_before,
+            report.impacts[0].price_after
+        );
+        assert_eq!(
+            report.pool.get_token_reserve(),
+            pool.get_token_reserve() + unlock
+        );
+        let expected = (1.0 - report.pool.market_price() / pool.market_price()) * 100.0;
+        assert!((report.max_drawdown_percent - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recovery_softens_drawdown() {
+        let pool = LiquidityPool::default();
+        let schedule = [100_000_000 * 10u64.pow(6); 3];
+        let run = |recovery| {
+            let behavior = UnlockBehavior {
+                sell_fraction: 1.0,
+                chunks: 1,
+                recovery,
+            };
+            pool.simulate_unlocks(&schedule, behavior).unwrap()
+        };
+        let full = run(1.0);
+        assert!(full.max_drawdown_percent < run(0.0).max_drawdown_percent);
+        assert!(full.impacts[2].price_before > run(0.0).impacts[2].price_before);
+        assert!(pool
+            .simulate_unlocks(
+                &schedule,
+                UnlockBehavior {
+                    sell_fraction: 1.5,
+                    chunks: 1,
+                    recovery: 0.0,
+                },
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,363 @@
+//! Crash-safe persistence through a snapshot plus a write-ahead log of trades.
+//!
+//! A [`WalPool`] keeps two files in its directory: `snapshot`, holding the pool state at the
+//! last checkpoint, and `wal`, holding every trade submitted since. Each trade is appended and
+//! synced to disk before it is applied, so after a crash [`WalPool::open`] rebuilds the exact
+//! state by replaying the log over the snapshot.
+
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{LiquidityPool, PoolError, PoolSnapshot, TradeOp, MAX_DECIMALS, MAX_FEE_BPS};
+
+const SNAPSHOT_FILE: &str = "snapshot";
+const WAL_FILE: &str = "wal";
+/// Layout of the snapshot file: a `version=` line, then one `key=value` line per field. Any
+/// other version is rejected rather than guessed at.
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("corrupt {file} at line {line}")]
+    Corrupt { file: &'static str, line: usize },
+    #[error("unsupported {file} version {version}")]
+    UnsupportedVersion { file: &'static str, version: u32 },
+    #[error("{file} is missing {field}")]
+    MissingField {
+        file: &'static str,
+        field: &'static str,
+    },
+    #[error(transparent)]
+    Pool(#[from] PoolError),
+}
+
+/// A pool whose trades are durably logged before they are applied.
+#[derive(Debug)]
+pub struct WalPool {
+    pool: LiquidityPool,
+    dir: PathBuf,
+    wal: File,
+}
+
+impl WalPool {
+    /// Starts persisting `pool` in `dir`, replacing any state previously stored there.
+    pub fn create(dir: impl AsRef<Path>, pool: LiquidityPool) -> Result<Self, WalError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        // Append mode keeps writes at the end of the log even after it is truncated.
+        let wal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(WAL_FILE))?;
+        // Clear the old log first so it can never be replayed over the new snapshot.
+        wal.set_len(0)?;
+        wal.sync_all()?;
+        write_snapshot(&dir, &PoolSnapshot::of(&pool))?;
+        Ok(Self { pool, dir, wal })
+    }
+
+    /// Recovers the pool persisted in `dir` from its snapshot and write-ahead log.
+    ///
+    /// A final log entry cut short by a crash was never applied and is discarded.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, WalError> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut pool = read_snapshot(&dir)?.to_pool();
+        let contents = fs::read_to_string(dir.join(WAL_FILE))?;
+        let complete = &contents[..contents.rfind('\n').map_or(0, |end| end + 1)];
+        for (index, line) in complete.lines().enumerate() {
+            let (seq, op) = decode_entry(line).ok_or(WalError::Corrupt {
+                file: WAL_FILE,
+                line: index + 1,
+            })?;
+            // Entries older than the snapshot are already reflected in it.
+            if seq < pool.trade_count() {
+                continue;
+            }
+            // A trade that was rejected when first submitted is rejected again identically.
+            let _ = pool.execute(&op);
+        }
+        let wal = OpenOptions::new().append(true).open(dir.join(WAL_FILE))?;
+        if complete.len() != contents.len() {
+            wal.set_len(complete.len() as u64)?;
+            wal.sync_all()?;
+        }
+        Ok(Self { pool, dir, wal })
+    }
+
+    pub fn pool(&self) -> &LiquidityPool {
+        &self.pool
+    }
+
+    /// Logs `op`, syncs the log to disk, then applies it to the pool.
+    pub fn execute(&mut self, op: &TradeOp) -> Result<u64, WalError> {
+        let entry = encode_entry(self.pool.trade_count(), op);
+        self.wal.write_all(entry.as_bytes())?;
+        self.wal.sync_data()?;
+        Ok(self.pool.execute(op)?)
+    }
+
+    /// Writes a fresh snapshot and empties the log, bounding recovery time.
+    pub fn checkpoint(&mut self) -> Result<(), WalError> {
+        write_snapshot(&self.dir, &PoolSnapshot::of(&self.pool))?;
+        self.wal.set_len(0)?;
+        self.wal.sync_all()?;
+        Ok(())
+    }
+}
+
+fn write_snapshot(dir: &Path, snapshot: &PoolSnapshot) -> io::Result<()> {
+    let mut contents = format!("version={SNAPSHOT_VERSION}\n");
+    for (key, value) in snapshot.fields() {
+        contents.push_str(&format!("{key}={value}\n"));
+    }
+    // Write then rename so a crash never leaves a half-written snapshot behind.
+    let tmp = dir.join(format!("{SNAPSHOT_FILE}.tmp"));
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(tmp, dir.join(SNAPSHOT_FILE))
+}
+
+fn read_snapshot(dir: &Path) -> Result<PoolSnapshot, WalError> {
+    let contents = fs::read_to_string(dir.join(SNAPSHOT_FILE))?;
+    let corrupt = |line| WalError::Corrupt {
+        file: SNAPSHOT_FILE,
+        line,
+    };
+    let mut lines = contents.lines().zip(1..);
+    let version = lines
+        .next()
+        .and_then(|(line, _)| line.strip_prefix("version="))
+        .and_then(|version| version.parse().ok())
+        .ok_or(corrupt(1))?;
+    if version != SNAPSHOT_VERSION {
+        return Err(WalError::UnsupportedVersion {
+            file: SNAPSHOT_FILE,
+            version,
+        });
+    }
+    // Every field is overwritten, or reported missing.
+    let mut snapshot = LiquidityPool::new(1, 1)
+        .expect("reserves are non-zero")
+        .snapshot();
+    let mut seen = BTreeSet::new();
+    for (line, number) in lines {
+        let (key, value) = line.split_once('=').ok_or(corrupt(number))?;
+        if !seen.insert(key) {
+            return Err(corrupt(number));
+        }
+        snapshot.set_field(key, value).ok_or(corrupt(number))?;
+    }
+    if let Some((field, _)) = snapshot
+        .fields()
+        .into_iter()
+        .find(|(field, _)| !seen.contains(field))
+    {
+        return Err(WalError::MissingField {
+            file: SNAPSHOT_FILE,
+            field,
+        });
+    }
+    if snapshot.native_reserve == 0
+        || snapshot.token_reserve == 0
+        || snapshot.fee_bps >= MAX_FEE_BPS
+        || snapshot.locked_liquidity > snapshot.lp_supply
+        || snapshot.virtual_native > snapshot.native_reserve
+        || snapshot.virtual_token > snapshot.token_reserve
+        || snapshot
+            .graduation
+            .is_some_and(|graduation| !graduation.target.is_valid())
+        || snapshot.native_decimals > MAX_DECIMALS
+        || snapshot.token_decimals > MAX_DECIMALS
+        || snapshot.protocol_fee_share_bps > MAX_FEE_BPS
+        || !snapshot.token_tax.is_valid()
+    {
+        return Err(corrupt(1));
+    }
+    Ok(snapshot)
+}
+
+fn encode_entry(seq: u64, op: &TradeOp) -> String {
+    let (kind, token_amount, limit) = match *op {
+        TradeOp::Buy {
+            token_amount,
+            max_native,
+        } => ("buy", token_amount, max_native),
+        TradeOp::Sell {
+            token_amount,
+            min_native,
+        } => ("sell", token_amount, min_native),
+    };
+    match limit {
+        Some(limit) => format!("{seq} {kind} {token_amount} {limit}\n"),
+        None => format!("{seq} {kind} {token_amount}\n"),
+    }
+}
+
+fn decode_entry(line: &str) -> Option<(u64, TradeOp)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (seq, kind, token_amount, limit) = match fields.as_slice() {
+        [seq, kind, token_amount] => (seq, kind, token_amount, None),
+        [seq, kind, token_amount, limit] => (seq, kind, token_amount, Some(limit.parse().ok()?)),
+        _ => return None,
+    };
+    let token_amount = token_amount.parse().ok()?;
+    let op = match *kind {
+        "buy" => TradeOp::Buy {
+            token_amount,
+            max_native: limit,
+        },
+        "sell" => TradeOp::Sell {
+            token_amount,
+            min_native: limit,
+        },
+        _ => return None,
+    };
+    Some((seq.parse().ok()?, op))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraduationTarget, RoundingPolicy, TokenTax};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("consta-pool-wal-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn buy(token_amount: u64) -> TradeOp {
+        TradeOp::Buy {
+            token_amount,
+            max_native: None,
+        }
+    }
+
+    #[test]
+    fn test_recover_after_crash() {
+        let dir = test_dir("recover");
+        let pool = LiquidityPool::default()
+            .with_fee(30)
+            .unwrap()
+            .with_graduation(GraduationTarget::TokensSoldBps(9_000))
+            .unwrap()
+            .with_decimals(9, 6)
+            .unwrap()
+            .with_rounding(RoundingPolicy::FAVOR_POOL)
+            .with_protocol_fee_share(2_000)
+            .unwrap()
+            .with_token_tax(TokenTax {
+                buy_bps: 100,
+                sell_bps: 200,
+            })
+            .unwrap();
+        let mut wal_pool = WalPool::create(&dir, pool).unwrap();
+        let token_amount = 1_000_000 * 10u64.pow(6);
+        wal_pool.execute(&buy(token_amount)).unwrap();
+        wal_pool.checkpoint().unwrap();
+        wal_pool.execute(&buy(token_amount)).unwrap();
+        let rejected = TradeOp::Buy {
+            token_amount,
+            max_native: Some(1),
+        };
+        assert!(wal_pool.execute(&rejected).is_err());
+        wal_pool
+            .execute(&TradeOp::Sell {
+                token_amount: 3 * token_amount,
+                min_native: None,
+            })
+            .unwrap();
+        let expected = wal_pool.pool().clone();
+        drop(wal_pool);
+
+        let recovered = WalPool::open(&dir).unwrap();
+        assert_eq!(
+            recovered.pool().get_native_reserve(),
+            expected.get_native_reserve()
+        );
+        assert_eq!(
+            recovered.pool().get_token_reserve(),
+            expected.get_token_reserve()
+        );
+        assert_eq!(recovered.pool().trade_count(), expected.trade_count());
+        assert_eq!(recovered.pool().fees_collected(), expected.fees_collected());
+        assert_eq!(recovered.pool().graduation(), expected.graduation());
+        assert_eq!(recovered.pool().token_decimals(), 6);
+        assert_eq!(recovered.pool().rounding(), RoundingPolicy::FAVOR_POOL);
+        assert_eq!(
+            recovered.pool().protocol_fees_owed(),
+            expected.protocol_fees_owed()
+        );
+        assert_eq!(recovered.pool().token_tax(), expected.token_tax());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_torn_entry_is_discarded() {
+        let dir = test_dir("torn");
+        let mut wal_pool = WalPool::create(&dir, LiquidityPool::default()).unwrap();
+        wal_pool.execute(&buy(10u64.pow(12))).unwrap();
+        let expected = wal_pool.pool().clone();
+        drop(wal_pool);
+        let mut wal = OpenOptions::new()
+            .append(true)
+            .open(dir.join(WAL_FILE))
+            .unwrap();
+        wal.write_all(b"1 buy 99").unwrap();
+
+        let mut recovered = WalPool::open(&dir).unwrap();
+        assert_eq!(
+            recovered.pool().get_token_reserve(),
+            expected.get_token_reserve()
+        );
+        recovered.execute(&buy(10u64.pow(12))).unwrap();
+        let reopened = WalPool::open(&dir).unwrap();
+        assert_eq!(reopened.pool().trade_count(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_versions() {
+        let dir = test_dir("versions");
+        WalPool::create(&dir, LiquidityPool::default()).unwrap();
+        let path = dir.join(SNAPSHOT_FILE);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("version=1\ninitial_token_reserve="));
+
+        fs::write(&path, contents.replace("version=1", "version=2")).unwrap();
+        assert!(matches!(
+            WalPool::open(&dir),
+            Err(WalError::UnsupportedVersion { version: 2, .. })
+        ));
+        // The unversioned layout of earlier development builds.
+        fs::write(&path, "1000000000000000 1000000000 1000000000000000 1\n").unwrap();
+        assert!(matches!(
+            WalPool::open(&dir),
+            Err(WalError::Corrupt { line: 1, .. })
+        ));
+        let without_tax: String = contents
+            .lines()
+            .filter(|line| !line.starts_with("token_tax_sell_bps="))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        fs::write(&path, without_tax).unwrap();
+        assert!(matches!(
+            WalPool::open(&dir),
+            Err(WalError::MissingField {
+                field: "token_tax_sell_bps",
+                ..
+            })
+        ));
+        fs::write(&path, format!("{contents}extra=1\n")).unwrap();
+        assert!(matches!(WalPool::open(&dir), Err(WalError::Corrupt { .. })));
+        fs::write(&path, contents).unwrap();
+        assert!(WalPool::open(&dir).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{LiquidityPool, TradeOp};
+
+/// A trade from an external stream, tagged with the actor that submitted it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaggedTrade<A> {
+    pub actor: A,
+    pub op: TradeOp,
+}
+
+/// Heuristics deciding when a buy and a sell by the same actor offset each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WashCriteria {
+    /// Maximum number of trades, by anyone, between the two legs.
+    pub window: usize,
+    /// Maximum difference between the token amounts of the two legs, relative to the larger.
+    pub tolerance: f64,
+}
+
+/// Self-crossing activity attributed to one actor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WashSuspicion<A> {
+    pub actor: A,
+    /// Tokens bought and sold by the actor.
+    pub volume: u128,
+    /// Part of `volume` belonging to offsetting buy/sell pairs.
+    pub matched_volume: u128,
+    pub matched_pairs: usize,
+    /// `matched_volume / volume`, from 0 (no offsetting trades) to 1 (only offsetting trades).
+    pub score: f64,
+}
+
+struct Leg {
+    step: usize,
+    is_buy: bool,
+    token_amount: u64,
+}
+
+impl LiquidityPool {
+    /// Replays `trades` against a copy of the pool and scores every actor by how much of its
+    /// volume was quickly offset by an opposite trade of similar size. Rejected trades are
+    /// ignored. Actors are returned most suspicious first.
+    pub fn detect_wash_trading<A: Eq + Hash + Clone>(
+        &self,
+        trades: impl IntoIterator<Item = TaggedTrade<A>>,
+        criteria: WashCriteria,
+    ) -> Vec<WashSuspicion<A>> {
+        let mut pool = self.clone();
+        let mut actors: HashMap<A, (WashSuspicion<A>, Vec<Leg>)> = HashMap::new();
+        for (step, trade) in trades.into_iter().enumerate() {
+            if pool.execute(&trade.op).is_err() {
+                continue;
+            }
+            let (suspicion, open_legs) = actors.entry(trade.actor.clone()).or_insert_with(|| {
+                let suspicion = WashSuspicion {
+                    actor: trade.actor,
+                    volume: 0,
+                    matched_volume: 0,
+                    matched_pairs: 0,
+                    score: 0.0,
+                };
+                (suspicion, Vec::new())
+            });
+            let token_amount = trade.op.token_amount();
+            let is_buy = matches!(trade.op, TradeOp::Buy { .. });
+            suspicion.volume += token_amount as u128;
+            open_legs.retain(|leg| step - leg.step <= criteria.window);
+            let offsetting = open_legs.iter().position(|leg| {
+                let larger = leg.token_amount.max(token_amount) as f64;
+                leg.is_buy != is_buy
+                    && leg.token_amount.abs_diff(token_amount) as f64 <= criteria.tolerance * larger
+            });
+            match offsetting {
+                Some(index) => {
+                    let leg = open_legs.remove(index);
+                    suspicion.matched_volume += leg.token_amount as u128 + token_amount as u128;
+                    suspicion.matched_pairs += 1;
+                }
+                None => open_legs.push(Leg {
+                    step,
+                    is_buy,
+                    token_amount,
+                }),
+            }
+        }
+        let mut suspicions: Vec<_> = actors
+            .into_values()
+            .map(|(mut suspicion, _)| {
+                suspicion.score = suspicion.matched_volume as f64 / suspicion.volume as f64;
+                suspicion
+            })
+            .collect();
+        suspicions.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.matched_volume.cmp(&a.matched_volume))
+        });
+        suspicions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(actor: &'static str, is_buy: bool, token_amount: u64) -> TaggedTrade<&'static str> {
+        let op = if is_buy {
+            TradeOp::Buy {
+                token_amount,
+                max_native: None,
+            }
+        } else {
+            TradeOp::Sell {
+                token_amount,
+                min_native: None,
+            }
+        };
+        TaggedTrade { actor, op }
+    }
+
+    #[test]
+    fn test_detect_wash_trading() {
+        let unit = 10u64.pow(12);
+        let trades = [
+            trade("washer", true, 10 * unit),
+            trade("trader", true, 3 * unit),
+            trade("washer", false, 10 * unit),
+            trade("trader", false, unit),
+            trade("washer", true, 5 * unit),
+            trade("trader", true, 2 * unit),
+            trade("trader", true, 2 * unit),
+            trade("trader", true, 2 * unit),
+            trade("washer", false, 5 * unit - 1),
+        ];
+        let criteria = WashCriteria {
+            window: 4,
+            tolerance: 0.01,
+        };
+        let suspicions = LiquidityPool::default().detect_wash_trading(trades, criteria);
+        assert_eq!(suspicions[0].actor, "washer");
+        assert_eq!(suspicions[0].matched_pairs, 2);
+        assert_eq!(suspicions[0].score, 1.0);
+        assert_eq!(suspicions[1].actor, "trader");
+        assert_eq!(suspicions[1].score, 0.0);
+
+        let strict = WashCriteria {
+            window: 3,
+            tolerance: 0.0,
+        };
+        let suspicions = LiquidityPool::default().detect_wash_trading(trades, strict);
+        let washer = suspicions.iter().find(|s| s.actor == "washer").unwrap();
+        assert_eq!(washer.matched_pairs, 1);
+        assert!((washer.score - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
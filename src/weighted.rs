@@ -0,0 +1,160 @@
+use crate::{Curve, CurveState, Direction, PoolError};
+
+/// The two-asset constant-mean curve of Balancer weighted pools,
+/// `native_reserve^w_native * token_reserve^w_token = k`, for pools holding their assets in
+/// unequal proportions such as 80/20.
+///
+/// Weights are relative: only their ratio matters, so `80` and `20` describe the same pool as
+/// `4` and `1`. Amounts follow Balancer's exact-in and exact-out formulas in floating point,
+/// rounded in favour of the pool, and the invariant is the weighted geometric mean of the
+/// reserves with weights normalized to one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weighted {
+    pub native_weight: u32,
+    pub token_weight: u32,
+}
+
+impl Weighted {
+    /// Returns the reserves and weights on each side of a trade in `direction`, as
+    /// `(reserve_in, weight_in, reserve_out, weight_out)`.
+    fn sides(&self, state: &CurveState, direction: Direction) -> (f64, f64, f64, f64) {
+        let native = (state.native_reserve as f64, self.native_weight as f64);
+        let token = (state.token_reserve as f64, self.token_weight as f64);
+        let ((reserve_in, weight_in), (reserve_out, weight_out)) = match direction {
+            Direction::BuyToken => (native, token),
+            Direction::SellToken => (token, native),
+        };
+        (reserve_in, weight_in, reserve_out, weight_out)
+    }
+}
+
+impl Curve for Weighted {
+    fn invariant(&self, native_reserve: u64, token_reserve: u64) -> Result<u128, PoolError> {
+        if self.native_weight == 0 || self.token_weight == 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let total = self.native_weight as f64 + self.token_weight as f64;
+        let mean = (native_reserve as f64).powf(self.native_weight as f64 / total)
+            * (token_reserve as f64).powf(self.token_weight as f64 / total);
+        Ok(mean as u128)
+    }
+
+    /// `reserve_out * (1 - (reserve_in / (reserve_in + amount_in))^(weight_in / weight_out))`,
+    /// rounded down.
+    fn amount_out(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_in: u64,
+    ) -> Result<u64, PoolError> {
+        let (reserve_in, weight_in, reserve_out, weight_out) = self.sides(state, direction);
+        let ratio = -(-(weight_in / weight_out) * (amount_in as f64 / reserve_in).ln_1p()).exp_m1();
+        Ok((reserve_out * ratio)
+            .floor()
+            .min(reserve_out - 1.0)
+            .max(0.0) as u64)
+    }
+
+    /// `reserve_in * ((reserve_out / (reserve_out - amount_out))^(weight_out / weight_in) - 1)`,
+    /// rounded up.
+    fn amount_in(
+        &self,
+        state: &CurveState,
+        direction: Direction,
+        amount_out: u64,
+    ) -> Result<u64, PoolError> {
+        let (reserve_in, weight_in, reserve_out, weight_out) = self.sides(state, direction);
+        if amount_out as f64 >= reserve_out {
+            return Err(PoolError::InsufficientPoolFunds {
+                requested: amount_out,
+                available: reserve_out as u64,
+            });
+        }
+        let ratio =
+            (-(weight_out / weight_in) * (-(amount_out as f64) / reserve_out).ln_1p()).exp_m1();
+        let amount_in = (reserve_in * ratio).ceil();
+        if amount_in.is_finite() && amount_in < u64::MAX as f64 {
+            Ok(amount_in as u64)
+        } else {
+            Err(PoolError::Overflow)
+        }
+    }
+
+    /// `(native_reserve / native_weight) / (token_reserve / token_weight)`.
+    fn spot_price(&self, state: &CurveState) -> f64 {
+        (state.native_reserve as f64 / self.native_weight as f64)
+            / (state.token_reserve as f64 / self.token_weight as f64)
+    }
+
+    /// Trades round in favour of the pool, so they may raise the invariant but never lower it
+    /// beyond floating-point error.
+    fn on_curve(&self, state: &CurveState) -> bool {
+        self.invariant(state.native_reserve, state.token_reserve)
+            .is_ok_and(|invariant| {
+                invariant + (state.invariant >> 40) + 2 >= state.invariant
+                    && invariant - state.invariant.min(invariant) <= state.invariant >> 10
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityPool;
+
+    #[test]
+    fn test_weighted_pool() {
+        let curve = Weighted {
+            native_weight: 80,
+            token_weight: 20,
+        };
+        let mut pool = LiquidityPool::with_curve(curve, 4 * 10u64.pow(12), 10u64.pow(12)).unwrap();
+        assert_eq!(pool.spot_price(), 1.0);
+
+        // Exact in and exact out are inverses up to rounding in favour of the pool.
+        let state = pool.curve_state();
+        let token_amount = 10u64.pow(10);
+        let cost = curve
+            .amount_in(&state, Direction::BuyToken, token_amount)
+            .unwrap();
+        let bought = curve.amount_out(&state, Direction::BuyToken, cost).unwrap();
+        assert!(bought >= token_amount - 1 && bought <= token_amount);
+        // Buying 1% of the light side moves the price less than in an even pool.
+        let even = LiquidityPool::new(10u64.pow(12), 10u64.pow(12)).unwrap();
+        let impact = pool.quote_buy(token_amount).unwrap().price_impact;
+        assert!(impact > 0.0 && impact < even.quote_buy(token_amount).unwrap().price_impact);
+
+        assert_eq!(pool.buy(token_amount, None), Ok(cost));
+        assert_eq!(pool.check_invariants(), Ok(()));
+        let received = pool.sell(token_amount, None).unwrap();
+        assert!(received < cost && cost - received <= 2);
+        assert_eq!(pool.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_even_weights_match_constant_product() {
+        let curve = Weighted {
+            native_weight: 1,
+            token_weight: 1,
+        };
+        let weighted = LiquidityPool::with_curve(curve, 10u64.pow(12), 10u64.pow(12)).unwrap();
+        let product = LiquidityPool::new(10u64.pow(12), 10u64.pow(12)).unwrap();
+        let token_amount = 10u64.pow(11);
+        let cost = weighted.quote_buy(token_amount).unwrap().amount_in;
+        let expected = product.quote_buy(token_amount).unwrap().amount_in;
+        assert!(cost.abs_diff(expected) <= 1);
+        assert_eq!(
+            LiquidityPool::with_curve(
+                Weighted {
+                    native_weight: 0,
+                    token_weight: 1
+                },
+                1_000,
+                1_000
+            )
+            .map(|_| ()),
+            Err(PoolError::InvalidAmount)
+        );
+    }
+}
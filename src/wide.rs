@@ -0,0 +1,379 @@
+//! Constant product swap math over reserve integers wider than `u64`, for tokens with 18
+//! decimals or large supplies that [`LiquidityPool`](crate::LiquidityPool) cannot hold without
+//! scaling.
+//!
+//! [`WidePool`] is not a generic [`LiquidityPool`](crate::LiquidityPool): it only quotes and
+//! applies swaps, and has none of the pool's liquidity provision, fee split, token tax, virtual
+//! reserves, rounding policies, history or subscriptions. Its swaps follow the main pool's
+//! default configuration, rounding reserves down and leaving the fee in the native reserve. It is
+//! generic over a [`ReserveInt`] integer: `u64`, `u128`, or `primitive_types::U256` with the
+//! `u256` feature. The invariant is kept as two reserves, so its product is only ever formed
+//! inside [`ReserveInt::mul_div`] and never needs a wider type to be stored.
+
+use core::fmt;
+
+use crate::{PoolError, Result, MAX_FEE_BPS};
+
+/// Unsigned integer a [`WidePool`] keeps its reserves in.
+pub trait ReserveInt: Copy + Ord + fmt::Debug {
+    const ZERO: Self;
+
+    fn from_u64(value: u64) -> Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    fn checked_sub(self, other: Self) -> Option<Self>;
+
+    /// Returns `a * b / den` and its remainder, computed without overflow, or `None` if `den`
+    /// is zero or the quotient does not fit.
+    fn mul_div_rem(a: Self, b: Self, den: Self) -> Option<(Self, Self)>;
+
+    fn to_f64(self) -> f64;
+
+    /// Converts to `u64` for error context, saturating at `u64::MAX`.
+    fn saturating_to_u64(self) -> u64;
+
+    /// `a * b / den` rounded down.
+    fn mul_div(a: Self, b: Self, den: Self) -> Option<Self> {
+        Self::mul_div_rem(a, b, den).map(|(quotient, _)| quotient)
+    }
+
+    /// `a * b / den` rounded up.
+    fn mul_div_ceil(a: Self, b: Self, den: Self) -> Option<Self> {
+        let (quotient, remainder) = Self::mul_div_rem(a, b, den)?;
+        if remainder == Self::ZERO {
+            Some(quotient)
+        } else {
+            quotient.checked_add(Self::from_u64(1))
+        }
+    }
+}
+
+impl ReserveInt for u64 {
+    const ZERO: Self = 0;
+
+    fn from_u64(value: u64) -> Self {
+        value
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u64::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        u64::checked_sub(self, other)
+    }
+
+    fn mul_div_rem(a: Self, b: Self, den: Self) -> Option<(Self, Self)> {
+        let product = a as u128 * b as u128;
+        let den = den as u128;
+        let quotient = u64::try_from(product.checked_div(den)?).ok()?;
+        Some((quotient, (product % den) as u64))
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn saturating_to_u64(self) -> u64 {
+        self
+    }
+}
+
+impl ReserveInt for u128 {
+    const ZERO: Self = 0;
+
+    fn from_u64(value: u64) -> Self {
+        value as u128
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u128::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        u128::checked_sub(self, other)
+    }
+
+    fn mul_div_rem(a: Self, b: Self, den: Self) -> Option<(Self, Self)> {
+        crate::curve::mul_div_rem(a, b, den)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn saturating_to_u64(self) -> u64 {
+        u64::try_from(self).unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(feature = "u256")]
+impl ReserveInt for primitive_types::U256 {
+    const ZERO: Self = primitive_types::U256::zero();
+
+    fn from_u64(value: u64) -> Self {
+        value.into()
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        primitive_types::U256::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        primitive_types::U256::checked_sub(self, other)
+    }
+
+    fn mul_div_rem(a: Self, b: Self, den: Self) -> Option<(Self, Self)> {
+        if den.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = a.full_mul(b).div_mod(den.into());
+        Some((quotient.try_into().ok()?, remainder.try_into().ok()?))
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0.iter().rev().fold(0.0, |value, &limb| {
+            value * 18_446_744_073_709_551_616.0 + limb as f64
+        })
+    }
+
+    fn saturating_to_u64(self) -> u64 {
+        if self.bits() > 64 {
+            u64::MAX
+        } else {
+            self.low_u64()
+        }
+    }
+}
+
+/// Constant product swaps over [`ReserveInt`] integers, `u128` by default. Only the swap math
+/// of [`LiquidityPool`](crate::LiquidityPool), with its default fee handling, is covered: no
+/// liquidity provision, fee split, token tax, virtual reserves, rounding policies, history or
+/// subscriptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WidePool<R: ReserveInt = u128> {
+    native_reserve: R,
+    token_reserve: R,
+    /// The invariant `k`, as the product of the reserves it was last set from.
+    invariant: (R, R),
+    fee_bps: u16,
+}
+
+impl<R: ReserveInt> WidePool<R> {
+    /// Creates a pool from its initial reserves. Fails with [`PoolError::ZeroLiquidity`] if
+    /// either is empty.
+    pub fn new(native_reserve: R, token_reserve: R) -> Result<Self> {
+        if native_reserve == R::ZERO || token_reserve == R::ZERO {
+            return Err(PoolError::ZeroLiquidity);
+        }
+        Ok(Self {
+            native_reserve,
+            token_reserve,
+            invariant: (native_reserve, token_reserve),
+            fee_bps: 0,
+        })
+    }
+
+    /// Charges a swap fee of `fee_bps` basis points, as
+    /// [`LiquidityPool::with_fee`](crate::LiquidityPool::with_fee) with its default fee split:
+    /// the whole fee stays in the native reserve and grows the invariant.
+    pub fn with_fee(mut self, fee_bps: u16) -> Result<Self> {
+        if fee_bps >= MAX_FEE_BPS {
+            return Err(PoolError::InvalidAmount);
+        }
+        self.fee_bps = fee_bps;
+        Ok(self)
+    }
+
+    pub fn native_reserve(&self) -> R {
+        self.native_reserve
+    }
+
+    pub fn token_reserve(&self) -> R {
+        self.token_reserve
+    }
+
+    pub fn fee_bps(&self) -> u16 {
+        self.fee_bps
+    }
+
+    /// Returns the marginal price of one token in native currency.
+    pub fn spot_price(&self) -> f64 {
+        self.native_reserve.to_f64() / self.token_reserve.to_f64()
+    }
+
+    /// Returns the native currency buying `token_amount` tokens would cost, fee included.
+    pub fn simulate_buy(&self, token_amount: R) -> Result<R> {
+        self.project_buy(token_amount)
+            .map(|(native_amount, _)| native_amount)
+    }
+
+    /// Returns the native currency selling `token_amount` tokens would pay, net of the fee.
+    pub fn simulate_sell(&self, token_amount: R) -> Result<R> {
+        self.project_sell(token_amount)
+            .map(|(native_amount, _)| native_amount)
+    }
+
+    /// Buys `token_amount` tokens, spending at most `max_native` if set, and returns the native
+    /// currency spent.
+    pub fn buy(&mut self, token_amount: R, max_native: Option<R>) -> Result<R> {
+        let (native_amount, reserves) = self.project_buy(token_amount)?;
+        if let Some(max_native) = max_native.filter(|&max_native| native_amount > max_native) {
+            return Err(PoolError::SlippageExceeded {
+                limit: max_native.saturating_to_u64(),
+                actual: native_amount.saturating_to_u64(),
+            });
+        }
+        self.apply(reserves);
+        Ok(native_amount)
+    }
+
+    /// Sells `token_amount` tokens, receiving at least `min_native` if set, and returns the
+    /// native currency received.
+    pub fn sell(&mut self, token_amount: R, min_native: Option<R>) -> Result<R> {
+        let (native_amount, reserves) = self.project_sell(token_amount)?;
+        if let Some(min_native) = min_native.filter(|&min_native| native_amount < min_native) {
+            return Err(PoolError::SlippageExceeded {
+                limit: min_native.saturating_to_u64(),
+                actual: native_amount.saturating_to_u64(),
+            });
+        }
+        self.apply(reserves);
+        Ok(native_amount)
+    }
+
+    /// Moves to the reserves after a trade, resetting the invariant to them if the trade's fee
+    /// left it behind.
+    fn apply(&mut self, reserves: (R, R)) {
+        (self.native_reserve, self.token_reserve) = reserves;
+        if self.fee_bps > 0 {
+            self.invariant = reserves;
+        }
+    }
+
+    /// Returns the native paid, fee included, and the reserves after buying.
+    fn project_buy(&self, token_amount: R) -> Result<(R, (R, R))> {
+        if token_amount == R::ZERO {
+            return Err(PoolError::InvalidAmount);
+        }
+        let new_token_reserve = self
+            .token_reserve
+            .checked_sub(token_amount)
+            .filter(|&reserve| reserve > R::ZERO)
+            .ok_or(PoolError::InsufficientPoolFunds {
+                requested: token_amount.saturating_to_u64(),
+                available: self.token_reserve.saturating_to_u64(),
+            })?;
+        let (k_native, k_token) = self.invariant;
+        let new_native_reserve =
+            R::mul_div(k_native, k_token, new_token_reserve).ok_or(PoolError::Overflow)?;
+        let native_in = new_native_reserve
+            .checked_sub(self.native_reserve)
+            .ok_or(PoolError::Overflow)?;
+        let fee = self.fee_on(native_in)?;
+        let native_amount = native_in.checked_add(fee).ok_or(PoolError::Overflow)?;
+        let new_native_reserve = new_native_reserve
+            .checked_add(fee)
+            .ok_or(PoolError::Overflow)?;
+        Ok((native_amount, (new_native_reserve, new_token_reserve)))
+    }
+
+    /// Returns the native received, net of the fee, and the reserves after selling.
+    fn project_sell(&self, token_amount: R) -> Result<(R, (R, R))> {
+        if token_amount == R::ZERO {
+            return Err(PoolError::InvalidAmount);
+        }
+        let new_token_reserve = self
+            .token_reserve
+            .checked_add(token_amount)
+            .ok_or(PoolError::Overflow)?;
+        let (k_native, k_token) = self.invariant;
+        let new_native_reserve =
+            R::mul_div(k_native, k_token, new_token_reserve).ok_or(PoolError::Overflow)?;
+        let native_out = self
+            .native_reserve
+            .checked_sub(new_native_reserve)
+            .ok_or(PoolError::Overflow)?;
+        let fee = self.fee_on(native_out)?;
+        let native_amount = native_out.checked_sub(fee).ok_or(PoolError::Overflow)?;
+        let new_native_reserve = new_native_reserve
+            .checked_add(fee)
+            .ok_or(PoolError::Overflow)?;
+        Ok((native_amount, (new_native_reserve, new_token_reserve)))
+    }
+
+    /// Fee charged on `native_amount` moving through the reserves, rounded up.
+    fn fee_on(&self, native_amount: R) -> Result<R> {
+        R::mul_div_ceil(
+            native_amount,
+            R::from_u64(self.fee_bps as u64),
+            R::from_u64(MAX_FEE_BPS as u64),
+        )
+        .ok_or(PoolError::Overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LiquidityPool;
+
+    #[test]
+    fn test_u64_matches_liquidity_pool() {
+        let mut wide = WidePool::<u64>::new(10u64.pow(12), 10u64.pow(15))
+            .unwrap()
+            .with_fee(30)
+            .unwrap();
+        let mut pool = LiquidityPool::new(10u64.pow(12), 10u64.pow(15))
+            .unwrap()
+            .with_fee(30)
+            .unwrap();
+        let token_amount = 10u64.pow(13);
+        for _ in 0..3 {
+            assert_eq!(wide.buy(token_amount, None), pool.buy(token_amount, None));
+            assert_eq!(
+                wide.sell(token_amount / 3, None),
+                pool.sell(token_amount / 3, None)
+            );
+        }
+        assert_eq!(wide.native_reserve(), pool.get_native_reserve());
+        assert_eq!(wide.token_reserve(), pool.get_token_reserve());
+        assert_eq!(
+            wide.invariant.0 as u128 * wide.invariant.1 as u128,
+            pool.get_constant_product()
+        );
+    }
+
+    #[test]
+    fn test_eighteen_decimals() {
+        // A billion tokens with 18 decimals against a thousand native units with 18 decimals.
+        let mut pool = WidePool::<u128>::new(10u128.pow(21), 10u128.pow(27)).unwrap();
+        let token_amount = 10u128.pow(26);
+        let spent = pool.buy(token_amount, None).unwrap();
+        // A tenth of the tokens costs a ninth of the native reserve.
+        assert_eq!(spent, 10u128.pow(21) / 9);
+        assert!((pool.spot_price() - 1e-6 / 0.81).abs() < 1e-15);
+        assert!(pool.sell(token_amount, None).unwrap() <= spent);
+        assert_eq!(
+            pool.buy(pool.token_reserve(), None),
+            Err(PoolError::InsufficientPoolFunds {
+                requested: u64::MAX,
+                available: u64::MAX
+            })
+        );
+        assert_eq!(WidePool::<u128>::new(0, 1), Err(PoolError::ZeroLiquidity));
+    }
+
+    #[cfg(feature = "u256")]
+    #[test]
+    fn test_u256_reserves() {
+        use primitive_types::U256;
+        let reserve = U256::from(u128::MAX) * 1_000;
+        let mut pool = WidePool::new(reserve, reserve).unwrap();
+        let spent = pool.buy(reserve / 2, None).unwrap();
+        assert_eq!(spent, reserve);
+        assert_eq!(pool.spot_price(), 4.0);
+        assert_eq!(pool.sell(reserve / 2, None), Ok(reserve));
+    }
+}